@@ -0,0 +1,94 @@
+use editor_protocol::*;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::process::ExitCode;
+
+/// Socket used when neither `--socket` nor `EDITOR_ADMIN_SOCKET` is given;
+/// matches the `admin_socket` in the readme's example server config.
+const DEFAULT_SOCKET: &str = "editor_server.sock";
+
+const USAGE: &str = "\
+Usage: editor_admin [--socket PATH] <command> [argument]
+
+Commands:
+  sessions               list connected and resumable sessions
+  kick <username>        disconnect a user and drop their sessions
+  broadcast <message>    show an announcement to every client
+  save                   write all unsaved documents to storage now
+  reload-config          re-read the server config file
+  lock <document>        reject edits to a document
+  unlock <document>      allow edits to a document again";
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut socket = std::env::var("EDITOR_ADMIN_SOCKET").unwrap_or_else(|_| DEFAULT_SOCKET.to_string());
+    if args.first().map(String::as_str) == Some("--socket") {
+        if args.len() < 2 {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+        socket = args.remove(1);
+        args.remove(0);
+    }
+
+    let Some(command) = args.first().and_then(|name| admin_command(name)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let request = format_command_with_arg(command, &args[1..].join(" "));
+
+    match send_request(&socket, request.trim_end()) {
+        Ok(reply) => match reply.split_first() {
+            Some((status, lines)) if status == ADMIN_OK => {
+                for line in lines {
+                    println!("{}", line);
+                }
+                ExitCode::SUCCESS
+            }
+            Some((status, _)) => {
+                let payload = status.strip_prefix(ERROR_MSG).unwrap_or(status).trim();
+                let (code, _, message) = parse_error(payload);
+                eprintln!("Error ({}): {}", code.as_str(), message);
+                ExitCode::FAILURE
+            }
+            None => {
+                eprintln!("Error: empty reply from the server");
+                ExitCode::FAILURE
+            }
+        },
+        Err(e) => {
+            eprintln!("Could not reach the server at {}: {}", socket, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Maps a CLI subcommand to its admin protocol command.
+fn admin_command(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sessions" => ADMIN_SESSIONS_CMD,
+        "kick" => ADMIN_KICK_CMD,
+        "broadcast" => ADMIN_BROADCAST_CMD,
+        "save" => ADMIN_SAVE_CMD,
+        "reload-config" => ADMIN_RELOAD_CONFIG_CMD,
+        "lock" => ADMIN_LOCK_CMD,
+        "unlock" => ADMIN_UNLOCK_CMD,
+        _ => return None,
+    })
+}
+
+/// Sends one command and returns the reply lines up to END_OF_MESSAGE.
+fn send_request(socket: &str, request: &str) -> std::io::Result<Vec<String>> {
+    let mut stream = UnixStream::connect(socket)?;
+    writeln!(stream, "{}", request)?;
+    stream.flush()?;
+    let mut reply = Vec::new();
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line == END_OF_MESSAGE_DELIMITER {
+            break;
+        }
+        reply.push(line);
+    }
+    Ok(reply)
+}
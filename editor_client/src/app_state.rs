@@ -1,17 +1,61 @@
+use crate::event::{RevisionEntry, SearchHit};
+use crate::highlight::SyntaxHighlighter;
+use crate::keymap::Keymap;
+use crate::offline_queue::OfflineQueue;
+use crate::requests::RequestTracker;
+use crate::search::{next_match, previous_match, SearchMatch, SearchState};
+use editor_protocol::diff::DiffLine;
+use editor_protocol::ops::LineEdit;
+use ratatui::layout::Direction;
 use ratatui::widgets::ListState;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Yeniden bağlanınca gönderilecek en fazla ara içerik sayısı; uzun bir çevrimdışı
+// oturum sunucunun mesaj hızı sınırına takılmasın diye düzenlemeler birleştirilir.
+const MAX_REPLAY_MESSAGES: usize = 20;
 
 #[derive(Debug, Default)]
 pub struct AppState {
+    #[allow(dead_code)] // Şimdilik arayüzde gösterilmiyor
     pub username: String,
     pub available_documents: Vec<String>,
     pub documents_list_state: ListState, // Belge listesindeki seçimi takip etmek için
     pub active_users: Vec<String>,
     // pub users_list_state: ListState, // Kullanıcı listesi için de gerekirse eklenebilir
-    pub current_document_name: Option<String>,
+    pub current_document_name: Option<String>, // Etkin sekmedeki belge
     pub current_document_content: Vec<String>, // İçeriği satır satır tutalım
+    pub tabs: Vec<DocumentTab>, // Açık belgeler, sekme sırasıyla (etkin olan dahil)
+    pub panes: Vec<Pane>, // İçerik alanı bölündüyse bölmeler; boşsa tek içerik paneli
+    pub focused_pane: usize, // Düzenlenen bölme; etkin sekmenin belgesini gösterir
+    pub split_direction: Direction, // Bölmeler yan yana (Horizontal) mı alt alta (Vertical) mı
+    pub synced_content: Vec<String>, // Sunucunun bildiği son içerik (yeniden bağlanınca uzlaştırma için)
+    pub connected: bool, // Sunucuyla oturum açık mı?
+    pub latency: Option<Duration>, // Son ölçülen sunucu gecikmesi
+    pub resync_pending: bool, // Yeniden bağlandıktan sonra aktif belgenin içeriği bekleniyor
+    pub offline_queue: OfflineQueue, // Bağlantı yokken yapılan, gönderilmeyi bekleyen düzenlemeler
+    pub requests: RequestTracker, // Sunucunun henüz yanıtlamadığı istekler
+    pub save_failed: bool, // Son belge güncellemesi reddedildi ya da yanıtsız kaldı
+    pub cursor_row: usize, // İçerik düzenleyicideki imleç satırı
+    pub cursor_col: usize, // İmleç sütunu (karakter cinsinden)
+    pub scroll_row: usize, // İçerik panelinde en üstte görünen satır
+    pub scroll_col: usize, // Uzun satırlarda soldan kaydırılan karakter sayısı
+    pub content_height: usize, // Son çizimde içerik panelinin kaç satır gösterebildiği
+    pub show_line_numbers: bool, // İçerik panelinde satır numarası sütunu
+    pub highlighter: SyntaxHighlighter, // Aktif belgenin sözdizimi renklendirme önbelleği
+    pub keymap: Keymap, // Tuş birleşimlerinden eylemlere eşleme (keymap.conf)
+    pub show_markdown_preview: bool, // İçerik panelinin yanında Markdown önizlemesi
     pub command_input: String,                 // Kullanıcının girdiği komut
     pub event_log: Vec<String>, // Sunucu olayları ve durum mesajları için
+    pub history: Option<HistoryView>, // Açıksa belge geçmişi paneli
+    pub diff_view: Option<DiffView>, // Açıksa içerik alanında gösterilen fark
+    pub pending_local_diff: Option<u64>, // Yerel tamponla karşılaştırılmak üzere istenen revizyon
+    pub show_blame: bool, // İçerik panelinde satır yazarları gösterilsin mi?
+    pub line_authors: Vec<String>, // Aktif belgenin satır başına yazarları
+    pub blame_due: Option<Instant>, // Ertelenmiş BLAME isteğinin gönderileceği an
+    pub search: Option<SearchState>, // Açıksa içerik panelindeki arama/değiştirme çubuğu
+    pub search_results: Option<SearchResultsView>, // Açıksa tüm belgelerde arama sonuçları paneli
+    pub pending_jump: Option<(String, usize)>, // Belge yüklenince imlecin gideceği satır (belge, satır)
     pub active_window: ActiveWindow, // Hangi pencerenin aktif olduğunu belirtir
     pub should_quit: bool,           // Uygulamadan çıkış yapılmalı mı?
 }
@@ -21,14 +65,17 @@ pub enum ActiveWindow {
     #[default]
     CommandInput,
     DocumentList,
-    // ContentView, // Eğer içerik alanı da doğrudan düzenlenebilir olacaksa
+    ContentView,
+    History,
+    Search,
+    SearchResults,
 }
 
 impl AppState {
     pub fn new(username: String) -> Self {
         let mut app_state = AppState {
             username,
-            event_log: vec!["Connecting...".to_string()],
+            event_log: vec![t!(Connecting)],
             ..Default::default()
         };
         app_state.documents_list_state.select(None); // Başlangıçta hiçbir belge seçili değil
@@ -42,6 +89,474 @@ impl AppState {
         }
     }
 
+    // TAB ile bir sonraki pencereye geç
+    pub fn focus_next_window(&mut self) {
+        self.active_window = match self.active_window {
+            ActiveWindow::CommandInput => ActiveWindow::DocumentList,
+            ActiveWindow::DocumentList => ActiveWindow::ContentView,
+            ActiveWindow::ContentView if self.history.is_some() => ActiveWindow::History,
+            ActiveWindow::ContentView | ActiveWindow::History if self.search_results.is_some() => ActiveWindow::SearchResults,
+            ActiveWindow::ContentView | ActiveWindow::History | ActiveWindow::Search | ActiveWindow::SearchResults => {
+                ActiveWindow::CommandInput
+            }
+        };
+    }
+
+    // İçerik değiştiğinde imleci geçerli bir konuma çek
+    pub fn clamp_cursor(&mut self) {
+        self.cursor_row = self.cursor_row.min(self.current_document_content.len().saturating_sub(1));
+        let line_len = self.current_document_content.get(self.cursor_row).map_or(0, |line| line.chars().count());
+        self.cursor_col = self.cursor_col.min(line_len);
+    }
+
+    // Görünen alanı imleci içerecek şekilde kaydırır
+    pub fn scroll_to_cursor(&mut self, height: usize, width: usize) {
+        if self.cursor_row < self.scroll_row {
+            self.scroll_row = self.cursor_row;
+        } else if height > 0 && self.cursor_row >= self.scroll_row + height {
+            self.scroll_row = self.cursor_row + 1 - height;
+        }
+        if self.cursor_col < self.scroll_col {
+            self.scroll_col = self.cursor_col;
+        } else if width > 0 && self.cursor_col >= self.scroll_col + width {
+            self.scroll_col = self.cursor_col + 1 - width;
+        }
+    }
+
+    // İmleci bir sayfa yukarı taşır; görünen alan imleci izler
+    pub fn page_up(&mut self) {
+        let page = self.content_height.max(1);
+        self.cursor_row = self.cursor_row.saturating_sub(page);
+        self.scroll_row = self.scroll_row.saturating_sub(page);
+        self.clamp_cursor();
+    }
+
+    pub fn page_down(&mut self) {
+        let page = self.content_height.max(1);
+        self.cursor_row += page;
+        self.scroll_row += page;
+        self.clamp_cursor();
+        // Son sayfada boş alan bırakma
+        let last_page_start = self.current_document_content.len().saturating_sub(page);
+        self.scroll_row = self.scroll_row.min(last_page_start);
+    }
+
+    // İmlecin bulunduğu satır; boş belgede ilk satırı oluşturur
+    fn cursor_line(&mut self) -> &mut String {
+        if self.current_document_content.is_empty() {
+            self.current_document_content.push(String::new());
+        }
+        self.clamp_cursor();
+        &mut self.current_document_content[self.cursor_row]
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let col = self.cursor_col;
+        let line = self.cursor_line();
+        let byte_index = char_to_byte_index(line, col);
+        line.insert(byte_index, c);
+        self.cursor_col += 1;
+    }
+
+    pub fn insert_newline(&mut self) {
+        let col = self.cursor_col;
+        let line = self.cursor_line();
+        let rest = line.split_off(char_to_byte_index(line, col));
+        self.current_document_content.insert(self.cursor_row + 1, rest);
+        self.cursor_row += 1;
+        self.cursor_col = 0;
+    }
+
+    // İmlecin solundaki karakteri siler; satır başındaysa önceki satırla birleştirir
+    pub fn delete_backward(&mut self) {
+        if self.cursor_col > 0 {
+            let col = self.cursor_col;
+            let line = self.cursor_line();
+            line.remove(char_to_byte_index(line, col - 1));
+            self.cursor_col -= 1;
+        } else if self.cursor_row > 0 && self.cursor_row < self.current_document_content.len() {
+            let line = self.current_document_content.remove(self.cursor_row);
+            self.cursor_row -= 1;
+            let previous = &mut self.current_document_content[self.cursor_row];
+            self.cursor_col = previous.chars().count();
+            previous.push_str(&line);
+        }
+    }
+
+    // İmlecin altındaki karakteri siler; satır sonundaysa sonraki satırı birleştirir
+    pub fn delete_forward(&mut self) {
+        let col = self.cursor_col;
+        let row = self.cursor_row;
+        let line = self.cursor_line();
+        if col < line.chars().count() {
+            line.remove(char_to_byte_index(line, col));
+        } else if row + 1 < self.current_document_content.len() {
+            let next = self.current_document_content.remove(row + 1);
+            self.current_document_content[row].push_str(&next);
+        }
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        if self.cursor_col > 0 {
+            self.cursor_col -= 1;
+        } else if self.cursor_row > 0 {
+            self.cursor_row -= 1;
+            self.cursor_col = usize::MAX;
+        }
+        self.clamp_cursor();
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        let line_len = self.current_document_content.get(self.cursor_row).map_or(0, |line| line.chars().count());
+        if self.cursor_col < line_len {
+            self.cursor_col += 1;
+        } else if self.cursor_row + 1 < self.current_document_content.len() {
+            self.cursor_row += 1;
+            self.cursor_col = 0;
+        }
+    }
+
+    pub fn move_cursor_up(&mut self) {
+        self.cursor_row = self.cursor_row.saturating_sub(1);
+        self.clamp_cursor();
+    }
+
+    pub fn move_cursor_down(&mut self) {
+        self.cursor_row += 1;
+        self.clamp_cursor();
+    }
+
+    // İmleci verilen satırda sorgu kelimelerinden ilkinin geçtiği yere taşır
+    pub fn jump_to_line(&mut self, row: usize, query: &str) {
+        self.cursor_row = row;
+        let line = self.current_document_content.get(row).map(|line| line.to_lowercase()).unwrap_or_default();
+        self.cursor_col = query
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .filter_map(|word| line.find(&word.to_lowercase()))
+            .min()
+            .map_or(0, |byte_index| line[..byte_index].chars().count());
+        self.clamp_cursor();
+    }
+
+    // Arama çubuğunu açar; imlecin o anki konumu aramanın başlangıç noktasıdır
+    pub fn open_search(&mut self) {
+        if self.search.is_none() {
+            self.search = Some(SearchState::new((self.cursor_row, self.cursor_col)));
+        }
+        self.active_window = ActiveWindow::Search;
+    }
+
+    pub fn close_search(&mut self) {
+        self.search = None;
+        self.active_window = ActiveWindow::ContentView;
+    }
+
+    pub fn search_matches(&self) -> Vec<SearchMatch> {
+        self.search.as_ref().map_or_else(Vec::new, |search| search.find_all(&self.current_document_content))
+    }
+
+    // Sorgu ya da seçenekler değişince imleci başlangıç noktasındaki ya da
+    // ondan sonraki ilk eşleşmeye taşır; eşleşme yoksa imleç başlangıca döner.
+    pub fn search_from_origin(&mut self) {
+        let Some(origin) = self.search.as_ref().map(|search| search.origin) else {
+            return;
+        };
+        let (row, col) = next_match(&self.search_matches(), origin, true).map_or(origin, |m| (m.row, m.start));
+        self.cursor_row = row;
+        self.cursor_col = col;
+        self.clamp_cursor();
+    }
+
+    pub fn search_next(&mut self) {
+        if let Some(found) = next_match(&self.search_matches(), (self.cursor_row, self.cursor_col), false) {
+            self.cursor_row = found.row;
+            self.cursor_col = found.start;
+        }
+    }
+
+    pub fn search_previous(&mut self) {
+        if let Some(found) = previous_match(&self.search_matches(), (self.cursor_row, self.cursor_col)) {
+            self.cursor_row = found.row;
+            self.cursor_col = found.start;
+        }
+    }
+
+    // İmleç bir eşleşmenin başındaysa onu değiştirip sonrakine geçer, değilse
+    // yalnızca sonraki eşleşmeye gider. İçerik değiştiyse true döner.
+    pub fn replace_current_match(&mut self) -> bool {
+        let cursor = (self.cursor_row, self.cursor_col);
+        let Some(search) = self.search.as_ref() else {
+            return false;
+        };
+        let current = search.find_all(&self.current_document_content).into_iter().find(|m| (m.row, m.start) == cursor);
+        let Some(found) = current else {
+            self.search_next();
+            return false;
+        };
+        let Some(end) = search.replace_one(&mut self.current_document_content, found) else {
+            return false;
+        };
+        // Boş bir eşleşme boş metinle değiştirildiyse aynı yerde takılı kalma
+        let inclusive = end > found.start;
+        let matches = self.search_matches();
+        let (row, col) = next_match(&matches, (found.row, end), inclusive).map_or((found.row, end), |m| (m.row, m.start));
+        self.cursor_row = row;
+        self.cursor_col = col;
+        self.clamp_cursor();
+        true
+    }
+
+    // Tüm eşleşmeleri tek seferde değiştirir ve sayısını döndürür
+    pub fn replace_all_matches(&mut self) -> usize {
+        let Some(search) = self.search.as_ref() else {
+            return 0;
+        };
+        let count = search.replace_all(&mut self.current_document_content);
+        self.clamp_cursor();
+        count
+    }
+
+    // Yeniden bağlandıktan sonra gelen sunucu içeriği üzerine bağlantı yokken
+    // yapılan düzenlemeleri sırayla taşır ve sunucuya sırayla gönderilecek
+    // ara içerikleri döndürür. Bir düzenleme sunucudaki değişikliklerle aynı
+    // satırlara dokunuyorsa yerel sürüm tek bir düzenleme olarak gönderilir.
+    pub fn replay_offline_edits(&mut self, server_content: Vec<String>) -> Vec<Vec<String>> {
+        let pending = self.offline_queue.take().unwrap_or_else(|e| {
+            self.add_event_log(t!(OfflineReadFailed, e));
+            None
+        });
+        self.synced_content = server_content.clone();
+        let Some(pending) = pending.filter(|p| self.current_document_name.as_ref() == Some(&p.document)) else {
+            self.current_document_content = server_content;
+            self.clamp_cursor();
+            return Vec::new();
+        };
+
+        let mut remote_edit = LineEdit::between(&pending.base, &server_content);
+        let mut lines = server_content;
+        let mut snapshots = Vec::new();
+        let mut conflict = false;
+        for edit in &pending.edits {
+            let rebased = match &remote_edit {
+                Some(remote) => edit.transform(remote),
+                None => Some(edit.clone()),
+            };
+            if rebased.is_none_or(|rebased| rebased.apply(&mut lines).is_err()) {
+                conflict = true;
+                break;
+            }
+            snapshots.push(lines.clone());
+            if let Some(remote) = remote_edit.take() {
+                // Sunucu değişikliğini bir sonraki yerel düzenlemenin koordinatlarına taşı
+                remote_edit = remote.transform(edit);
+                conflict = remote_edit.is_none();
+                if conflict {
+                    break;
+                }
+            }
+        }
+
+        if conflict {
+            self.add_event_log(t!(OfflineConflict));
+            let mut local_lines = pending.base;
+            for edit in &pending.edits {
+                let _ = edit.apply(&mut local_lines);
+            }
+            snapshots = vec![local_lines];
+        } else {
+            self.add_event_log(t!(ReplayingOfflineEdits, pending.edits.len()));
+            // Sırayı koruyarak eşit aralıklı ara içerikleri ve her zaman sonuncuyu tut
+            let step = snapshots.len().div_ceil(MAX_REPLAY_MESSAGES);
+            let count = snapshots.len();
+            snapshots = snapshots
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| (count - 1 - i) % step == 0)
+                .map(|(_, snapshot)| snapshot)
+                .collect();
+        }
+        if let Some(last) = snapshots.last() {
+            self.current_document_content = last.clone();
+        }
+        self.clamp_cursor();
+        snapshots
+    }
+
+    pub fn active_tab_index(&self) -> Option<usize> {
+        let name = self.current_document_name.as_ref()?;
+        self.tabs.iter().position(|tab| &tab.name == name)
+    }
+
+    // Etkin sekmenin bir sonraki (`forward` değilse önceki) sekmesi; başa/sona döner
+    pub fn adjacent_tab_index(&self, forward: bool) -> Option<usize> {
+        let count = self.tabs.len();
+        let index = self.active_tab_index().filter(|_| count > 1)?;
+        Some(if forward { (index + 1) % count } else { (index + count - 1) % count })
+    }
+
+    // Arka plandaki bir sekme; etkin belge ve açık olmayan belgeler için None
+    pub fn background_tab_mut(&mut self, name: &str) -> Option<&mut DocumentTab> {
+        if self.current_document_name.as_deref() == Some(name) {
+            return None;
+        }
+        self.tabs.iter_mut().find(|tab| tab.name == name)
+    }
+
+    // Sunucudan yeni açılan belge için sekme ekler ve onu etkin yapar; belge
+    // zaten açıksa yalnızca o sekmeye geçer.
+    pub fn open_tab(&mut self, name: &str) {
+        if let Some(index) = self.tabs.iter().position(|tab| tab.name == name) {
+            self.activate_tab(index);
+            return;
+        }
+        self.stash_active_tab();
+        self.tabs.push(DocumentTab { name: name.to_string(), ..Default::default() });
+        self.load_tab(self.tabs.len() - 1);
+    }
+
+    // Verilen sekmeye geçer; zaten etkinse ya da yoksa false
+    pub fn activate_tab(&mut self, index: usize) -> bool {
+        if index >= self.tabs.len() || self.active_tab_index() == Some(index) {
+            return false;
+        }
+        self.stash_active_tab();
+        self.load_tab(index);
+        true
+    }
+
+    // Etkin sekmeyi kapatır ve yerine gelen sekmeye geçer; kapatılan belgenin adını döndürür
+    pub fn close_active_tab(&mut self) -> Option<String> {
+        let index = self.active_tab_index()?;
+        let closed = self.tabs.remove(index);
+        let next = (!self.tabs.is_empty()).then(|| self.tabs[index.min(self.tabs.len() - 1)].name.clone());
+        // Kapanan belgeyi gösteren diğer bölmeler yerine gelen belgeyi gösterir
+        for pane in self.panes.iter_mut().filter(|pane| pane.document.as_ref() == Some(&closed.name)) {
+            pane.document = next.clone();
+            pane.cursor = (0, 0);
+            pane.scroll = (0, 0);
+        }
+        self.current_document_name = None;
+        self.current_document_content.clear();
+        self.synced_content.clear();
+        self.line_authors.clear();
+        (self.cursor_row, self.cursor_col, self.scroll_row, self.scroll_col) = (0, 0, 0, 0);
+        if !self.tabs.is_empty() {
+            self.load_tab(index.min(self.tabs.len() - 1));
+        }
+        Some(closed.name)
+    }
+
+    // Etkin belgenin tamponunu, imlecini ve kaydırmasını sekmesine kaydeder
+    fn stash_active_tab(&mut self) {
+        let Some(index) = self.active_tab_index() else {
+            return;
+        };
+        let tab = &mut self.tabs[index];
+        tab.content = std::mem::take(&mut self.current_document_content);
+        tab.synced_content = std::mem::take(&mut self.synced_content);
+        tab.line_authors = std::mem::take(&mut self.line_authors);
+        tab.cursor = (self.cursor_row, self.cursor_col);
+        tab.scroll = (self.scroll_row, self.scroll_col);
+    }
+
+    fn load_tab(&mut self, index: usize) {
+        let tab = &mut self.tabs[index];
+        self.current_document_name = Some(tab.name.clone());
+        self.current_document_content = std::mem::take(&mut tab.content);
+        self.synced_content = std::mem::take(&mut tab.synced_content);
+        self.line_authors = std::mem::take(&mut tab.line_authors);
+        (self.cursor_row, self.cursor_col) = tab.cursor;
+        (self.scroll_row, self.scroll_col) = tab.scroll;
+        self.clamp_cursor();
+        // Geçmiş paneli yalnızca kendi belgesi etkinken anlamlı
+        if self.history.as_ref().is_some_and(|history| history.document_name != self.tabs[index].name) {
+            self.history = None;
+            if self.active_window == ActiveWindow::History {
+                self.active_window = ActiveWindow::CommandInput;
+            }
+        }
+    }
+
+    // Odaklı bölmeyi ikiye böler; yeni bölme aynı belgeyi aynı konumda gösterir
+    // ve odağı alır.
+    pub fn split_pane(&mut self, direction: Direction) {
+        if self.panes.is_empty() {
+            self.panes.push(Pane::default());
+            self.focused_pane = 0;
+        }
+        self.split_direction = direction;
+        self.stash_focused_pane();
+        self.focused_pane += 1;
+        self.panes.insert(self.focused_pane, Pane::default());
+    }
+
+    // Odaklı bölmeyi kapatır; tek bölme kalınca içerik alanı bölünmez olur
+    pub fn close_pane(&mut self) -> bool {
+        if self.panes.is_empty() {
+            return false;
+        }
+        self.panes.remove(self.focused_pane);
+        let next = self.focused_pane.min(self.panes.len() - 1);
+        self.load_pane(next);
+        if self.panes.len() == 1 {
+            self.panes.clear();
+            self.focused_pane = 0;
+        }
+        true
+    }
+
+    // Odaklı bölmenin bir sonraki (`forward` değilse önceki) bölmesi
+    pub fn adjacent_pane_index(&self, forward: bool) -> Option<usize> {
+        if self.panes.len() < 2 {
+            return None;
+        }
+        let count = self.panes.len();
+        Some(if forward { (self.focused_pane + 1) % count } else { (self.focused_pane + count - 1) % count })
+    }
+
+    pub fn focus_pane(&mut self, index: usize) -> bool {
+        if index >= self.panes.len() || index == self.focused_pane {
+            return false;
+        }
+        self.stash_focused_pane();
+        self.load_pane(index);
+        true
+    }
+
+    // Odaklı bölmenin belgesini, imlecini ve kaydırmasını kaydeder; sözdizimi
+    // önbelleği de bölmeyle birlikte kalır.
+    fn stash_focused_pane(&mut self) {
+        let pane = &mut self.panes[self.focused_pane];
+        pane.document = self.current_document_name.clone();
+        pane.cursor = (self.cursor_row, self.cursor_col);
+        pane.scroll = (self.scroll_row, self.scroll_col);
+        pane.highlighter = std::mem::take(&mut self.highlighter);
+    }
+
+    // Bölmeye odaklanır: belgesi başka bir sekmedeyse o sekmeye geçilir
+    fn load_pane(&mut self, index: usize) {
+        self.focused_pane = index;
+        let pane = &mut self.panes[index];
+        self.highlighter = std::mem::take(&mut pane.highlighter);
+        let (document, cursor, scroll) = (pane.document.clone(), pane.cursor, pane.scroll);
+        if let Some(tab_index) = document.and_then(|name| self.tabs.iter().position(|tab| tab.name == name)) {
+            self.activate_tab(tab_index);
+        }
+        (self.cursor_row, self.cursor_col) = cursor;
+        (self.scroll_row, self.scroll_col) = scroll;
+        self.clamp_cursor();
+    }
+
+    // Bir bölmenin gösterdiği satırlar: etkin belgeninki ya da arka plandaki sekmeninki
+    pub fn pane_lines(&self, document: Option<&str>) -> &[String] {
+        match document {
+            Some(name) if self.current_document_name.as_deref() == Some(name) => &self.current_document_content,
+            Some(name) => self.tabs.iter().find(|tab| tab.name == name).map_or(&[], |tab| &tab.content),
+            None => &[],
+        }
+    }
+
     pub fn select_next_document(&mut self) {
         if self.available_documents.is_empty() {
             self.documents_list_state.select(None);
@@ -79,4 +594,354 @@ impl AppState {
     }
 }
 
-pub type SharedAppState = Arc<Mutex<AppState>>;
\ No newline at end of file
+// Açık bir belgenin sekmesi. Etkin sekmenin tamponu, imleci ve kaydırması
+// AppState'in current_document_* alanlarındadır; buradaki alanlar yalnızca
+// sekme arka plandayken dolu.
+#[derive(Debug, Default)]
+pub struct DocumentTab {
+    pub name: String,
+    pub content: Vec<String>,
+    pub synced_content: Vec<String>, // Sunucunun bildiği son içerik
+    pub cursor: (usize, usize), // (satır, sütun)
+    pub scroll: (usize, usize),
+    pub line_authors: Vec<String>,
+}
+
+// Bölünmüş içerik alanındaki bir bölme. Odaklı bölmenin durumu AppState'in
+// imleç ve kaydırma alanlarındadır; buradaki kopya odak başka bölmeye geçince
+// güncellenir.
+#[derive(Debug, Default)]
+pub struct Pane {
+    pub document: Option<String>,
+    pub cursor: (usize, usize),
+    pub scroll: (usize, usize),
+    pub highlighter: SyntaxHighlighter,
+}
+
+// Geçmiş panelinin durumu
+#[derive(Debug, Default)]
+pub struct HistoryView {
+    pub document_name: String,
+    pub revisions: Vec<RevisionEntry>, // En yeni revizyon başta
+    pub list_state: ListState,
+    pub preview: Option<(u64, Vec<String>)>, // Önizlenen revizyon ve satırları
+    pub preview_scroll: usize, // Önizlemede en üstte görünen satır
+}
+
+impl HistoryView {
+    pub fn new(document_name: String, mut revisions: Vec<RevisionEntry>) -> Self {
+        revisions.reverse();
+        let mut list_state = ListState::default();
+        if !revisions.is_empty() {
+            list_state.select(Some(0));
+        }
+        HistoryView { document_name, revisions, list_state, preview: None, preview_scroll: 0 }
+    }
+
+    pub fn selected_revision(&self) -> Option<u64> {
+        self.list_state.selected().and_then(|i| self.revisions.get(i)).map(|r| r.number)
+    }
+
+    pub fn select_next(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            if i + 1 < self.revisions.len() {
+                self.list_state.select(Some(i + 1));
+            }
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            self.list_state.select(Some(i.saturating_sub(1)));
+        }
+    }
+}
+
+// Tüm belgelerde yapılan aramanın sonuç paneli
+#[derive(Debug, Default)]
+pub struct SearchResultsView {
+    pub query: String,
+    pub hits: Vec<SearchHit>,
+    pub list_state: ListState,
+}
+
+impl SearchResultsView {
+    pub fn new(query: String, hits: Vec<SearchHit>) -> Self {
+        let mut list_state = ListState::default();
+        if !hits.is_empty() {
+            list_state.select(Some(0));
+        }
+        SearchResultsView { query, hits, list_state }
+    }
+
+    pub fn selected_hit(&self) -> Option<&SearchHit> {
+        self.list_state.selected().and_then(|i| self.hits.get(i))
+    }
+
+    pub fn select_next(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            if i + 1 < self.hits.len() {
+                self.list_state.select(Some(i + 1));
+            }
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if let Some(i) = self.list_state.selected() {
+            self.list_state.select(Some(i.saturating_sub(1)));
+        }
+    }
+}
+
+// İçerik alanında gösterilen birleşik (unified) fark
+#[derive(Debug)]
+pub struct DiffView {
+    pub title: String,
+    pub lines: Vec<DiffLine>,
+}
+
+fn char_to_byte_index(line: &str, char_index: usize) -> usize {
+    line.char_indices().nth(char_index).map_or(line.len(), |(i, _)| i)
+}
+
+pub type SharedAppState = Arc<Mutex<AppState>>;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn app_with_document(name: &str) -> AppState {
+        let mut app = AppState::new("ayse".to_string());
+        app.current_document_name = Some(name.to_string());
+        app
+    }
+
+    #[test]
+    fn offline_edits_are_replayed_over_unrelated_server_changes() {
+        let mut app = app_with_document("a.txt");
+        let base = lines(&["a", "b", "c"]);
+        app.offline_queue.push("a.txt", &base, &lines(&["A", "b", "c"])).unwrap();
+        app.offline_queue.push("a.txt", &base, &lines(&["A", "b", "c", "d"])).unwrap();
+
+        let snapshots = app.replay_offline_edits(lines(&["a", "b", "C"]));
+        assert_eq!(snapshots, [lines(&["A", "b", "C"]), lines(&["A", "b", "C", "d"])]);
+        assert_eq!(app.current_document_content, lines(&["A", "b", "C", "d"]));
+        assert_eq!(app.synced_content, lines(&["a", "b", "C"]));
+        assert!(app.offline_queue.is_empty());
+    }
+
+    #[test]
+    fn conflicting_offline_edits_are_sent_as_the_local_content() {
+        let mut app = app_with_document("a.txt");
+        let base = lines(&["a", "b"]);
+        app.offline_queue.push("a.txt", &base, &lines(&["A", "b"])).unwrap();
+        app.offline_queue.push("a.txt", &base, &lines(&["A", "B"])).unwrap();
+
+        let snapshots = app.replay_offline_edits(lines(&["x", "b"]));
+        assert_eq!(snapshots, [lines(&["A", "B"])]);
+        assert_eq!(app.current_document_content, lines(&["A", "B"]));
+        assert_eq!(app.event_log.last(), Some(&t!(OfflineConflict)));
+    }
+
+    #[test]
+    fn many_offline_edits_are_coalesced_into_fewer_messages() {
+        let mut app = app_with_document("a.txt");
+        let base = lines(&[""]);
+        let mut content = String::new();
+        for c in "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz".chars() {
+            content.push(c);
+            app.offline_queue.push("a.txt", &base, &[content.clone()]).unwrap();
+        }
+
+        let snapshots = app.replay_offline_edits(base);
+        assert!(snapshots.len() <= MAX_REPLAY_MESSAGES);
+        assert_eq!(snapshots.last(), Some(&vec![content.clone()]));
+        // Ara içerikler sırayı korur
+        assert!(snapshots.windows(2).all(|pair| pair[0][0].len() < pair[1][0].len()));
+        assert_eq!(app.current_document_content, [content]);
+    }
+
+    #[test]
+    fn offline_edits_of_another_document_are_not_replayed() {
+        let mut app = app_with_document("b.txt");
+        let base = lines(&["a"]);
+        app.offline_queue.push("a.txt", &base, &lines(&["A"])).unwrap();
+
+        assert!(app.replay_offline_edits(lines(&["b"])).is_empty());
+        assert_eq!(app.current_document_content, lines(&["b"]));
+    }
+
+    fn hit(document: &str, line: usize) -> SearchHit {
+        SearchHit { document: document.to_string(), line, snippet: String::new() }
+    }
+
+    #[test]
+    fn search_results_selection_stays_within_the_hits() {
+        let mut results = SearchResultsView::new("not".to_string(), vec![hit("a.txt", 1), hit("b.txt", 4)]);
+        assert_eq!(results.selected_hit().map(|hit| hit.line), Some(1));
+        results.select_next();
+        results.select_next();
+        assert_eq!(results.selected_hit().map(|hit| hit.document.as_str()), Some("b.txt"));
+        results.select_previous();
+        results.select_previous();
+        assert_eq!(results.selected_hit().map(|hit| hit.document.as_str()), Some("a.txt"));
+        assert!(SearchResultsView::new("yok".to_string(), Vec::new()).selected_hit().is_none());
+    }
+
+    #[test]
+    fn jumping_to_a_hit_puts_the_cursor_on_the_first_query_word() {
+        let mut app = app_with_document("a.txt");
+        app.current_document_content = lines(&["başlık", "Çay ve KAHVE notları"]);
+        app.jump_to_line(1, "kahve çay");
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 0));
+        app.jump_to_line(1, "notları");
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 13));
+        // Satırda geçmeyen bir sorguda satırın başına gidilir
+        app.jump_to_line(0, "kahve");
+        assert_eq!((app.cursor_row, app.cursor_col), (0, 0));
+        // Belge kısaldıysa son satırda kalınır
+        app.jump_to_line(9, "kahve");
+        assert_eq!(app.cursor_row, 1);
+    }
+
+    fn numbered_lines(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("satır {}", i)).collect()
+    }
+
+    #[test]
+    fn scroll_follows_the_cursor_when_content_shrinks() {
+        let mut app = app_with_document("a.txt");
+        app.current_document_content = numbered_lines(100);
+        app.cursor_row = 90;
+        app.cursor_col = 8;
+        app.scroll_to_cursor(10, 5);
+        assert_eq!((app.scroll_row, app.scroll_col), (81, 4));
+
+        app.current_document_content = lines(&["ab", "c"]);
+        app.clamp_cursor();
+        app.scroll_to_cursor(10, 5);
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 1));
+        assert_eq!((app.scroll_row, app.scroll_col), (1, 1));
+
+        app.current_document_content.clear();
+        app.clamp_cursor();
+        app.scroll_to_cursor(10, 5);
+        assert_eq!((app.cursor_row, app.cursor_col, app.scroll_row, app.scroll_col), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn paging_stays_within_the_content() {
+        let mut app = app_with_document("a.txt");
+        app.current_document_content = numbered_lines(25);
+        app.content_height = 10;
+        app.page_down();
+        app.page_down();
+        app.page_down();
+        assert_eq!((app.cursor_row, app.scroll_row), (24, 15));
+        app.page_up();
+        app.page_up();
+        app.page_up();
+        assert_eq!((app.cursor_row, app.scroll_row), (0, 0));
+    }
+
+    // Sırayla açılan sekmeler; her birinin içeriği tek satır olarak belgenin adıdır
+    fn app_with_tabs(names: &[&str]) -> AppState {
+        let mut app = AppState::new("ayse".to_string());
+        for name in names {
+            app.open_tab(name);
+            app.current_document_content = lines(&[name]);
+        }
+        app
+    }
+
+    #[test]
+    fn switching_tabs_keeps_each_buffer_and_cursor() {
+        let mut app = app_with_tabs(&["a.txt", "b.txt"]);
+        app.cursor_col = 3;
+        assert!(app.activate_tab(0));
+        assert_eq!(app.current_document_name.as_deref(), Some("a.txt"));
+        assert_eq!(app.current_document_content, ["a.txt"]);
+        assert!(!app.activate_tab(0));
+        app.open_tab("b.txt");
+        assert_eq!(app.tabs.len(), 2);
+        assert_eq!(app.current_document_content, ["b.txt"]);
+        assert_eq!(app.cursor_col, 3);
+    }
+
+    #[test]
+    fn closing_the_active_tab_activates_the_next_one() {
+        let mut app = app_with_tabs(&["a.txt", "b.txt", "c.txt"]);
+        app.activate_tab(1);
+        assert_eq!(app.close_active_tab().as_deref(), Some("b.txt"));
+        assert_eq!(app.current_document_name.as_deref(), Some("c.txt"));
+        assert_eq!(app.current_document_content, ["c.txt"]);
+        // En sağdaki sekme kapanınca soldaki etkin olur
+        assert_eq!(app.close_active_tab().as_deref(), Some("c.txt"));
+        assert_eq!(app.current_document_name.as_deref(), Some("a.txt"));
+        assert_eq!(app.current_document_content, ["a.txt"]);
+        assert_eq!(app.tabs.iter().map(|tab| tab.name.as_str()).collect::<Vec<_>>(), ["a.txt"]);
+    }
+
+    #[test]
+    fn closing_the_last_tab_leaves_no_document_open() {
+        let mut app = app_with_tabs(&["a.txt"]);
+        app.cursor_col = 4;
+        assert_eq!(app.close_active_tab().as_deref(), Some("a.txt"));
+        assert!(app.tabs.is_empty());
+        assert_eq!(app.current_document_name, None);
+        assert!(app.current_document_content.is_empty());
+        assert_eq!((app.cursor_row, app.cursor_col), (0, 0));
+        assert_eq!(app.close_active_tab(), None);
+    }
+
+    #[test]
+    fn panes_remember_their_document_and_cursor() {
+        let mut app = app_with_tabs(&["a.txt", "b.txt"]);
+        app.activate_tab(0);
+        app.cursor_col = 2;
+        app.split_pane(Direction::Horizontal);
+        assert_eq!((app.panes.len(), app.focused_pane), (2, 1));
+        app.open_tab("b.txt");
+        app.cursor_col = 5;
+
+        assert!(app.focus_pane(0));
+        assert_eq!(app.current_document_name.as_deref(), Some("a.txt"));
+        assert_eq!(app.cursor_col, 2);
+        assert_eq!(app.pane_lines(app.panes[1].document.as_deref()), ["b.txt"]);
+        assert_eq!(app.adjacent_pane_index(false), Some(1));
+        assert!(app.focus_pane(1));
+        assert_eq!(app.current_document_name.as_deref(), Some("b.txt"));
+        assert_eq!(app.cursor_col, 5);
+        assert_eq!(app.adjacent_pane_index(true), Some(0));
+    }
+
+    #[test]
+    fn closing_the_second_to_last_pane_unsplits_the_view() {
+        let mut app = app_with_tabs(&["a.txt"]);
+        assert!(!app.close_pane());
+        app.split_pane(Direction::Vertical);
+        app.split_pane(Direction::Vertical);
+        assert_eq!(app.panes.len(), 3);
+        assert!(app.close_pane());
+        assert_eq!((app.panes.len(), app.focused_pane), (2, 1));
+        assert!(app.close_pane());
+        assert!(app.panes.is_empty());
+        assert_eq!(app.adjacent_pane_index(true), None);
+        assert_eq!(app.current_document_name.as_deref(), Some("a.txt"));
+    }
+
+    #[test]
+    fn panes_showing_a_closed_tab_move_to_the_next_document() {
+        let mut app = app_with_tabs(&["a.txt", "b.txt"]);
+        app.cursor_col = 3;
+        app.split_pane(Direction::Horizontal);
+        app.focus_pane(0);
+        assert_eq!(app.close_active_tab().as_deref(), Some("b.txt"));
+        assert_eq!(app.current_document_name.as_deref(), Some("a.txt"));
+        assert_eq!(app.panes[1].document.as_deref(), Some("a.txt"));
+        assert_eq!(app.panes[1].cursor, (0, 0));
+    }
+}
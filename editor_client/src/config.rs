@@ -0,0 +1,101 @@
+use editor_protocol::locale::Locale;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Config file read at startup unless `EDITOR_CLIENT_CONFIG` points elsewhere.
+const DEFAULT_CONFIG_PATH: &str = "client.conf";
+
+/// Key bindings read at startup unless `keymap_path` points elsewhere.
+const DEFAULT_KEYMAP_PATH: &str = "keymap.conf";
+
+/// Client settings, loaded from a simple `key = value` file.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// File that keeps edits made while offline, so they survive a restart.
+    pub journal_path: Option<PathBuf>,
+    /// How often a `PING` is sent to the server.
+    pub keepalive_interval: Duration,
+    /// Silence after which the connection is considered dead and re-established.
+    pub keepalive_timeout: Duration,
+    /// How long to wait for the server to acknowledge a request.
+    pub request_timeout: Duration,
+    /// UI language; `LANG` decides when unset.
+    pub language: Option<Locale>,
+    /// Show a line-number gutter in the content panel (toggled with `NUMBERS`).
+    pub line_numbers: bool,
+    /// File that rebinds keys to editor actions (see `keymap.rs`).
+    pub keymap_path: PathBuf,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            journal_path: None,
+            keepalive_interval: Duration::from_secs(5),
+            keepalive_timeout: Duration::from_secs(15),
+            request_timeout: Duration::from_secs(10),
+            language: None,
+            line_numbers: true,
+            keymap_path: PathBuf::from(DEFAULT_KEYMAP_PATH),
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Loads the config file, falling back to defaults for anything missing or invalid.
+    pub fn load() -> Self {
+        let path = std::env::var("EDITOR_CLIENT_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+        let mut config = ClientConfig::default();
+        if let Ok(contents) = fs::read_to_string(&path) {
+            config.apply(&contents);
+            println!("Loaded config: {}", path);
+        }
+        config
+    }
+
+    fn apply(&mut self, contents: &str) {
+        for (line_no, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                eprintln!("Config line {}: expected `key = value`", line_no + 1);
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if let Err(e) = self.set(key, value) {
+                eprintln!("Config line {}: {}", line_no + 1, e);
+            }
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "journal_path" => self.journal_path = Some(PathBuf::from(value)).filter(|_| !value.is_empty()),
+            "keepalive_interval_ms" => self.keepalive_interval = Duration::from_millis(parse_number(key, value)?),
+            "keepalive_timeout_ms" => self.keepalive_timeout = Duration::from_millis(parse_number(key, value)?),
+            "request_timeout_ms" => self.request_timeout = Duration::from_millis(parse_number(key, value)?),
+            "language" => {
+                self.language = Some(Locale::parse(value).ok_or_else(|| format!("unsupported language '{}'", value))?)
+            }
+            "line_numbers" => self.line_numbers = parse_bool(key, value)?,
+            "keymap_path" => self.keymap_path = PathBuf::from(value),
+            _ => return Err(format!("unknown key '{}'", key)),
+        }
+        Ok(())
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("invalid boolean for '{}': {}", key, value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid number for '{}': {}", key, value))
+}
@@ -1,4 +1,7 @@
 use crossterm::event::KeyEvent;
+use editor_protocol::diff::DiffLine;
+use editor_protocol::ErrorCode;
+use std::time::Duration;
 // use std::sync::mpsc; // Bu satırı kaldırın veya yorum satırı yapın
 
 // Uygulama içinde dolaşacak olay türleri
@@ -15,9 +18,38 @@ pub enum ServerCommand {
     NewDocumentAvailable(String),
     UserJoined(String),
     UserLeft(String),
+    UserSwitched { username: String, document: String }, // Başka bir kullanıcı bir belgeye geçti
     ReceiveDocumentContent { name: String, content: String },
     UpdateDocumentContent { name: String, content: String },
     SwitchedToDocument { name: String }, 
+    HistoryList { name: String, revisions: Vec<RevisionEntry> },
+    RevisionContent { name: String, number: u64, content: String },
+    DiffResult { name: String, rev_a: String, rev_b: String, lines: Vec<DiffLine> },
+    BlameInfo { name: String, authors: Vec<String> },
+    SearchResults { query: String, hits: Vec<SearchHit> },
+    Connected, // CONNECTED_OK (ilk bağlantı veya yeniden bağlanma)
+    ConnectionLost(String), // Bağlantı koptu; dinleyici yeniden bağlanmayı deniyor
+    Latency(Duration), // PING/PONG ile ölçülen gidiş-dönüş süresi
+    ServerShuttingDown(String), // Sunucu kapanıyor; neden
+    Kicked(String), // Yönetici bağlantıyı kesti; yeniden bağlanılmaz
+    Announcement(String), // Yöneticinin tüm istemcilere duyurusu
     Status(String),
-    Error(String),
-}
\ No newline at end of file
+    Ack(String), // ACK <istek kimliği>
+    Error { code: ErrorCode, request_id: Option<String>, message: String }, // ERROR <kod> <istek> <mesaj>
+}
+
+// Sunucu tarafı aramada eşleşen bir satır
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub document: String,
+    pub line: usize, // 1'den başlar
+    pub snippet: String,
+}
+
+// Belge geçmişindeki bir revizyonun özeti
+#[derive(Debug, Clone)]
+pub struct RevisionEntry {
+    pub number: u64,
+    pub timestamp: u64,
+    pub author: String,
+}
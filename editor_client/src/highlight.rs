@@ -0,0 +1,170 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use std::fmt;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, HighlightState, Theme, ThemeSet};
+use syntect::parsing::{ParseState, SyntaxSet};
+
+// Renk teması; syntect'in gömülü temalarından
+const THEME_NAME: &str = "base16-ocean.dark";
+
+static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+// Dil tanımları ikiliye gömülüdür (TOML gibi syntect'te olmayanlar dahil);
+// ilk kullanımda bir kez yüklenir.
+pub fn syntaxes() -> &'static SyntaxSet {
+    SYNTAXES.get_or_init(two_face::syntax::extra_no_newlines)
+}
+
+fn theme() -> &'static Theme {
+    THEME.get_or_init(|| ThemeSet::load_defaults().themes.remove(THEME_NAME).unwrap_or_default())
+}
+
+// Aktif belgenin renklendirme önbelleği. Her satırın başındaki ayrıştırıcı
+// durumu saklanır; içerik değişince yalnızca ilk değişen satırdan itibaren,
+// o da ekranda görünen son satıra kadar yeniden renklendirilir.
+#[derive(Default)]
+pub struct SyntaxHighlighter {
+    document: Option<String>,
+    syntax: Option<String>, // Uzantıya karşılık gelen dil; None ise düz metin
+    lines: Vec<String>,
+    states: Vec<(HighlightState, ParseState)>, // `lines[i]` başlamadan önceki durum
+    styled: Vec<Vec<Span<'static>>>,
+}
+
+impl fmt::Debug for SyntaxHighlighter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyntaxHighlighter")
+            .field("document", &self.document)
+            .field("syntax", &self.syntax)
+            .field("cached_lines", &self.styled.len())
+            .finish()
+    }
+}
+
+impl SyntaxHighlighter {
+    // `lines[..end]` için renklendirilmiş satırlar; belgenin uzantısı bilinen
+    // bir dile karşılık gelmiyorsa None (düz metin gösterilir).
+    pub fn highlight(&mut self, document: &str, lines: &[String], end: usize) -> Option<&[Vec<Span<'static>>]> {
+        if self.document.as_deref() != Some(document) {
+            *self = SyntaxHighlighter { document: Some(document.to_string()), syntax: syntax_name(document), ..Default::default() };
+        }
+        let syntax = syntaxes().find_syntax_by_name(self.syntax.as_deref()?)?;
+
+        // Önbellek ilk farklı satıra kadar geçerli
+        let unchanged = self.lines.iter().zip(lines).take_while(|(cached, line)| cached == line).count();
+        self.lines.truncate(unchanged);
+        self.styled.truncate(unchanged);
+        self.states.truncate(unchanged);
+
+        let end = end.min(lines.len());
+        if self.styled.len() < end {
+            let mut highlighter = match self.states.last() {
+                Some((highlight_state, parse_state)) => {
+                    HighlightLines::from_state(theme(), highlight_state.clone(), parse_state.clone())
+                }
+                None => HighlightLines::new(syntax, theme()),
+            };
+            for line in &lines[self.styled.len()..end] {
+                // Bir önceki satırın sonundaki durum bu satırın başlangıcıdır
+                if !self.styled.is_empty() {
+                    let (highlight_state, parse_state) = highlighter.state();
+                    self.states.push((highlight_state.clone(), parse_state.clone()));
+                    highlighter = HighlightLines::from_state(theme(), highlight_state, parse_state);
+                }
+                self.styled.push(highlight_line(&mut highlighter, line));
+                self.lines.push(line.clone());
+            }
+        }
+        Some(&self.styled[..end])
+    }
+}
+
+// Markdown önizlemesindeki kod blokları için; dil adı ya da uzantısıyla
+// (`rust`, `rs`, `toml`...) bulunamazsa None.
+pub fn highlight_code(language: &str, lines: &[String]) -> Option<Vec<Vec<Span<'static>>>> {
+    let syntax = syntaxes().find_syntax_by_token(language)?;
+    let mut highlighter = HighlightLines::new(syntax, theme());
+    Some(lines.iter().map(|line| highlight_line(&mut highlighter, line)).collect())
+}
+
+fn highlight_line(highlighter: &mut HighlightLines, line: &str) -> Vec<Span<'static>> {
+    match highlighter.highlight_line(line, syntaxes()) {
+        Ok(regions) => regions
+            .into_iter()
+            .map(|(style, text)| Span::styled(text.to_string(), to_ratatui_style(style)))
+            .collect(),
+        // Tanım satırı ayrıştıramazsa o satır düz gösterilir
+        Err(_) => vec![Span::raw(line.to_string())],
+    }
+}
+
+// Belge adının uzantısına göre dil adı; düz metin için None.
+fn syntax_name(document: &str) -> Option<String> {
+    let (_, extension) = document.rsplit_once('.')?;
+    let syntax = syntaxes().find_syntax_by_extension(&extension.to_ascii_lowercase())?;
+    (syntax.name != syntaxes().find_syntax_plain_text().name).then(|| syntax.name.clone())
+}
+
+// Temanın arka planı kullanılmaz; terminalin kendi arka planı korunur.
+fn to_ratatui_style(style: syntect::highlighting::Style) -> Style {
+    let mut result = Style::default().fg(Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        result = result.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        result = result.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        result = result.add_modifier(Modifier::UNDERLINED);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn language_comes_from_a_known_extension() {
+        assert_eq!(syntax_name("main.rs").as_deref(), Some("Rust"));
+        assert_eq!(syntax_name("Cargo.TOML").as_deref(), Some("TOML"));
+        assert!(syntax_name("README.md").is_some());
+    }
+
+    #[test]
+    fn unknown_or_missing_extensions_are_plain_text() {
+        assert_eq!(syntax_name("notes.txt"), None);
+        assert_eq!(syntax_name("data.unknownext"), None);
+        assert_eq!(syntax_name("notlar"), None);
+    }
+
+    #[test]
+    fn plain_text_documents_are_not_highlighted() {
+        let mut highlighter = SyntaxHighlighter::default();
+        assert!(highlighter.highlight("notlar", &lines(&["fn main() {}"]), 1).is_none());
+        assert!(highlighter.highlight("notes.unknownext", &lines(&["fn main() {}"]), 1).is_none());
+    }
+
+    #[test]
+    fn highlighted_lines_keep_their_text() {
+        let mut highlighter = SyntaxHighlighter::default();
+        let content = lines(&["fn main() {", "    let x = \"a\";", "}"]);
+        let styled = highlighter.highlight("main.rs", &content, 2).unwrap();
+        assert_eq!(styled.len(), 2);
+        for (spans, line) in styled.iter().zip(&content) {
+            assert_eq!(spans.iter().map(|span| span.content.as_ref()).collect::<String>(), *line);
+        }
+        // Değişen satırdan sonrası yeniden renklendirilir
+        let edited = lines(&["fn main() {", "    let y = 1;", "}"]);
+        let styled = highlighter.highlight("main.rs", &edited, 3).unwrap();
+        assert_eq!(styled[1].iter().map(|span| span.content.as_ref()).collect::<String>(), edited[1]);
+        assert_eq!(styled.len(), 3);
+    }
+}
@@ -0,0 +1,134 @@
+use editor_protocol::locale::{fill, Locale};
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+mod en;
+mod tr;
+
+// Arayüz dili; açılışta bir kez seçilir (bkz. `language` ayarı ve `LANG`).
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+pub fn init(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+// Kullanıcıya gösterilen her metnin anahtarı. Her dilin kataloğu tüm
+// anahtarları karşılamak zorundadır; eksik bir çeviri derleme hatası verir.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    ClientTitle,
+    UsernamePrompt,
+    UsernameEmpty,
+    ConnectFailed,
+    Connecting,
+    OfflineEditsRestored,
+    JournalLoadFailed,
+    JournalWriteFailed,
+    OfflineReadFailed,
+    OfflineConflict,
+    ReplayingOfflineEdits,
+    Sending,
+    NoDocumentToEdit,
+    NoDocumentForHistory,
+    InvalidRevision,
+    NoDocumentToDiff,
+    DiffUsage,
+    BlameOnNoDocument,
+    BlameOff,
+    NoDocumentToRestore,
+    NoDocumentToClose,
+    NoDocumentToSplit,
+    NoSplitToClose,
+    DocumentClosed,
+    TabSwitchWhileOffline,
+    UnknownCommand,
+    SwitchRequested,
+    ReplacedMatches,
+    RestoringRevision,
+    NotConnectedNotSent,
+    SendFailed,
+    RequestTimedOut,
+    DocumentListUpdated,
+    NewDocumentAvailable,
+    UserJoined,
+    UserLeft,
+    UserSwitched,
+    Resynchronized,
+    DocumentLoaded,
+    ActiveDocumentUpdated,
+    InactiveDocumentUpdated,
+    SwitchedToDocument,
+    HistoryLoaded,
+    SearchResultsLoaded,
+    LocalDiffTitle,
+    ShowingLocalDiff,
+    RevisionReceived,
+    ShowingDiff,
+    Connected,
+    ServerShuttingDown,
+    Kicked,
+    Announcement,
+    ConnectionLost,
+    ServerStatus,
+    ServerError,
+    ServerErrorWithRequest,
+    SwitchBeforeEditing,
+    DocumentReadOnly,
+    ConnectionClosed,
+    ServerNotResponding,
+    ReadFailed,
+    Reconnecting,
+    InvalidRevisionHeader,
+    InvalidDiffHeader,
+    DocumentCreated,
+    UnknownServerMessage,
+    DocumentsTitle,
+    UsersTitle,
+    HistoryTitle,
+    SearchResultsTitle,
+    PreviewTitle,
+    ContentTitle,
+    NoDocumentTitle,
+    MarkdownTitle,
+    BlameTag,
+    DiffTitle,
+    SearchTitle,
+    SearchRegexTag,
+    SearchCaseTag,
+    SearchMatchCount,
+    SearchNoMatches,
+    SearchInvalidRegex,
+    SearchFindLabel,
+    SearchReplaceLabel,
+    SearchHelp,
+    CommandTitle,
+    StatusOffline,
+    StatusEditsPending,
+    StatusSaving,
+    StatusNotSaved,
+    StatusSaved,
+    EventsTitle,
+    EventsTitleWithLatency,
+}
+
+pub fn text(msg: Msg) -> &'static str {
+    match LOCALE.get().copied().unwrap_or_default() {
+        Locale::En => en::text(msg),
+        Locale::Tr => tr::text(msg),
+    }
+}
+
+// Katalog metnindeki `{}` yerlerini sırayla doldurur.
+pub fn format(msg: Msg, args: &[&dyn Display]) -> String {
+    fill(text(msg), args)
+}
+
+// `t!(Anahtar)` ya da `t!(Anahtar, arg1, arg2...)`: seçili dildeki metni String olarak verir.
+macro_rules! t {
+    ($msg:ident) => {
+        $crate::i18n::text($crate::i18n::Msg::$msg).to_string()
+    };
+    ($msg:ident, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format($crate::i18n::Msg::$msg, &[$(&$arg),+])
+    };
+}
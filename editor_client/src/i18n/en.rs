@@ -0,0 +1,100 @@
+use super::Msg;
+
+// İngilizce katalog
+pub fn text(msg: Msg) -> &'static str {
+    match msg {
+        Msg::ClientTitle => "Multi-User Text Editor Client (TUI)",
+        Msg::UsernamePrompt => "Enter your username: ",
+        Msg::UsernameEmpty => "Username cannot be empty.",
+        Msg::ConnectFailed => "Could not connect to server ({}): {}",
+        Msg::Connecting => "Connecting...",
+        Msg::OfflineEditsRestored => "{} offline edit(s) from a previous session will be replayed.",
+        Msg::JournalLoadFailed => "ERROR: Could not load offline journal: {}",
+        Msg::JournalWriteFailed => "ERROR: Could not write offline journal: {}",
+        Msg::OfflineReadFailed => "ERROR: Could not read offline edits: {}",
+        Msg::OfflineConflict => "Offline edits conflict with the server version; keeping local text.",
+        Msg::ReplayingOfflineEdits => "Replaying {} offline edit(s).",
+        Msg::Sending => "Sending: {}",
+        Msg::NoDocumentToEdit => "ERROR: No active document to edit.",
+        Msg::NoDocumentForHistory => "ERROR: No active document to show history for.",
+        Msg::InvalidRevision => "ERROR: Invalid revision: {}",
+        Msg::NoDocumentToDiff => "ERROR: No active document to diff.",
+        Msg::DiffUsage => "Usage: DIFF <revA> [revB|current]",
+        Msg::BlameOnNoDocument => "Blame gutter on (no active document).",
+        Msg::BlameOff => "Blame gutter off.",
+        Msg::NoDocumentToRestore => "ERROR: No active document to restore.",
+        Msg::NoDocumentToClose => "ERROR: No open document to close.",
+        Msg::NoDocumentToSplit => "ERROR: Open a document before splitting the view.",
+        Msg::NoSplitToClose => "The view is not split.",
+        Msg::DocumentClosed => "Closed document '{}'.",
+        Msg::TabSwitchWhileOffline => "Offline edits of '{}' are still pending; switch tabs once they are sent.",
+        Msg::UnknownCommand => "Unknown command or missing argument: {}",
+        Msg::ReplacedMatches => "Replaced {} match(es).",
+        Msg::SwitchRequested => "Requesting to switch to '{}'.",
+        Msg::RestoringRevision => "Restoring '{}' to revision {}.",
+        Msg::NotConnectedNotSent => "ERROR: Not connected; '{}' was not sent.",
+        Msg::SendFailed => "ERROR: Could not send '{}': {}",
+        Msg::RequestTimedOut => "[ERROR] Request #{} ({}) was not answered in time.",
+        Msg::DocumentListUpdated => "Document list updated.",
+        Msg::NewDocumentAvailable => "New document available: {}",
+        Msg::UserJoined => "{} joined.",
+        Msg::UserLeft => "{} left.",
+        Msg::UserSwitched => "{} switched to '{}'.",
+        Msg::Resynchronized => "Resynchronized document '{}'.",
+        Msg::DocumentLoaded => "Loaded document '{}'.",
+        Msg::ActiveDocumentUpdated => "Active document '{}' updated.",
+        Msg::InactiveDocumentUpdated => "Inactive document '{}' was updated.",
+        Msg::SwitchedToDocument => "Switched to document '{}'.",
+        Msg::SearchResultsLoaded => "{} match(es) for '{}'. Enter: open, Esc: close.",
+        Msg::HistoryLoaded => "{} revision(s) of '{}'. Enter: preview, r: restore, Esc: close.",
+        Msg::LocalDiffTitle => "{}: #{} ↔ local",
+        Msg::ShowingLocalDiff => "Showing diff against local buffer (Esc in command input to close).",
+        Msg::RevisionReceived => "Received revision {} of '{}' (history closed).",
+        Msg::ShowingDiff => "Showing diff (Esc in command input to close).",
+        Msg::Connected => "Connected to server.",
+        Msg::ServerShuttingDown => "[SERVER] Server is shutting down: {}. Will reconnect when it is back.",
+        Msg::Kicked => "[ERROR] Kicked from the server: {}. Restart the client to reconnect.",
+        Msg::Announcement => "[ANNOUNCEMENT] {}",
+        Msg::ConnectionLost => "[ERROR] {} Edits are queued until the connection is back.",
+        Msg::ServerStatus => "[SERVER] {}",
+        Msg::ServerError => "[ERROR {}] {}",
+        Msg::ServerErrorWithRequest => "[ERROR {}] {} (request {})",
+        Msg::SwitchBeforeEditing => "Switch to the document again (SWITCH <name>) before editing it.",
+        Msg::DocumentReadOnly => "Document is read-only until an administrator unlocks it.",
+        Msg::ConnectionClosed => "Server connection closed.",
+        Msg::ServerNotResponding => "Server did not respond for {} s.",
+        Msg::ReadFailed => "Read from server failed: {}",
+        Msg::Reconnecting => "Reconnecting in {} s (attempt {})...",
+        Msg::InvalidRevisionHeader => "Invalid revision header: {}",
+        Msg::InvalidDiffHeader => "Invalid diff header: {}",
+        Msg::DocumentCreated => "Document '{}' was created on the server.",
+        Msg::UnknownServerMessage => "[UNKNOWN FROM SERVER]: {}",
+        Msg::DocumentsTitle => "Documents (TAB to switch)",
+        Msg::UsersTitle => "Active Users",
+        Msg::SearchResultsTitle => "Search: {} ({} hits, Enter to open)",
+        Msg::HistoryTitle => "History: {}",
+        Msg::PreviewTitle => "Content: {} @ #{} (preview, Esc to close) ",
+        Msg::ContentTitle => "Content: {} ",
+        Msg::NoDocumentTitle => "Content (No Document Selected) ",
+        Msg::MarkdownTitle => "Markdown: {} ",
+        Msg::BlameTag => "[authors] ",
+        Msg::DiffTitle => "Diff: {} (Esc to close) ",
+        Msg::SearchTitle => "Search{}{} {}",
+        Msg::SearchRegexTag => " [regex]",
+        Msg::SearchCaseTag => " [match case]",
+        Msg::SearchMatchCount => "- {}/{}",
+        Msg::SearchNoMatches => "- no matches",
+        Msg::SearchInvalidRegex => "- invalid regex: {}",
+        Msg::SearchFindLabel => "Find:    ",
+        Msg::SearchReplaceLabel => "Replace: ",
+        Msg::SearchHelp => "Enter/↑ next/prev · Tab field · Alt+R regex · Alt+C case · Ctrl+R replace · Ctrl+A all · Esc close",
+        Msg::CommandTitle => "Command{} (TAB to switch)",
+        Msg::StatusOffline => " [offline]",
+        Msg::StatusEditsPending => " [{} edits pending]",
+        Msg::StatusSaving => " [saving…]",
+        Msg::StatusNotSaved => " [not saved]",
+        Msg::StatusSaved => " [saved]",
+        Msg::EventsTitle => "Events / Status",
+        Msg::EventsTitleWithLatency => "Events / Status - latency {} ms",
+    }
+}
@@ -0,0 +1,100 @@
+use super::Msg;
+
+// Türkçe katalog
+pub fn text(msg: Msg) -> &'static str {
+    match msg {
+        Msg::ClientTitle => "Çok Kullanıcılı Metin Düzenleyici İstemcisi (TUI)",
+        Msg::UsernamePrompt => "Kullanıcı adınızı girin: ",
+        Msg::UsernameEmpty => "Kullanıcı adı boş olamaz.",
+        Msg::ConnectFailed => "Sunucuya bağlanılamadı ({}): {}",
+        Msg::Connecting => "Bağlanılıyor...",
+        Msg::OfflineEditsRestored => "Önceki oturumdan kalan {} çevrimdışı düzenleme yeniden oynatılacak.",
+        Msg::JournalLoadFailed => "HATA: Çevrimdışı günlük yüklenemedi: {}",
+        Msg::JournalWriteFailed => "HATA: Çevrimdışı günlüğe yazılamadı: {}",
+        Msg::OfflineReadFailed => "HATA: Çevrimdışı düzenlemeler okunamadı: {}",
+        Msg::OfflineConflict => "Çevrimdışı düzenlemeler sunucudaki sürümle çakışıyor; yerel metin korunuyor.",
+        Msg::ReplayingOfflineEdits => "{} çevrimdışı düzenleme yeniden oynatılıyor.",
+        Msg::Sending => "Gönderiliyor: {}",
+        Msg::NoDocumentToEdit => "HATA: Düzenlenecek aktif belge yok.",
+        Msg::NoDocumentForHistory => "HATA: Geçmişi gösterilecek aktif belge yok.",
+        Msg::InvalidRevision => "HATA: Geçersiz revizyon: {}",
+        Msg::NoDocumentToDiff => "HATA: Karşılaştırılacak aktif belge yok.",
+        Msg::DiffUsage => "Kullanım: DIFF <revA> [revB|current]",
+        Msg::BlameOnNoDocument => "Yazar sütunu açık (aktif belge yok).",
+        Msg::BlameOff => "Yazar sütunu kapalı.",
+        Msg::NoDocumentToClose => "HATA: Kapatılacak açık belge yok.",
+        Msg::NoDocumentToSplit => "HATA: Görünümü bölmeden önce bir belge açın.",
+        Msg::NoSplitToClose => "Görünüm bölünmüş değil.",
+        Msg::DocumentClosed => "'{}' belgesi kapatıldı.",
+        Msg::TabSwitchWhileOffline => "'{}' belgesinin çevrimdışı düzenlemeleri henüz gönderilmedi; gönderilince sekme değiştirin.",
+        Msg::NoDocumentToRestore => "HATA: Geri yüklenecek aktif belge yok.",
+        Msg::UnknownCommand => "Bilinmeyen komut ya da eksik argüman: {}",
+        Msg::ReplacedMatches => "{} eşleşme değiştirildi.",
+        Msg::SwitchRequested => "'{}' belgesine geçiş isteniyor.",
+        Msg::RestoringRevision => "'{}' belgesi {} numaralı revizyona geri yükleniyor.",
+        Msg::NotConnectedNotSent => "HATA: Bağlantı yok; '{}' gönderilmedi.",
+        Msg::SendFailed => "HATA: '{}' gönderilemedi: {}",
+        Msg::RequestTimedOut => "[HATA] #{} numaralı istek ({}) zamanında yanıtlanmadı.",
+        Msg::DocumentListUpdated => "Belge listesi güncellendi.",
+        Msg::NewDocumentAvailable => "Yeni belge: {}",
+        Msg::UserJoined => "{} katıldı.",
+        Msg::UserLeft => "{} ayrıldı.",
+        Msg::UserSwitched => "{}, '{}' belgesine geçti.",
+        Msg::Resynchronized => "'{}' belgesi yeniden eşitlendi.",
+        Msg::DocumentLoaded => "'{}' belgesi yüklendi.",
+        Msg::ActiveDocumentUpdated => "Aktif belge '{}' güncellendi.",
+        Msg::InactiveDocumentUpdated => "Aktif olmayan '{}' belgesi güncellendi.",
+        Msg::SwitchedToDocument => "'{}' belgesine geçildi.",
+        Msg::SearchResultsLoaded => "{} sonuç ('{}'). Enter: aç, Esc: kapat.",
+        Msg::HistoryLoaded => "{} revizyon ('{}'). Enter: önizle, r: geri yükle, Esc: kapat.",
+        Msg::LocalDiffTitle => "{}: #{} ↔ yerel",
+        Msg::ShowingLocalDiff => "Yerel tamponla fark gösteriliyor (kapatmak için komut satırında Esc).",
+        Msg::RevisionReceived => "{} numaralı revizyon alındı: '{}' (geçmiş kapalı).",
+        Msg::ShowingDiff => "Fark gösteriliyor (kapatmak için komut satırında Esc).",
+        Msg::Connected => "Sunucuya bağlanıldı.",
+        Msg::ServerShuttingDown => "[SUNUCU] Sunucu kapanıyor: {}. Geri geldiğinde yeniden bağlanılacak.",
+        Msg::Kicked => "[HATA] Sunucudan atıldınız: {}. Yeniden bağlanmak için istemciyi yeniden başlatın.",
+        Msg::Announcement => "[DUYURU] {}",
+        Msg::ConnectionLost => "[HATA] {} Bağlantı geri gelene kadar düzenlemeler kuyrukta bekler.",
+        Msg::ServerStatus => "[SUNUCU] {}",
+        Msg::ServerError => "[HATA {}] {}",
+        Msg::ServerErrorWithRequest => "[HATA {}] {} (istek {})",
+        Msg::SwitchBeforeEditing => "Düzenlemeden önce belgeye yeniden geçin (SWITCH <ad>).",
+        Msg::DocumentReadOnly => "Belge, bir yönetici kilidi açana kadar salt okunur.",
+        Msg::ConnectionClosed => "Sunucu bağlantısı kesildi.",
+        Msg::ServerNotResponding => "Sunucu {} sn boyunca yanıt vermedi.",
+        Msg::ReadFailed => "Sunucudan okuma hatası: {}",
+        Msg::Reconnecting => "{} sn sonra yeniden bağlanılacak (deneme {})...",
+        Msg::InvalidRevisionHeader => "Geçersiz revizyon başlığı: {}",
+        Msg::InvalidDiffHeader => "Geçersiz fark başlığı: {}",
+        Msg::DocumentCreated => "'{}' belgesi sunucuda oluşturuldu.",
+        Msg::UnknownServerMessage => "[SUNUCU BİLİNMEYEN]: {}",
+        Msg::DocumentsTitle => "Belgeler (TAB ile geçiş)",
+        Msg::UsersTitle => "Aktif Kullanıcılar",
+        Msg::SearchResultsTitle => "Arama: {} ({} sonuç, Enter ile aç)",
+        Msg::HistoryTitle => "Geçmiş: {}",
+        Msg::PreviewTitle => "İçerik: {} @ #{} (önizleme, Esc ile kapat) ",
+        Msg::ContentTitle => "İçerik: {} ",
+        Msg::NoDocumentTitle => "İçerik (Belge Seçilmedi) ",
+        Msg::MarkdownTitle => "Markdown önizlemesi: {} ",
+        Msg::BlameTag => "[yazarlar] ",
+        Msg::DiffTitle => "Fark: {} (Esc ile kapat) ",
+        Msg::SearchTitle => "Ara{}{} {}",
+        Msg::SearchRegexTag => " [düzenli ifade]",
+        Msg::SearchCaseTag => " [büyük/küçük harf]",
+        Msg::SearchMatchCount => "- {}/{}",
+        Msg::SearchNoMatches => "- eşleşme yok",
+        Msg::SearchInvalidRegex => "- geçersiz düzenli ifade: {}",
+        Msg::SearchFindLabel => "Bul:      ",
+        Msg::SearchReplaceLabel => "Değiştir: ",
+        Msg::SearchHelp => "Enter/↑ sonraki/önceki · Tab alan · Alt+R düzenli ifade · Alt+C harf · Ctrl+R değiştir · Ctrl+A tümü · Esc kapat",
+        Msg::CommandTitle => "Komut{} (TAB ile geçiş)",
+        Msg::StatusOffline => " [bağlantı yok]",
+        Msg::StatusEditsPending => " [{} düzenleme bekliyor]",
+        Msg::StatusSaving => " [kaydediliyor…]",
+        Msg::StatusNotSaved => " [kaydedilmedi]",
+        Msg::StatusSaved => " [kaydedildi]",
+        Msg::EventsTitle => "Olaylar / Durum",
+        Msg::EventsTitleWithLatency => "Olaylar / Durum - gecikme {} ms",
+    }
+}
@@ -0,0 +1,416 @@
+use crate::app_state::ActiveWindow;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
+use std::path::Path;
+
+/// A named editor action that a key chord can be bound to.
+///
+/// Actions are shared between windows where the meaning carries over
+/// (`select_next` moves the selection in every list); `applies_to` decides
+/// which windows an action is available in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    FocusNext,
+    Cancel,
+    SubmitCommand,
+    SelectPrevious,
+    SelectNext,
+    OpenSelected,
+    DiffRevision,
+    RestoreRevision,
+    Undo,
+    Redo,
+    OpenSearch,
+    InsertNewline,
+    DeleteBackward,
+    DeleteForward,
+    CursorLeft,
+    CursorRight,
+    CursorUp,
+    CursorDown,
+    PageUp,
+    PageDown,
+    LineStart,
+    LineEnd,
+    DocumentStart,
+    DocumentEnd,
+    PreviousTab,
+    NextTab,
+    CloseTab,
+    PreviousPane,
+    NextPane,
+    SearchToggleField,
+    SearchToggleRegex,
+    SearchToggleCase,
+    SearchNext,
+    SearchPrevious,
+    ReplaceMatch,
+    ReplaceAll,
+}
+
+impl Action {
+    /// Every action, in lookup order: when two actions available in the same
+    /// window share a chord, the earlier one wins.
+    pub const ALL: [Action; 36] = [
+        Action::SearchToggleField,
+        Action::SearchToggleRegex,
+        Action::SearchToggleCase,
+        Action::SearchNext,
+        Action::SearchPrevious,
+        Action::ReplaceMatch,
+        Action::ReplaceAll,
+        Action::FocusNext,
+        Action::Cancel,
+        Action::SubmitCommand,
+        Action::SelectPrevious,
+        Action::SelectNext,
+        Action::OpenSelected,
+        Action::DiffRevision,
+        Action::RestoreRevision,
+        Action::Undo,
+        Action::Redo,
+        Action::OpenSearch,
+        Action::InsertNewline,
+        Action::DeleteBackward,
+        Action::DeleteForward,
+        Action::PreviousTab,
+        Action::NextTab,
+        Action::CloseTab,
+        Action::PreviousPane,
+        Action::NextPane,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::CursorUp,
+        Action::CursorDown,
+        Action::PageUp,
+        Action::PageDown,
+        Action::DocumentStart,
+        Action::DocumentEnd,
+        Action::LineStart,
+        Action::LineEnd,
+    ];
+
+    /// Name used in the keymap file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::FocusNext => "focus_next",
+            Action::Cancel => "cancel",
+            Action::SubmitCommand => "submit_command",
+            Action::SelectPrevious => "select_previous",
+            Action::SelectNext => "select_next",
+            Action::OpenSelected => "open_selected",
+            Action::DiffRevision => "diff_revision",
+            Action::RestoreRevision => "restore_revision",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::OpenSearch => "open_search",
+            Action::InsertNewline => "insert_newline",
+            Action::DeleteBackward => "delete_backward",
+            Action::DeleteForward => "delete_forward",
+            Action::CursorLeft => "cursor_left",
+            Action::CursorRight => "cursor_right",
+            Action::CursorUp => "cursor_up",
+            Action::CursorDown => "cursor_down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::LineStart => "line_start",
+            Action::LineEnd => "line_end",
+            Action::DocumentStart => "document_start",
+            Action::DocumentEnd => "document_end",
+            Action::PreviousTab => "previous_tab",
+            Action::NextTab => "next_tab",
+            Action::CloseTab => "close_tab",
+            Action::PreviousPane => "previous_pane",
+            Action::NextPane => "next_pane",
+            Action::SearchToggleField => "search_toggle_field",
+            Action::SearchToggleRegex => "search_toggle_regex",
+            Action::SearchToggleCase => "search_toggle_case",
+            Action::SearchNext => "search_next",
+            Action::SearchPrevious => "search_previous",
+            Action::ReplaceMatch => "replace_match",
+            Action::ReplaceAll => "replace_all",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Whether the action does anything while `window` has focus.
+    pub fn applies_to(self, window: ActiveWindow) -> bool {
+        use ActiveWindow::*;
+        match self {
+            Action::FocusNext => window != Search,
+            Action::Cancel => true,
+            Action::SubmitCommand => window == CommandInput,
+            Action::SelectPrevious | Action::SelectNext | Action::OpenSelected => {
+                matches!(window, DocumentList | History | SearchResults)
+            }
+            Action::DiffRevision | Action::RestoreRevision => window == History,
+            Action::PageUp | Action::PageDown => matches!(window, History | ContentView),
+            Action::DeleteBackward => matches!(window, CommandInput | ContentView | Search),
+            Action::SearchToggleField
+            | Action::SearchToggleRegex
+            | Action::SearchToggleCase
+            | Action::SearchNext
+            | Action::SearchPrevious
+            | Action::ReplaceMatch
+            | Action::ReplaceAll => window == Search,
+            _ => window == ContentView,
+        }
+    }
+
+    /// Chords bound to the action when the keymap file does not rebind it.
+    fn default_chords(self) -> &'static [&'static str] {
+        match self {
+            Action::FocusNext => &["Tab"],
+            Action::Cancel => &["Esc"],
+            Action::SubmitCommand => &["Enter"],
+            Action::SelectPrevious => &["Up"],
+            Action::SelectNext => &["Down"],
+            Action::OpenSelected => &["Enter"],
+            Action::DiffRevision => &["d"],
+            Action::RestoreRevision => &["r"],
+            Action::Undo => &["Ctrl+Z"],
+            Action::Redo => &["Ctrl+Y"],
+            Action::OpenSearch => &["Ctrl+F"],
+            Action::InsertNewline => &["Enter"],
+            Action::DeleteBackward => &["Backspace"],
+            Action::DeleteForward => &["Delete"],
+            Action::CursorLeft => &["Left"],
+            Action::CursorRight => &["Right"],
+            Action::CursorUp => &["Up"],
+            Action::CursorDown => &["Down"],
+            Action::PageUp => &["PageUp"],
+            Action::PageDown => &["PageDown"],
+            Action::LineStart => &["Home"],
+            Action::LineEnd => &["End"],
+            Action::DocumentStart => &["Ctrl+Home"],
+            Action::DocumentEnd => &["Ctrl+End"],
+            Action::PreviousTab => &["Alt+Left"],
+            Action::NextTab => &["Alt+Right"],
+            Action::CloseTab => &["Ctrl+W"],
+            Action::PreviousPane => &["Ctrl+Left", "Ctrl+Up"],
+            Action::NextPane => &["Ctrl+Right", "Ctrl+Down"],
+            Action::SearchToggleField => &["Tab"],
+            Action::SearchToggleRegex => &["Alt+R"],
+            Action::SearchToggleCase => &["Alt+C"],
+            Action::SearchNext => &["Enter", "Down", "F3"],
+            Action::SearchPrevious => &["Up", "Shift+F3"],
+            Action::ReplaceMatch => &["Ctrl+R"],
+            Action::ReplaceAll => &["Ctrl+A"],
+        }
+    }
+}
+
+/// A key together with its modifiers, e.g. `Ctrl+Z` or `Shift+F3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Parses `Mod+Mod+Key`. Letters are case-insensitive; `Shift+a` is `A`.
+    pub fn parse(text: &str) -> Result<KeyChord, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        // `Ctrl++` binds the plus key itself
+        if text.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let Some((key, modifier_names)) = parts.split_last() else {
+            return Err(format!("empty key chord '{}'", text));
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", name, text)),
+            };
+        }
+        let code = match key.to_ascii_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "esc" | "escape" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            name => match (name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()), key.chars().count()) {
+                (Some(number @ 1..=24), _) => KeyCode::F(number),
+                (_, 1) => KeyCode::Char(key.chars().next().unwrap_or_default().to_ascii_lowercase()),
+                _ => return Err(format!("unknown key '{}' in '{}'", key, text)),
+            },
+        };
+        Ok(KeyChord::from_key(code, modifiers))
+    }
+
+    pub fn from_event(event: &KeyEvent) -> KeyChord {
+        KeyChord::from_key(event.code, event.modifiers)
+    }
+
+    /// Shift on character keys is carried by the character itself (`A`), so
+    /// chords match whether or not the terminal reports the modifier.
+    fn from_key(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyChord { code: KeyCode::Char(c.to_ascii_uppercase()), modifiers: modifiers - KeyModifiers::SHIFT }
+            }
+            _ => KeyChord { code, modifiers },
+        }
+    }
+}
+
+/// Key chords bound to each action; built from the defaults and overridden
+/// per action by the keymap file.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyChord>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let chords = action.default_chords().iter().filter_map(|chord| KeyChord::parse(chord).ok()).collect();
+                (action, chords)
+            })
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// Loads the keymap file if it exists; actions it does not mention keep their default keys.
+    pub fn load(path: &Path) -> Self {
+        let mut keymap = Keymap::default();
+        if let Ok(contents) = fs::read_to_string(path) {
+            keymap.apply(&contents);
+            println!("Loaded keymap: {}", path.display());
+        }
+        keymap
+    }
+
+    fn apply(&mut self, contents: &str) {
+        for (line_no, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, chords)) = line.split_once('=') else {
+                eprintln!("Keymap line {}: expected `action = key, key...`", line_no + 1);
+                continue;
+            };
+            if let Err(e) = self.bind(name.trim(), chords.trim()) {
+                eprintln!("Keymap line {}: {}", line_no + 1, e);
+            }
+        }
+    }
+
+    /// Replaces the chords of one action; an empty list unbinds it.
+    fn bind(&mut self, name: &str, chords: &str) -> Result<(), String> {
+        let action = Action::parse(name).ok_or_else(|| format!("unknown action '{}'", name))?;
+        let chords = chords
+            .split(',')
+            .map(str::trim)
+            .filter(|chord| !chord.is_empty())
+            .map(KeyChord::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some((_, bound)) = self.bindings.iter_mut().find(|(bound_action, _)| *bound_action == action) {
+            *bound = chords;
+        }
+        Ok(())
+    }
+
+    /// The action `event` triggers in `window`, if any.
+    pub fn action(&self, window: ActiveWindow, event: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(event);
+        self.bindings
+            .iter()
+            .find(|(action, chords)| action.applies_to(window) && chords.contains(&chord))
+            .map(|(action, _)| *action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> KeyChord {
+        KeyChord::parse(text).unwrap()
+    }
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn chords_parse_modifiers_and_named_keys() {
+        assert_eq!(chord("Ctrl+Z"), KeyChord { code: KeyCode::Char('z'), modifiers: KeyModifiers::CONTROL });
+        assert_eq!(chord("control + alt + z"), chord("Alt+Ctrl+Z"));
+        assert_eq!(chord("Shift+F3"), KeyChord { code: KeyCode::F(3), modifiers: KeyModifiers::SHIFT });
+        assert_eq!(chord("pgdn"), KeyChord { code: KeyCode::PageDown, modifiers: KeyModifiers::NONE });
+        assert_eq!(chord("Ctrl++"), KeyChord { code: KeyCode::Char('+'), modifiers: KeyModifiers::CONTROL });
+    }
+
+    #[test]
+    fn shifted_letters_match_with_or_without_the_reported_modifier() {
+        assert_eq!(chord("Shift+a"), KeyChord::from_event(&press(KeyCode::Char('A'), KeyModifiers::SHIFT)));
+        assert_eq!(chord("Shift+a"), KeyChord::from_event(&press(KeyCode::Char('A'), KeyModifiers::NONE)));
+        assert_ne!(chord("a"), chord("Shift+a"));
+    }
+
+    #[test]
+    fn invalid_chords_are_rejected() {
+        for text in ["", "Hyper+X", "F25", "Ctrl+", "NoSuchKey"] {
+            assert!(KeyChord::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn every_action_has_a_name_and_valid_default_keys() {
+        for action in Action::ALL {
+            assert_eq!(Action::parse(action.name()), Some(action));
+            assert!(!action.default_chords().is_empty(), "{:?}", action);
+            for text in action.default_chords() {
+                assert!(KeyChord::parse(text).is_ok(), "{:?}: {}", action, text);
+            }
+        }
+    }
+
+    #[test]
+    fn keymap_file_overrides_single_actions() {
+        let mut keymap = Keymap::default();
+        keymap.apply("# comment\nundo = Ctrl+U, F9\nredo =\nnot_an_action = x\ncursor_up = Hyper+K\nmissing equals sign");
+        let action = |window, code, modifiers| keymap.action(window, &press(code, modifiers));
+
+        assert_eq!(action(ActiveWindow::ContentView, KeyCode::Char('u'), KeyModifiers::CONTROL), Some(Action::Undo));
+        assert_eq!(action(ActiveWindow::ContentView, KeyCode::F(9), KeyModifiers::NONE), Some(Action::Undo));
+        assert_eq!(action(ActiveWindow::ContentView, KeyCode::Char('z'), KeyModifiers::CONTROL), None);
+        // An empty list unbinds; an invalid line leaves the defaults alone
+        assert_eq!(action(ActiveWindow::ContentView, KeyCode::Char('y'), KeyModifiers::CONTROL), None);
+        assert_eq!(action(ActiveWindow::ContentView, KeyCode::Up, KeyModifiers::NONE), Some(Action::CursorUp));
+    }
+
+    #[test]
+    fn the_same_key_depends_on_the_focused_window() {
+        let keymap = Keymap::default();
+        let enter = press(KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(keymap.action(ActiveWindow::CommandInput, &enter), Some(Action::SubmitCommand));
+        assert_eq!(keymap.action(ActiveWindow::ContentView, &enter), Some(Action::InsertNewline));
+        assert_eq!(keymap.action(ActiveWindow::History, &enter), Some(Action::OpenSelected));
+        assert_eq!(keymap.action(ActiveWindow::Search, &enter), Some(Action::SearchNext));
+    }
+}
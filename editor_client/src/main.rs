@@ -6,37 +6,62 @@ use std::{
     sync::mpsc,
     sync::{Arc, Mutex},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crossterm::{
-    event::{self as CEvent, DisableMouseCapture, EnableMouseCapture, Event as TermEvent, KeyCode, KeyEventKind},
+    event::{self as CEvent, DisableMouseCapture, EnableMouseCapture, Event as TermEvent, KeyCode, KeyEventKind, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
+    layout::Direction,
     Terminal,
 };
 
+#[macro_use]
+mod i18n;
 mod app_state;
+mod config;
 mod event;
+mod highlight;
+mod keymap;
+mod markdown;
+mod offline_queue;
+mod requests;
+mod search;
 mod server_listener;
 mod ui;
 
-use app_state::{AppState, ActiveWindow, SharedAppState};
+use app_state::{AppState, ActiveWindow, DiffView, HistoryView, SearchResultsView, SharedAppState};
+use config::ClientConfig;
+use editor_protocol::diff::diff_lines;
+use editor_protocol::locale::Locale;
 use event::{AppEvent, ServerCommand};
+use keymap::{Action, Keymap};
+use offline_queue::OfflineQueue;
+use requests::{next_request_id, PendingRequest, RequestTracker};
+
+// Olay gelmediğinde yanıtsız isteklerin ne sıklıkla denetleneceği
+const REQUEST_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+// Düzenlemelerden sonra yazar bilgisinin en fazla bu sıklıkla yeniden istenmesi
+const BLAME_REFRESH_DELAY: Duration = Duration::from_secs(1);
 
 fn main() -> Result<(), Box<dyn Error>> {
-    println!("Multi-User Text Editor Client (TUI)");
-    print!("Enter your username: ");
+    let config = ClientConfig::load();
+    let keymap = Keymap::load(&config.keymap_path);
+    i18n::init(config.language.or_else(Locale::from_env).unwrap_or_default());
+    println!("{}", t!(ClientTitle));
+    print!("{}", t!(UsernamePrompt));
     io::stdout().flush()?;
     let mut username_input = String::new();
     io::stdin().read_line(&mut username_input)?;
     let username = username_input.trim().to_string();
 
     if username.is_empty() {
-        eprintln!("Username cannot be empty.");
+        eprintln!("{}", t!(UsernameEmpty));
         return Ok(());
     }
 
@@ -46,7 +71,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app_state_arc: SharedAppState = Arc::new(Mutex::new(AppState::new(username.clone())));
+    let mut app_state = AppState::new(username.clone());
+    app_state.requests = RequestTracker::new(config.request_timeout);
+    app_state.show_line_numbers = config.line_numbers;
+    app_state.keymap = keymap;
+    match OfflineQueue::open(config.journal_path.clone()) {
+        Ok(queue) => {
+            if !queue.is_empty() {
+                app_state.add_event_log(t!(OfflineEditsRestored, queue.len()));
+            }
+            app_state.offline_queue = queue;
+        }
+        Err(e) => app_state.add_event_log(t!(JournalLoadFailed, e)),
+    }
+    let app_state_arc: SharedAppState = Arc::new(Mutex::new(app_state));
     let (event_tx, event_rx) = mpsc::channel::<AppEvent>();
 
     let stream_to_server = match TcpStream::connect(format!("{}:{}", CLIENT_CONNECT_ADDRESS, PORT)) {
@@ -54,7 +92,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         Err(e) => {
             disable_raw_mode()?;
             execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
-            eprintln!("Could not connect to server ({}:{}): {}", CLIENT_CONNECT_ADDRESS, PORT, e);
+            eprintln!("{}", t!(ConnectFailed, format!("{}:{}", CLIENT_CONNECT_ADDRESS, PORT), e));
             return Ok(());
         }
     };
@@ -62,13 +100,27 @@ fn main() -> Result<(), Box<dyn Error>> {
     let stream_reader_clone = stream_to_server.try_clone()?;
 
     {
+        let request_id = next_request_id();
+        app_state_arc.lock().unwrap().requests.track(request_id, CONNECT_CMD, None);
         let mut writer_guard = stream_writer_arc.lock().unwrap();
-        writeln!(writer_guard, "{} {}", CONNECT_CMD, username)?;
+        writeln!(writer_guard, "{}", format_request(request_id, &format!("{} {}", CONNECT_CMD, username)))?;
         writer_guard.flush()?;
     }
 
     let server_listener_event_tx = event_tx.clone();
-    server_listener::start_server_listener_thread(stream_reader_clone, server_listener_event_tx);
+    server_listener::start_server_listener_thread(
+        stream_reader_clone,
+        Arc::clone(&stream_writer_arc),
+        username,
+        config.keepalive_timeout,
+        server_listener_event_tx,
+    );
+    server_listener::start_heartbeat_thread(Arc::clone(&stream_writer_arc), config.keepalive_interval);
+
+    // Dil tanımlarını arka planda yükle; ilk renklendirilen belge ekranı bekletmesin
+    thread::spawn(|| {
+        highlight::syntaxes();
+    });
 
     let keyboard_event_tx = event_tx;
     thread::spawn(move || {
@@ -76,10 +128,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         loop {
             if CEvent::poll(tick_rate).unwrap_or(false) {
                 if let Ok(TermEvent::Key(key_event)) = CEvent::read() {
-                    if key_event.kind == KeyEventKind::Press {
-                        if keyboard_event_tx.send(AppEvent::Input(key_event)).is_err() {
-                            break;
-                        }
+                    if key_event.kind == KeyEventKind::Press
+                        && keyboard_event_tx.send(AppEvent::Input(key_event)).is_err()
+                    {
+                        break;
                     }
                 }
             }
@@ -95,11 +147,19 @@ fn main() -> Result<(), Box<dyn Error>> {
             }
         }
 
-        if let Ok(app_event) = event_rx.recv() {
-            let mut app = app_state_arc.lock().unwrap();
-            handle_event(app_event, &mut app, &stream_writer_arc);
-        } else {
-            break;
+        // Olay gelmese de zaman aşımına uğrayan istekleri fark etmek için uyan
+        match event_rx.recv_timeout(REQUEST_CHECK_INTERVAL) {
+            Ok(app_event) => {
+                let mut app = app_state_arc.lock().unwrap();
+                handle_event(app_event, &mut app, &stream_writer_arc);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => expire_requests(&mut app_state_arc.lock().unwrap()),
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        let mut app = app_state_arc.lock().unwrap();
+        if app.blame_due.is_some_and(|due| due <= Instant::now()) {
+            request_blame(&mut app, &stream_writer_arc);
         }
     }
 
@@ -113,120 +173,713 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn handle_event(event: AppEvent, app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>) {
     match event {
         AppEvent::Input(key_event) => handle_key_event(key_event, app, stream_writer),
-        AppEvent::ServerMessage(server_cmd) => handle_server_command(server_cmd, app),
+        AppEvent::ServerMessage(server_cmd) => handle_server_command(server_cmd, app, stream_writer),
     }
 }
 
+// Tuşlar önce odaktaki pencerede geçerli bir eyleme çevrilir (bkz. keymap.rs);
+// eyleme bağlı olmayan karakterler metin olarak yazılır.
 fn handle_key_event(key_event: crossterm::event::KeyEvent, app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>) {
+    let action = app.keymap.action(app.active_window, &key_event);
     match app.active_window {
         ActiveWindow::CommandInput => {
-            match key_event.code {
-                KeyCode::Enter => {
+            match action {
+                Some(Action::SubmitCommand) => {
                     let command_full = app.command_input.trim().to_string();
                     app.command_input.clear();
-                    app.add_event_log(format!("Sending: {}", command_full));
+                    app.add_event_log(t!(Sending, command_full));
 
                     let parts: Vec<&str> = command_full.splitn(2, ' ').collect();
                     let cmd_verb = parts[0].to_uppercase();
                     let cmd_arg = if parts.len() > 1 { parts[1] } else { "" };
 
-                    let mut writer_guard = stream_writer.lock().unwrap();
                     match cmd_verb.as_str() {
                         "QUIT" => {
-                            let _ = writeln!(writer_guard, "{}", DISCONNECT_CMD);
+                            let _ = send_line(stream_writer, &format_request(next_request_id(), DISCONNECT_CMD));
                             app.should_quit = true;
                         },
-                        "LIST" => { let _ = writeln!(writer_guard, "{}", LIST_DOCUMENTS_CMD); },
-                        "CREATE" if !cmd_arg.is_empty() => { let _ = writeln!(writer_guard, "{} {}", CREATE_DOCUMENT_CMD, cmd_arg); },
-                        "SWITCH" if !cmd_arg.is_empty() => { let _ = writeln!(writer_guard, "{} {}", SWITCH_DOCUMENT_CMD, cmd_arg); },
+                        "LIST" => send_command(app, stream_writer, LIST_DOCUMENTS_CMD),
+                        "CREATE" if !cmd_arg.is_empty() => send_command(app, stream_writer, &format!("{} {}", CREATE_DOCUMENT_CMD, cmd_arg)),
+                        "SWITCH" if !cmd_arg.is_empty() => open_document(app, stream_writer, cmd_arg),
+                        "CLOSE" => close_document(app, stream_writer),
+                        // Vim'deki gibi: SPLIT alt alta, VSPLIT yan yana böler
+                        "SPLIT" | "VSPLIT" => {
+                            if app.current_document_name.is_some() {
+                                let direction = if cmd_verb == "SPLIT" { Direction::Vertical } else { Direction::Horizontal };
+                                app.split_pane(direction);
+                                app.active_window = ActiveWindow::ContentView;
+                                if !cmd_arg.is_empty() {
+                                    open_document(app, stream_writer, cmd_arg);
+                                }
+                            } else {
+                                app.add_event_log(t!(NoDocumentToSplit));
+                            }
+                        },
+                        "UNSPLIT" => {
+                            if app.close_pane() {
+                                request_blame(app, stream_writer);
+                            } else {
+                                app.add_event_log(t!(NoSplitToClose));
+                            }
+                        },
                         "EDIT" => {
-                            if let Some(ref doc_name) = app.current_document_name {
-                                let msg = format_document_message(UPDATE_DOCUMENT_CMD, doc_name, cmd_arg);
-                                let _ = writeln!(writer_guard, "{}", msg);
+                            if app.current_document_name.is_some() {
+                                app.current_document_content = cmd_arg.lines().map(String::from).collect();
+                                send_document_update(app, stream_writer);
                             } else {
-                                app.add_event_log("ERROR: No active document to edit.".to_string());
+                                app.add_event_log(t!(NoDocumentToEdit));
                             }
                         },
-                        _ => app.add_event_log(format!("Unknown command or missing argument: {}", command_full)),
+                        "HISTORY" => {
+                            if let Some(doc_name) = app.current_document_name.clone() {
+                                send_command(app, stream_writer, &format!("{} {}", HISTORY_CMD, doc_name));
+                            } else {
+                                app.add_event_log(t!(NoDocumentForHistory));
+                            }
+                        },
+                        "DIFF" if !cmd_arg.is_empty() => {
+                            let revisions: Vec<&str> = cmd_arg.split_whitespace().collect();
+                            match (app.current_document_name.clone(), revisions.as_slice()) {
+                                (Some(doc_name), [rev_a, rev_b]) => {
+                                    send_command(app, stream_writer, &format!("{} {} {} {}", DIFF_DOCUMENT_CMD, doc_name, rev_a, rev_b));
+                                },
+                                // Tek revizyon: yerel tamponla karşılaştır
+                                (Some(doc_name), [rev_a]) => match rev_a.parse::<u64>() {
+                                    Ok(number) => {
+                                        app.pending_local_diff = Some(number);
+                                        send_command(app, stream_writer, &format!("{} {} {}", GET_REVISION_CMD, doc_name, number));
+                                    },
+                                    Err(_) => app.add_event_log(t!(InvalidRevision, rev_a)),
+                                },
+                                (None, _) => app.add_event_log(t!(NoDocumentToDiff)),
+                                _ => app.add_event_log(t!(DiffUsage)),
+                            }
+                        },
+                        "NUMBERS" => app.show_line_numbers = !app.show_line_numbers,
+                        "MARKDOWN" => app.show_markdown_preview = !app.show_markdown_preview,
+                        "BLAME" => {
+                            app.show_blame = !app.show_blame;
+                            match (app.current_document_name.clone(), app.show_blame) {
+                                (Some(doc_name), true) => send_command(app, stream_writer, &format!("{} {}", BLAME_CMD, doc_name)),
+                                (None, true) => app.add_event_log(t!(BlameOnNoDocument)),
+                                (_, false) => app.add_event_log(t!(BlameOff)),
+                            }
+                        },
+                        "RESTORE" if !cmd_arg.is_empty() => {
+                            if let Some(doc_name) = app.current_document_name.clone() {
+                                send_command(app, stream_writer, &format!("{} {} {}", RESTORE_REVISION_CMD, doc_name, cmd_arg));
+                            } else {
+                                app.add_event_log(t!(NoDocumentToRestore));
+                            }
+                        },
+                        "SEARCH" if !cmd_arg.is_empty() => send_command(app, stream_writer, &format!("{} {}", SEARCH_CMD, cmd_arg)),
+                        _ => app.add_event_log(t!(UnknownCommand, command_full)),
+                    }
+                },
+                Some(Action::DeleteBackward) => { app.command_input.pop(); },
+                Some(Action::Cancel) => app.diff_view = None,
+                Some(Action::FocusNext) => app.focus_next_window(),
+                None => {
+                    if let KeyCode::Char(c) = key_event.code {
+                        app.command_input.push(c);
                     }
-                    let _ = writer_guard.flush();
                 },
-                KeyCode::Char(c) => app.command_input.push(c),
-                KeyCode::Backspace => { app.command_input.pop(); },
-                KeyCode::Tab => app.active_window = ActiveWindow::DocumentList,
                 _ => {},
             }
         },
         ActiveWindow::DocumentList => {
-            match key_event.code {
-                KeyCode::Enter => {
+            match action {
+                Some(Action::OpenSelected) => {
                     if let Some(selected_index) = app.documents_list_state.selected() {
                         if let Some(doc_name) = app.available_documents.get(selected_index).cloned() {
-                            let mut writer_guard = stream_writer.lock().unwrap();
-                            let _ = writeln!(writer_guard, "{} {}", SWITCH_DOCUMENT_CMD, &doc_name);
-                            let _ = writer_guard.flush();
-                            app.add_event_log(format!("Requesting to switch to '{}'.", doc_name));
+                            open_document(app, stream_writer, &doc_name);
                         }
                     }
                     app.active_window = ActiveWindow::CommandInput;
                 },
-                KeyCode::Up => app.select_previous_document(),
-                KeyCode::Down => app.select_next_document(),
-                KeyCode::Tab => app.active_window = ActiveWindow::CommandInput,
+                Some(Action::SelectPrevious) => app.select_previous_document(),
+                Some(Action::SelectNext) => app.select_next_document(),
+                Some(Action::FocusNext) => app.focus_next_window(),
                 _ => {},
             }
         },
+        ActiveWindow::History => {
+            let Some(history) = app.history.as_mut() else {
+                app.active_window = ActiveWindow::CommandInput;
+                return;
+            };
+            let selected = history.selected_revision().map(|revision| (history.document_name.clone(), revision));
+            match action {
+                Some(Action::SelectPrevious) => history.select_previous(),
+                Some(Action::SelectNext) => history.select_next(),
+                // Önizlemeyi sayfa sayfa kaydır; sona taşan kaydırma çizimde düzeltilir
+                Some(Action::PageUp) => history.preview_scroll = history.preview_scroll.saturating_sub(app.content_height.max(1)),
+                Some(Action::PageDown) => history.preview_scroll += app.content_height.max(1),
+                Some(Action::OpenSelected) => {
+                    if let Some((doc_name, revision)) = selected {
+                        send_command(app, stream_writer, &format!("{} {} {}", GET_REVISION_CMD, doc_name, revision));
+                    }
+                },
+                Some(Action::DiffRevision) => {
+                    if let Some((doc_name, revision)) = selected {
+                        send_command(app, stream_writer, &format!("{} {} {} {}", DIFF_DOCUMENT_CMD, doc_name, revision, CURRENT_REVISION));
+                    }
+                },
+                Some(Action::RestoreRevision) => {
+                    if let Some((doc_name, revision)) = selected {
+                        app.add_event_log(t!(RestoringRevision, doc_name, revision));
+                        send_command(app, stream_writer, &format!("{} {} {}", RESTORE_REVISION_CMD, doc_name, revision));
+                    }
+                },
+                Some(Action::Cancel) => {
+                    // Önce önizlemeyi kapat, sonra paneli
+                    if history.preview.is_some() {
+                        history.preview = None;
+                    } else {
+                        app.history = None;
+                        app.active_window = ActiveWindow::CommandInput;
+                    }
+                },
+                Some(Action::FocusNext) => app.focus_next_window(),
+                _ => {},
+            }
+        },
+        ActiveWindow::SearchResults => {
+            let Some(results) = app.search_results.as_mut() else {
+                app.active_window = ActiveWindow::CommandInput;
+                return;
+            };
+            match action {
+                Some(Action::SelectPrevious) => results.select_previous(),
+                Some(Action::SelectNext) => results.select_next(),
+                Some(Action::OpenSelected) => open_search_hit(app, stream_writer),
+                Some(Action::Cancel) => {
+                    app.search_results = None;
+                    app.active_window = ActiveWindow::CommandInput;
+                },
+                Some(Action::FocusNext) => app.focus_next_window(),
+                _ => {},
+            }
+        },
+        ActiveWindow::ContentView => {
+            let Some(doc_name) = app.current_document_name.clone() else {
+                if action == Some(Action::FocusNext) {
+                    app.focus_next_window();
+                }
+                return;
+            };
+            match action {
+                // Sekmeler arasında geçiş ve etkin sekmeyi kapatma
+                Some(Action::PreviousTab | Action::NextTab) => {
+                    if let Some(index) = app.adjacent_tab_index(action == Some(Action::NextTab)) {
+                        switch_tab(app, stream_writer, index);
+                    }
+                },
+                Some(Action::CloseTab) => close_document(app, stream_writer),
+                // Bölmeler arasında odak
+                Some(Action::PreviousPane | Action::NextPane) => {
+                    if let Some(index) = app.adjacent_pane_index(action == Some(Action::NextPane)) {
+                        focus_pane(app, stream_writer, index);
+                    }
+                },
+                Some(Action::Undo) => send_command(app, stream_writer, &format!("{} {}", UNDO_CMD, doc_name)),
+                Some(Action::Redo) => send_command(app, stream_writer, &format!("{} {}", REDO_CMD, doc_name)),
+                Some(Action::OpenSearch) => app.open_search(),
+                Some(Action::InsertNewline) => {
+                    app.insert_newline();
+                    send_document_update(app, stream_writer);
+                },
+                Some(Action::DeleteBackward) => {
+                    app.delete_backward();
+                    send_document_update(app, stream_writer);
+                },
+                Some(Action::DeleteForward) => {
+                    app.delete_forward();
+                    send_document_update(app, stream_writer);
+                },
+                Some(Action::CursorLeft) => app.move_cursor_left(),
+                Some(Action::CursorRight) => app.move_cursor_right(),
+                Some(Action::CursorUp) => app.move_cursor_up(),
+                Some(Action::CursorDown) => app.move_cursor_down(),
+                Some(Action::PageUp) => app.page_up(),
+                Some(Action::PageDown) => app.page_down(),
+                Some(Action::DocumentStart) => {
+                    app.cursor_row = 0;
+                    app.cursor_col = 0;
+                },
+                Some(Action::DocumentEnd) => {
+                    app.cursor_row = usize::MAX;
+                    app.cursor_col = usize::MAX;
+                    app.clamp_cursor();
+                },
+                Some(Action::LineStart) => app.cursor_col = 0,
+                Some(Action::LineEnd) => {
+                    app.cursor_col = usize::MAX;
+                    app.clamp_cursor();
+                },
+                Some(Action::Cancel) => app.active_window = ActiveWindow::CommandInput,
+                Some(Action::FocusNext) => app.focus_next_window(),
+                None => match key_event.code {
+                    KeyCode::Char(c) if !key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.insert_char(c);
+                        send_document_update(app, stream_writer);
+                    },
+                    _ => {},
+                },
+                _ => {},
+            }
+        },
+        ActiveWindow::Search => handle_search_key(key_event, action, app, stream_writer),
+    }
+}
+
+// Seçili arama sonucuna gider; başka bir belgedeyse önce o belgeye geçilir ve
+// imleç içerik gelince taşınır.
+fn open_search_hit(app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>) {
+    let Some(results) = app.search_results.as_ref() else {
+        return;
+    };
+    let Some(hit) = results.selected_hit().cloned() else {
+        return;
+    };
+    let row = hit.line.saturating_sub(1);
+    let query = results.query.clone();
+    if let Some(index) = app.tabs.iter().position(|tab| tab.name == hit.document) {
+        switch_tab(app, stream_writer, index);
+        if app.current_document_name.as_ref() == Some(&hit.document) {
+            app.jump_to_line(row, &query);
+        }
+    } else {
+        app.pending_jump = Some((hit.document.clone(), row));
+        send_command(app, stream_writer, &format!("{} {}", SWITCH_DOCUMENT_CMD, hit.document));
+    }
+    app.active_window = ActiveWindow::ContentView;
+}
+
+// Arama çubuğu açıkken basılan tuşlar. Değiştirmeler yerel tampona uygulanıp
+// tek bir UPDATE_DOCUMENT ile gönderilir, böylece sunucuda tek adımda geri alınır.
+fn handle_search_key(key_event: crossterm::event::KeyEvent, action: Option<Action>, app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>) {
+    let Some(search) = app.search.as_mut() else {
+        app.active_window = ActiveWindow::ContentView;
+        return;
+    };
+    match action {
+        Some(Action::Cancel) => app.close_search(),
+        Some(Action::SearchToggleField) => search.editing_replacement = !search.editing_replacement,
+        Some(Action::SearchToggleRegex) => {
+            search.toggle_regex();
+            app.search_from_origin();
+        },
+        Some(Action::SearchToggleCase) => {
+            search.toggle_case_sensitive();
+            app.search_from_origin();
+        },
+        Some(Action::ReplaceMatch) => {
+            let replaced = app.replace_current_match();
+            if replaced {
+                send_document_update(app, stream_writer);
+            }
+        },
+        Some(Action::ReplaceAll) => {
+            let count = app.replace_all_matches();
+            if count > 0 {
+                send_document_update(app, stream_writer);
+            }
+            app.add_event_log(t!(ReplacedMatches, count));
+        },
+        Some(Action::DeleteBackward) => {
+            search.pop();
+            if !search.editing_replacement {
+                app.search_from_origin();
+            }
+        },
+        Some(Action::SearchNext) => app.search_next(),
+        Some(Action::SearchPrevious) => app.search_previous(),
+        None => match key_event.code {
+            KeyCode::Char(c) if !key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                search.push(c);
+                if !search.editing_replacement {
+                    app.search_from_origin();
+                }
+            },
+            _ => {},
+        },
+        _ => {},
+    }
+}
+
+// Belge bir sekmede zaten açıksa o sekmeye geçer, değilse sunucudan açar;
+// sunucu yeni belgeye de abone eder ve önceki sekmeler açık kalır.
+fn open_document(app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>, doc_name: &str) {
+    match app.tabs.iter().position(|tab| tab.name == doc_name) {
+        Some(index) => switch_tab(app, stream_writer, index),
+        None => {
+            app.add_event_log(t!(SwitchRequested, doc_name));
+            send_command(app, stream_writer, &format!("{} {}", SWITCH_DOCUMENT_CMD, doc_name));
+        }
     }
 }
 
-fn handle_server_command(server_cmd: ServerCommand, app: &mut AppState) {
+// Açık sekmeler arasında yerel olarak geçer; sunucuya istek gitmez. Çevrimdışı
+// kuyruk tek bir belgenin düzenlemelerini tuttuğundan kuyruk boşalana kadar geçilmez.
+fn switch_tab(app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>, index: usize) {
+    if let Some(doc_name) = pending_offline_document(app) {
+        if app.tabs.get(index).is_some_and(|tab| tab.name != doc_name) {
+            app.add_event_log(t!(TabSwitchWhileOffline, doc_name));
+            return;
+        }
+    }
+    if app.activate_tab(index) {
+        let doc_name = app.current_document_name.clone().unwrap_or_default();
+        app.add_event_log(t!(SwitchedToDocument, doc_name));
+        request_blame(app, stream_writer);
+    }
+}
+
+// Başka bir bölmeye odaklanır; bölmenin belgesi başka bir sekmedeyse sekme
+// geçişindeki gibi çevrimdışı kuyruk boşalana kadar geçilmez.
+fn focus_pane(app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>, index: usize) {
+    let document = app.panes.get(index).and_then(|pane| pane.document.clone());
+    if let Some(doc_name) = pending_offline_document(app) {
+        if document.as_ref().is_some_and(|document| *document != doc_name) {
+            app.add_event_log(t!(TabSwitchWhileOffline, doc_name));
+            return;
+        }
+    }
+    let previous = app.current_document_name.clone();
+    if app.focus_pane(index) && app.current_document_name != previous {
+        request_blame(app, stream_writer);
+    }
+}
+
+// Çevrimdışı kuyrukta gönderilmeyi bekleyen düzenlemeleri olan belge
+fn pending_offline_document(app: &AppState) -> Option<String> {
+    app.offline_queue.document().filter(|_| !app.offline_queue.is_empty()).map(String::from)
+}
+
+// Etkin sekmeyi kapatır ve sunucudaki aboneliği bırakır.
+fn close_document(app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>) {
+    // Bekleyen çevrimdışı düzenlemeler bu sekmenin tamponuna dayanıyor
+    if let Some(doc_name) = pending_offline_document(app).filter(|doc_name| app.current_document_name.as_ref() == Some(doc_name)) {
+        app.add_event_log(t!(TabSwitchWhileOffline, doc_name));
+        return;
+    }
+    let Some(doc_name) = app.close_active_tab() else {
+        app.add_event_log(t!(NoDocumentToClose));
+        return;
+    };
+    if app.connected {
+        send_command(app, stream_writer, &format!("{} {}", CLOSE_DOCUMENT_CMD, doc_name));
+    }
+    app.add_event_log(t!(DocumentClosed, doc_name));
+    request_blame(app, stream_writer);
+}
+
+fn send_line(stream_writer: &Arc<Mutex<TcpStream>>, line: &str) -> io::Result<()> {
+    let mut writer_guard = stream_writer.lock().unwrap();
+    writeln!(writer_guard, "{}", line)?;
+    writer_guard.flush()
+}
+
+// Mesajı yeni bir istek kimliğiyle gönderir ve yanıtı beklenenlere ekler.
+fn send_request(
+    app: &mut AppState,
+    stream_writer: &Arc<Mutex<TcpStream>>,
+    line: &str,
+    update: Option<(String, Vec<String>)>,
+) -> io::Result<()> {
+    let request_id = next_request_id();
+    send_line(stream_writer, &format_request(request_id, line))?;
+    app.requests.track(request_id, line, update);
+    Ok(())
+}
+
+// Kullanıcının başlattığı bir komutu gönderir; bağlantı yoksa gönderilmediğini bildirir.
+fn send_command(app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>, line: &str) {
+    if !app.connected {
+        app.add_event_log(t!(NotConnectedNotSent, line));
+    } else if let Err(e) = send_request(app, stream_writer, line, None) {
+        app.connected = false;
+        app.add_event_log(t!(SendFailed, line, e));
+    }
+}
+
+// Yerel tamponu tüm içerik olarak sunucuya gönderir. Bağlantı yokken (veya
+// gönderim başarısız olursa) değişiklik çevrimdışı kuyruğa eklenir ve oturum
+// geri gelince sırayla yeniden oynatılır.
+fn send_document_update(app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>) {
+    let Some(doc_name) = app.current_document_name.clone() else {
+        return;
+    };
+    let content = app.current_document_content.clone();
+    if app.connected && !app.resync_pending && app.offline_queue.is_empty() {
+        let message = format_document_message(UPDATE_DOCUMENT_CMD, &doc_name, &content.join("\n"));
+        // `synced_content` sunucu ACK gönderince ilerler
+        if send_request(app, stream_writer, &message, Some((doc_name.clone(), content.clone()))).is_ok() {
+            schedule_blame(app);
+            return;
+        }
+        app.connected = false;
+    }
+    let synced_content = app.synced_content.clone();
+    if let Err(e) = app.offline_queue.push(&doc_name, &synced_content, &content) {
+        app.add_event_log(t!(JournalWriteFailed, e));
+    }
+}
+
+// Yazar sütunu açıksa aktif belge için güncel yazar bilgisini ister.
+fn request_blame(app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>) {
+    app.blame_due = None;
+    if let (true, Some(doc_name)) = (app.show_blame, app.current_document_name.clone()) {
+        let _ = send_request(app, stream_writer, &format!("{} {}", BLAME_CMD, doc_name), None);
+    }
+}
+
+// Düzenlemelerden sonra yazar bilgisini ertelenmiş olarak ister: her tuşta
+// istek gitmez, art arda düzenlemeler tek bir BLAME ile karşılanır.
+fn schedule_blame(app: &mut AppState) {
+    if app.show_blame && app.blame_due.is_none() {
+        app.blame_due = Some(Instant::now() + BLAME_REFRESH_DELAY);
+    }
+}
+
+// Zaman aşımına uğrayan istekleri bildirir.
+fn expire_requests(app: &mut AppState) {
+    for (request_id, request) in app.requests.expire() {
+        if request.update.is_some() {
+            app.save_failed = true;
+        }
+        app.add_event_log(t!(RequestTimedOut, request_id, request.command));
+    }
+}
+
+// Bağlantı koptuğunda yanıt gelmeyecek istekleri bırakır. Onaylanmamış
+// güncellemeler sunucuya ulaşmamış olabilir; yerel içerik çevrimdışı kuyruğa
+// alınır ve yeniden bağlanınca gönderilir.
+fn drop_in_flight_requests(app: &mut AppState) {
+    if app.requests.update_in_flight() {
+        if let Some(doc_name) = app.current_document_name.clone() {
+            let (synced_content, content) = (app.synced_content.clone(), app.current_document_content.clone());
+            if let Err(e) = app.offline_queue.push(&doc_name, &synced_content, &content) {
+                app.add_event_log(t!(JournalWriteFailed, e));
+            }
+        }
+    }
+    app.requests.clear();
+}
+
+fn handle_server_command(server_cmd: ServerCommand, app: &mut AppState, stream_writer: &Arc<Mutex<TcpStream>>) {
     match server_cmd {
         ServerCommand::UpdateDocumentList(docs) => {
             app.available_documents = docs;
             if app.documents_list_state.selected().is_none() && !app.available_documents.is_empty() {
                 app.documents_list_state.select(Some(0));
             }
-            app.add_event_log("Document list updated.".to_string());
+            app.add_event_log(t!(DocumentListUpdated));
         },
         ServerCommand::NewDocumentAvailable(doc_name) => {
             if !app.available_documents.contains(&doc_name) {
                 app.available_documents.push(doc_name.clone());
             }
-            app.add_event_log(format!("New document available: {}", doc_name));
+            app.add_event_log(t!(NewDocumentAvailable, doc_name));
         },
         ServerCommand::UserJoined(username) => {
             if !app.active_users.contains(&username) {
                 app.active_users.push(username.clone());
             }
-            app.add_event_log(format!("{} joined.", username));
+            app.add_event_log(t!(UserJoined, username));
         },
         ServerCommand::UserLeft(username) => {
             app.active_users.retain(|u| u != &username);
-            app.add_event_log(format!("{} left.", username));
+            app.add_event_log(t!(UserLeft, username));
+        },
+        ServerCommand::UserSwitched { username, document } => {
+            if !app.active_users.contains(&username) {
+                app.active_users.push(username.clone());
+            }
+            app.add_event_log(t!(UserSwitched, username, document));
         },
         ServerCommand::ReceiveDocumentContent { name, content } => {
-            app.current_document_name = Some(name.clone());
-            app.current_document_content = content.lines().map(String::from).collect();
-            app.add_event_log(format!("Loaded document '{}'.", name));
+            let server_content: Vec<String> = content.lines().map(String::from).collect();
+            if app.resync_pending && app.current_document_name.as_ref() == Some(&name) {
+                app.resync_pending = false;
+                for snapshot in app.replay_offline_edits(server_content) {
+                    let message = format_document_message(UPDATE_DOCUMENT_CMD, &name, &snapshot.join("\n"));
+                    if send_request(app, stream_writer, &message, Some((name.clone(), snapshot))).is_err() {
+                        // Bağlantı yine koptu; kalanlar bir sonraki oturumda gönderilir
+                        app.connected = false;
+                        send_document_update(app, stream_writer);
+                        break;
+                    }
+                }
+                app.add_event_log(t!(Resynchronized, name));
+            } else if let Some(tab) = app.background_tab_mut(&name) {
+                // Arka plandaki bir sekme (yeniden bağlanınca tazelenir)
+                tab.content = server_content.clone();
+                tab.synced_content = server_content;
+                return;
+            } else {
+                if app.current_document_name.as_ref() != Some(&name) {
+                    app.open_tab(&name);
+                }
+                app.current_document_content = server_content.clone();
+                app.synced_content = server_content;
+                app.clamp_cursor();
+                if let Some((_, row)) = app.pending_jump.take_if(|(doc_name, _)| *doc_name == name) {
+                    let query = app.search_results.as_ref().map(|results| results.query.clone()).unwrap_or_default();
+                    app.jump_to_line(row, &query);
+                }
+                app.add_event_log(t!(DocumentLoaded, name));
+            }
+            request_blame(app, stream_writer);
         },
         ServerCommand::UpdateDocumentContent { name, content } => {
+            if app.resync_pending && app.current_document_name.as_ref() == Some(&name) {
+                // Uzlaştırma için gelecek DOCUMENT_CONTENT bu değişikliği zaten içerir
+                return;
+            }
             if app.current_document_name.as_ref() == Some(&name) {
                 app.current_document_content = content.lines().map(String::from).collect();
-                app.add_event_log(format!("Active document '{}' updated.", name));
+                app.synced_content = app.current_document_content.clone();
+                app.clamp_cursor();
+                app.add_event_log(t!(ActiveDocumentUpdated, name));
+                schedule_blame(app);
             } else {
-                app.add_event_log(format!("Inactive document '{}' was updated.", name));
+                if let Some(tab) = app.background_tab_mut(&name) {
+                    tab.content = content.lines().map(String::from).collect();
+                    tab.synced_content = tab.content.clone();
+                }
+                app.add_event_log(t!(InactiveDocumentUpdated, name));
             }
         },
         ServerCommand::SwitchedToDocument { name } => {
-            app.current_document_name = Some(name.clone());
+            if app.resync_pending && app.current_document_name.as_ref() == Some(&name) {
+                return; // Yeniden abonelik; yerel tampon korunur
+            }
+            // Yeni sekme açılır; içerik hemen ardından DOCUMENT_CONTENT ile gelir
+            app.open_tab(&name);
             app.current_document_content.clear();
-            app.add_event_log(format!("Switched to document '{}'.", name));
+            app.synced_content.clear();
+            app.line_authors.clear();
+            app.cursor_row = 0;
+            app.cursor_col = 0;
+            app.scroll_row = 0;
+            app.scroll_col = 0;
+            app.add_event_log(t!(SwitchedToDocument, name));
+        },
+        ServerCommand::HistoryList { name, revisions } => {
+            app.add_event_log(t!(HistoryLoaded, revisions.len(), name));
+            app.history = Some(HistoryView::new(name, revisions));
+            app.active_window = ActiveWindow::History;
+        },
+        ServerCommand::RevisionContent { name, number, content } => {
+            if app.pending_local_diff == Some(number) && app.current_document_name.as_ref() == Some(&name) {
+                app.pending_local_diff = None;
+                let local_content = app.current_document_content.join("\n");
+                app.diff_view = Some(DiffView {
+                    title: t!(LocalDiffTitle, name, number),
+                    lines: diff_lines(&content, &local_content),
+                });
+                app.add_event_log(t!(ShowingLocalDiff));
+                return;
+            }
+            match app.history.as_mut() {
+                Some(history) if history.document_name == name => {
+                    history.preview = Some((number, content.lines().map(String::from).collect()));
+                    history.preview_scroll = 0;
+                },
+                _ => app.add_event_log(t!(RevisionReceived, number, name)),
+            }
         },
-        ServerCommand::Status(msg) => app.add_event_log(format!("[SERVER] {}", msg)),
-        ServerCommand::Error(err_msg) => {
-            app.add_event_log(format!("[ERROR] {}", err_msg));
-            if err_msg.contains("Server connection closed") {
-                app.should_quit = true;
+        ServerCommand::DiffResult { name, rev_a, rev_b, lines } => {
+            app.diff_view = Some(DiffView { title: format!("{}: #{} ↔ #{}", name, rev_a, rev_b), lines });
+            app.add_event_log(t!(ShowingDiff));
+        },
+        ServerCommand::BlameInfo { name, authors } => {
+            if app.current_document_name.as_ref() == Some(&name) {
+                app.line_authors = authors;
+            }
+        },
+        ServerCommand::SearchResults { query, hits } => {
+            app.add_event_log(t!(SearchResultsLoaded, hits.len(), query));
+            app.search_results = Some(SearchResultsView::new(query, hits));
+            app.active_window = ActiveWindow::SearchResults;
+        },
+        ServerCommand::Connected => {
+            app.connected = true;
+            app.add_event_log(t!(Connected));
+            if app.current_document_name.is_none() {
+                // Önceki oturumdan kalan çevrimdışı düzenlemelerin belgesini aç
+                if let Some(doc_name) = app.offline_queue.document().map(String::from) {
+                    app.open_tab(&doc_name);
+                }
+            }
+            // Yeniden bağlandıysak arka plandaki sekmelerin içeriğini tazele ve
+            // aktif belgeye tekrar abone ol; gelen içerik üzerine bekleyen
+            // düzenlemeler yeniden oynatılır
+            let background: Vec<String> = app
+                .tabs
+                .iter()
+                .filter(|tab| app.current_document_name.as_ref() != Some(&tab.name))
+                .map(|tab| tab.name.clone())
+                .collect();
+            for doc_name in background {
+                send_command(app, stream_writer, &format!("{} {}", GET_DOCUMENT_CMD, doc_name));
+            }
+            if let Some(doc_name) = app.current_document_name.clone() {
+                app.resync_pending = true;
+                send_command(app, stream_writer, &format!("{} {}", SWITCH_DOCUMENT_CMD, doc_name));
+            }
+        },
+        ServerCommand::Latency(latency) => app.latency = Some(latency),
+        ServerCommand::ServerShuttingDown(reason) => {
+            app.add_event_log(t!(ServerShuttingDown, reason));
+        },
+        ServerCommand::Kicked(reason) => {
+            drop_in_flight_requests(app);
+            app.connected = false;
+            app.latency = None;
+            app.resync_pending = false;
+            app.add_event_log(t!(Kicked, reason));
+        },
+        ServerCommand::Announcement(text) => app.add_event_log(t!(Announcement, text)),
+        ServerCommand::ConnectionLost(reason) => {
+            drop_in_flight_requests(app);
+            app.connected = false;
+            app.latency = None;
+            app.resync_pending = false;
+            app.add_event_log(t!(ConnectionLost, reason));
+        },
+        ServerCommand::Status(msg) => app.add_event_log(t!(ServerStatus, msg)),
+        ServerCommand::Ack(request_id) => {
+            if let Some(PendingRequest { update: Some((doc_name, content)), .. }) = app.requests.complete(&request_id) {
+                app.save_failed = false;
+                if app.current_document_name.as_ref() == Some(&doc_name) {
+                    app.synced_content = content;
+                } else if let Some(tab) = app.background_tab_mut(&doc_name) {
+                    tab.synced_content = content;
+                }
+            }
+        },
+        ServerCommand::Error { code, request_id, message } => {
+            let request = request_id.as_deref().and_then(|id| app.requests.complete(id));
+            if request.is_some_and(|request| request.update.is_some()) {
+                app.save_failed = true;
+            }
+            match request_id {
+                Some(id) => app.add_event_log(t!(ServerErrorWithRequest, code.as_str(), message, id)),
+                None => app.add_event_log(t!(ServerError, code.as_str(), message)),
+            }
+            match code {
+                // Belge başka biri tarafından silinmiş olabilir; listeyi tazele
+                ErrorCode::DocumentNotFound => send_command(app, stream_writer, LIST_DOCUMENTS_CMD),
+                ErrorCode::NotEditingDocument => {
+                    app.add_event_log(t!(SwitchBeforeEditing));
+                },
+                ErrorCode::DocumentLocked => {
+                    app.add_event_log(t!(DocumentReadOnly));
+                },
+                _ => {},
             }
         },
     }
@@ -0,0 +1,336 @@
+use crate::highlight;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+// Markdown metnini önizleme paneli için biçimlendirilmiş satırlara çevirir.
+// `width` yatay çizgilerin uzunluğudur; uzun satırlar paneldeki sarma ile bölünür.
+pub fn render(lines: &[String], width: usize) -> Vec<Line<'static>> {
+    let source = lines.join("\n");
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer { width, ..Default::default() };
+    for event in Parser::new_ext(&source, options) {
+        renderer.handle(event);
+    }
+    renderer.flush();
+    renderer.lines
+}
+
+// Tablo hücreleri tablo bitene kadar biriktirilir; sütun genişlikleri ancak
+// o zaman bilinir.
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    header_rows: usize,
+}
+
+#[derive(Default)]
+struct Renderer {
+    width: usize,
+    lines: Vec<Line<'static>>,
+    current: Vec<Span<'static>>, // Yazılmakta olan satırın parçaları
+    styles: Vec<Style>,          // İç içe vurguların biçimleri (kalın, italik, bağlantı...)
+    lists: Vec<Option<u64>>,     // İç içe listeler; numaralıysa sıradaki numara
+    item_marker: Option<String>, // Yeni başlayan liste öğesinin işareti ("• ", "2. ")
+    quote_depth: usize,
+    code_block: Option<Option<String>>, // Kod bloğu içindeysek bloğun dili
+    code: String,
+    links: Vec<String>, // Açık bağlantıların adresleri
+    table: Option<Table>,
+}
+
+impl Renderer {
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.code_block.is_some() => self.code.push_str(&text),
+            Event::Text(text) => self.push(text.to_string(), self.style()),
+            Event::Code(code) => self.push(code.to_string(), self.style().fg(Color::Yellow)),
+            Event::InlineMath(text) | Event::DisplayMath(text) => self.push(text.to_string(), self.style()),
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for line in html.lines() {
+                    self.push(line.to_string(), Style::default().fg(Color::DarkGray));
+                    self.flush();
+                }
+            }
+            Event::FootnoteReference(name) => self.push(format!("[^{}]", name), self.style().fg(Color::Blue)),
+            Event::SoftBreak => self.push(" ".to_string(), self.style()),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.block_gap();
+                self.lines.push(Line::styled("─".repeat(self.width), Style::default().fg(Color::DarkGray)));
+            }
+            Event::TaskListMarker(checked) => {
+                self.push(if checked { "[x] " } else { "[ ] " }.to_string(), Style::default().fg(Color::Green))
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.block_gap(),
+            Tag::Heading { level, .. } => {
+                self.block_gap();
+                self.styles.push(heading_style(level));
+            }
+            Tag::BlockQuote(_) => {
+                self.block_gap();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.block_gap();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(str::to_string),
+                    CodeBlockKind::Indented => None,
+                };
+                self.code_block = Some(language);
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.block_gap();
+                } else {
+                    self.flush();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.item_marker = Some(marker);
+            }
+            Tag::Table(alignments) => {
+                self.block_gap();
+                self.table = Some(Table { alignments, ..Default::default() });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.styles.push(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.styles.push(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self.styles.push(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                self.styles.push(Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED));
+                self.links.push(dest_url.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.flush(),
+            TagEnd::Heading(_) => {
+                self.flush();
+                self.styles.pop();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => self.end_code_block(),
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+            }
+            TagEnd::Item => self.flush(),
+            TagEnd::TableHead => {
+                if let Some(table) = self.table.as_mut() {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::TableCell => {
+                let cell = std::mem::take(&mut self.current);
+                if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(cell);
+                }
+            }
+            TagEnd::Table => self.end_table(),
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link | TagEnd::Image => {
+                self.styles.pop();
+                if let Some(url) = self.links.pop().filter(|url| !url.is_empty()) {
+                    self.push(format!(" ({})", url), Style::default().fg(Color::DarkGray));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // İç içe vurguların birleşik biçimi
+    fn style(&self) -> Style {
+        self.styles.iter().fold(Style::default(), |style, inner| style.patch(*inner))
+    }
+
+    fn push(&mut self, text: String, style: Style) {
+        self.current.push(Span::styled(text, style));
+    }
+
+    // Bloklar arasında tek bir boş satır bırakır; liste öğeleri arasında bırakmaz
+    fn block_gap(&mut self) {
+        self.flush();
+        if self.lists.is_empty() && self.lines.last().is_some_and(|line| line.width() > 0) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    // Yazılmakta olan satırı alıntı ve liste girintisiyle birlikte tamamlar
+    fn flush(&mut self) {
+        if self.current.is_empty() || self.table.is_some() {
+            return;
+        }
+        let mut spans = self.prefix();
+        spans.append(&mut self.current);
+        self.lines.push(Line::from(spans));
+    }
+
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let mut prefix = Vec::new();
+        if self.quote_depth > 0 {
+            prefix.push(Span::styled("│ ".repeat(self.quote_depth), Style::default().fg(Color::DarkGray)));
+        }
+        if !self.lists.is_empty() {
+            prefix.push(Span::raw("  ".repeat(self.lists.len() - 1)));
+            match self.item_marker.take() {
+                Some(marker) => prefix.push(Span::styled(marker, Style::default().fg(Color::Cyan))),
+                // Öğenin devam satırları işaretin genişliği kadar içeriden başlar
+                None => prefix.push(Span::raw("  ")),
+            }
+        }
+        prefix
+    }
+
+    // Dili bilinen kod blokları renklendirilir, diğerleri tek renk gösterilir
+    fn end_code_block(&mut self) {
+        let language = self.code_block.take().flatten();
+        let code = std::mem::take(&mut self.code);
+        let code_lines: Vec<String> = code.lines().map(str::to_string).collect();
+        let styled = language.and_then(|language| highlight::highlight_code(&language, &code_lines));
+        for (index, line) in code_lines.iter().enumerate() {
+            self.current.push(Span::raw("  "));
+            match styled.as_ref().and_then(|styled| styled.get(index)) {
+                Some(spans) => self.current.extend(spans.iter().cloned()),
+                None => self.push(line.clone(), Style::default().fg(Color::Yellow)),
+            }
+            self.flush();
+        }
+    }
+
+    fn end_table(&mut self) {
+        let Some(table) = self.table.take() else { return };
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                table.rows.iter().filter_map(|row| row.get(column)).map(|cell| spans_width(cell)).max().unwrap_or(0)
+            })
+            .collect();
+        let border = Style::default().fg(Color::DarkGray);
+        for (row_index, row) in table.rows.into_iter().enumerate() {
+            let header = row_index < table.header_rows;
+            let mut cells = row.into_iter();
+            for (column, width) in widths.iter().enumerate() {
+                if column > 0 {
+                    self.push(" │ ".to_string(), border);
+                }
+                let cell = cells.next().unwrap_or_default();
+                let padding = width - spans_width(&cell);
+                let (left, right) = match table.alignments.get(column) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                self.push(" ".repeat(left), Style::default());
+                for span in cell {
+                    let style = if header { span.style.add_modifier(Modifier::BOLD) } else { span.style };
+                    self.push(span.content.into_owned(), style);
+                }
+                self.push(" ".repeat(right), Style::default());
+            }
+            self.flush();
+            if header && row_index + 1 == table.header_rows {
+                let separator: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                self.push(separator.join("─┼─"), border);
+                self.flush();
+            }
+        }
+    }
+}
+
+fn heading_style(level: HeadingLevel) -> Style {
+    let style = Style::default().add_modifier(Modifier::BOLD);
+    match level {
+        HeadingLevel::H1 => style.fg(Color::Magenta).add_modifier(Modifier::UNDERLINED),
+        HeadingLevel::H2 => style.fg(Color::Magenta),
+        _ => style.fg(Color::Cyan),
+    }
+}
+
+fn spans_width(spans: &[Span]) -> usize {
+    spans.iter().map(|span| span.content.chars().count()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(markdown: &str, width: usize) -> Vec<String> {
+        let lines: Vec<String> = markdown.lines().map(str::to_string).collect();
+        render(&lines, width).iter().map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect()).collect()
+    }
+
+    fn span<'a>(line: &'a Line<'static>, content: &str) -> &'a Span<'static> {
+        line.spans.iter().find(|span| span.content == content).unwrap()
+    }
+
+    #[test]
+    fn blocks_are_separated_by_one_blank_line() {
+        assert_eq!(text("# Başlık\n\n\n\nBir **kalın** kelime\nve devamı", 10), ["Başlık", "", "Bir kalın kelime ve devamı"]);
+        assert_eq!(text("a\n\n---\n\nb", 4), ["a", "", "────", "", "b"]);
+    }
+
+    #[test]
+    fn inline_styles_are_combined() {
+        let lines = render(&["# **Kalın** başlık".to_string(), "*italik ve `kod`*".to_string()], 10);
+        let heading = span(&lines[0], "Kalın").style;
+        assert!(heading.add_modifier.contains(Modifier::BOLD | Modifier::UNDERLINED));
+        assert_eq!(heading.fg, Some(Color::Magenta));
+        let code = span(&lines[2], "kod").style;
+        assert!(code.add_modifier.contains(Modifier::ITALIC));
+        assert_eq!(code.fg, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn lists_are_numbered_and_indented() {
+        assert_eq!(
+            text("3. üç\n4. dört\n   - iç\n   - [x] bitti\n\ndevam", 10),
+            ["3. üç", "4. dört", "  • iç", "  • [x] bitti", "", "devam"]
+        );
+    }
+
+    #[test]
+    fn quotes_links_and_code_blocks_are_marked() {
+        assert_eq!(text("> alıntı\n> devam", 10), ["│ alıntı devam"]);
+        assert_eq!(text("> > iç", 10), ["│ │ iç"]);
+        assert_eq!(text("[site](https://example.com)", 10), ["site (https://example.com)"]);
+        assert_eq!(text("```\nfn main() {}\n    girinti\n```", 10), ["  fn main() {}", "      girinti"]);
+    }
+
+    #[test]
+    fn table_columns_are_aligned() {
+        assert_eq!(
+            text("| ad | sayı |\n|:--|--:|\n| elma | 3 |\n| ay | 12 |", 10),
+            ["ad   │ sayı", "─────┼─────", "elma │    3", "ay   │   12"]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use editor_protocol::ops::LineEdit;
+use editor_protocol::{format_document_message, END_OF_MESSAGE_DELIMITER};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+// Günlük dosyasındaki kayıt türleri
+const JOURNAL_BASE: &str = "BASE";
+const JOURNAL_EDIT: &str = "EDIT";
+
+// Bağlantı yokken yapılan düzenlemeler. Hepsi aynı belgeye aittir ve sunucunun
+// bildiği son içerikten (`base`) başlayarak sırayla uygulanır. Günlük dosyası
+// verilmişse her düzenleme oraya da yazılır, böylece istemci kapansa bile kaybolmaz.
+#[derive(Debug, Default)]
+pub struct OfflineQueue {
+    document: Option<String>,
+    base: Vec<String>,
+    edits: Vec<LineEdit>,
+    tip: Vec<String>, // `base` üzerine tüm düzenlemeler uygulanmış hali
+    journal: Option<PathBuf>,
+}
+
+// Yeniden bağlanınca sunucuya sırayla gönderilecek bekleyen düzenlemeler
+pub struct PendingEdits {
+    pub document: String,
+    pub base: Vec<String>,
+    pub edits: Vec<LineEdit>,
+}
+
+impl OfflineQueue {
+    // Günlük dosyası varsa önceki oturumdan kalan düzenlemeleri yükler.
+    pub fn open(journal: Option<PathBuf>) -> io::Result<Self> {
+        let mut queue = OfflineQueue { journal, ..Default::default() };
+        if let Some(path) = queue.journal.clone().filter(|path| path.exists()) {
+            queue.load_journal(BufReader::new(File::open(path)?))?;
+        }
+        Ok(queue)
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    pub fn document(&self) -> Option<&str> {
+        self.document.as_deref()
+    }
+
+    // `content` halini kuyruğa ekler. Kuyruk boşsa `base` sunucunun bildiği son
+    // içerik olarak kaydedilir; başka bir belgeye ait eski düzenlemeler atılır.
+    pub fn push(&mut self, document: &str, base: &[String], content: &[String]) -> io::Result<()> {
+        if self.document.as_deref() != Some(document) {
+            self.clear()?;
+        }
+        if self.edits.is_empty() {
+            self.document = Some(document.to_string());
+            self.base = base.to_vec();
+            self.tip = base.to_vec();
+            self.write_journal(&format_document_message(JOURNAL_BASE, document, &base.join("\n")), false)?;
+        }
+        let Some(edit) = LineEdit::between(&self.tip, content) else {
+            return Ok(());
+        };
+        self.tip = content.to_vec();
+        let header = format!("{} {} {}", edit.start, edit.removed.len(), edit.inserted.len());
+        let body = edit.removed.iter().chain(&edit.inserted).cloned().collect::<Vec<_>>().join("\n");
+        self.edits.push(edit);
+        self.write_journal(&format_document_message(JOURNAL_EDIT, &header, &body), true)
+    }
+
+    // Bekleyen düzenlemeleri kuyruktan (ve günlükten) çıkarır.
+    pub fn take(&mut self) -> io::Result<Option<PendingEdits>> {
+        let pending = match self.document.take() {
+            Some(document) if !self.edits.is_empty() => Some(PendingEdits {
+                document,
+                base: std::mem::take(&mut self.base),
+                edits: std::mem::take(&mut self.edits),
+            }),
+            _ => None,
+        };
+        self.clear()?;
+        Ok(pending)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.document = None;
+        self.base.clear();
+        self.edits.clear();
+        self.tip.clear();
+        match &self.journal {
+            Some(path) if path.exists() => fs::remove_file(path),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_journal(&self, entry: &str, append: bool) -> io::Result<()> {
+        let Some(path) = &self.journal else {
+            return Ok(());
+        };
+        let mut file = OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(path)?;
+        writeln!(file, "{}", entry)?;
+        file.sync_data()
+    }
+
+    // Günlük, protokoldeki gibi END_OF_MESSAGE ile biten kayıtlardan oluşur:
+    // bir `BASE <belge>` ve ardından `EDIT <satır> <silinen> <eklenen>` kayıtları.
+    fn load_journal(&mut self, reader: impl BufRead) -> io::Result<()> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, format!("journal: {}", msg));
+        let mut lines = reader.lines();
+        while let Some(header) = lines.next() {
+            let header = header?;
+            let mut body = Vec::new();
+            for line in lines.by_ref() {
+                let line = line?;
+                if line == END_OF_MESSAGE_DELIMITER {
+                    break;
+                }
+                body.push(line);
+            }
+            let (kind, argument) = header.split_once(' ').unwrap_or((header.as_str(), ""));
+            match kind {
+                JOURNAL_BASE => {
+                    self.document = Some(argument.to_string());
+                    self.base = if body == [""] { Vec::new() } else { body };
+                    self.tip = self.base.clone();
+                    self.edits.clear();
+                }
+                JOURNAL_EDIT => {
+                    let counts: Vec<usize> = argument.split(' ').filter_map(|n| n.parse().ok()).collect();
+                    let [start, removed, inserted] = counts[..] else {
+                        return Err(invalid("bad edit header"));
+                    };
+                    if removed + inserted == 0 || body.len() != removed + inserted {
+                        return Err(invalid("edit body does not match its header"));
+                    }
+                    let edit = LineEdit { start, removed: body[..removed].to_vec(), inserted: body[removed..].to_vec() };
+                    edit.apply(&mut self.tip).map_err(|e| invalid(&e))?;
+                    self.edits.push(edit);
+                }
+                _ => return Err(invalid("unknown entry")),
+            }
+        }
+        if self.document.is_none() {
+            self.edits.clear();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test sonunda silinen geçici günlük dosyası
+    struct TempJournal(PathBuf);
+
+    impl TempJournal {
+        fn new(test: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("editor_journal_{}_{}", std::process::id(), test));
+            let _ = fs::remove_file(&path);
+            TempJournal(path)
+        }
+    }
+
+    impl Drop for TempJournal {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    fn load(journal: &str) -> io::Result<OfflineQueue> {
+        let mut queue = OfflineQueue::default();
+        queue.load_journal(journal.as_bytes())?;
+        Ok(queue)
+    }
+
+    fn replay(pending: &PendingEdits) -> Vec<String> {
+        let mut content = pending.base.clone();
+        for edit in &pending.edits {
+            edit.apply(&mut content).unwrap();
+        }
+        content
+    }
+
+    #[test]
+    fn take_returns_edits_on_top_of_the_base() {
+        let mut queue = OfflineQueue::open(None).unwrap();
+        queue.push("a.txt", &lines("bir\niki"), &lines("bir\nIKI")).unwrap();
+        queue.push("a.txt", &lines("yok sayılır"), &lines("bir\nIKI\nüç")).unwrap();
+        queue.push("a.txt", &lines("yok sayılır"), &lines("bir\nIKI\nüç")).unwrap();
+        assert_eq!(queue.len(), 2);
+
+        let pending = queue.take().unwrap().unwrap();
+        assert_eq!(pending.document, "a.txt");
+        assert_eq!(pending.base, lines("bir\niki"));
+        assert_eq!(replay(&pending), lines("bir\nIKI\nüç"));
+        assert!(queue.is_empty());
+        assert!(queue.take().unwrap().is_none());
+    }
+
+    #[test]
+    fn switching_documents_drops_older_edits() {
+        let mut queue = OfflineQueue::open(None).unwrap();
+        queue.push("a.txt", &lines("a"), &lines("b")).unwrap();
+        queue.push("b.txt", &lines("x"), &lines("y")).unwrap();
+        assert_eq!(queue.document(), Some("b.txt"));
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn journal_survives_a_restart() {
+        let journal = TempJournal::new("restart");
+        let mut queue = OfflineQueue::open(Some(journal.0.clone())).unwrap();
+        queue.push("not defteri.txt", &[], &lines("ilk")).unwrap();
+        queue.push("not defteri.txt", &[], &lines("ilk\n\nson")).unwrap();
+        queue.push("not defteri.txt", &[], &lines("ilk\nson")).unwrap();
+        drop(queue);
+
+        let mut restored = OfflineQueue::open(Some(journal.0.clone())).unwrap();
+        assert_eq!(restored.document(), Some("not defteri.txt"));
+        assert_eq!(restored.len(), 3);
+        let pending = restored.take().unwrap().unwrap();
+        assert!(pending.base.is_empty());
+        assert_eq!(replay(&pending), lines("ilk\nson"));
+        assert!(!journal.0.exists());
+    }
+
+    #[test]
+    fn edits_without_a_base_are_dropped() {
+        let queue = load("EDIT 0 0 1\nx\nEND_OF_MESSAGE\n").unwrap();
+        assert!(queue.is_empty());
+        assert_eq!(queue.document(), None);
+    }
+
+    #[test]
+    fn malformed_journals_are_rejected() {
+        let cases = [
+            "EDIT 0 1",             // eksik sayı
+            "EDIT 0 0 0\n",         // boş düzenleme
+            "EDIT 0 1 1\nx",        // gövde başlıktan kısa
+            "EDIT 0 1 0\ny",        // silinen satır içerikle uyuşmuyor
+            "EDIT 5 0 1\ny",        // belgenin dışında
+            "SIL a.txt\n",          // bilinmeyen kayıt
+        ];
+        for entry in cases {
+            let journal = format!("BASE a.txt\nx\nEND_OF_MESSAGE\n{}\nEND_OF_MESSAGE\n", entry);
+            let error = load(&journal).err().unwrap_or_else(|| panic!("{:?} should fail", entry));
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{:?}", entry);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// İstek kimlikleri tüm thread'ler arasında tekildir (yeniden bağlanırken
+// dinleyici thread de CONNECT gönderir).
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_request_id() -> u64 {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+// Sunucuya gönderilmiş ama henüz ACK ya da ERROR ile yanıtlanmamış bir istek
+#[derive(Debug)]
+pub struct PendingRequest {
+    pub command: String, // Günlükte gösterilecek komut adı
+    pub update: Option<(String, Vec<String>)>, // UPDATE_DOCUMENT ise belge adı ve gönderilen içerik
+    sent_at: Instant,
+}
+
+// Yanıt bekleyen istekler. Süresi dolanlar `expire` ile ayıklanır.
+#[derive(Debug, Default)]
+pub struct RequestTracker {
+    in_flight: HashMap<u64, PendingRequest>,
+    timeout: Duration,
+}
+
+impl RequestTracker {
+    pub fn new(timeout: Duration) -> Self {
+        RequestTracker { in_flight: HashMap::new(), timeout }
+    }
+
+    pub fn track(&mut self, request_id: u64, line: &str, update: Option<(String, Vec<String>)>) {
+        let command = line.split([' ', '\n']).next().unwrap_or_default().to_string();
+        self.in_flight.insert(request_id, PendingRequest { command, update, sent_at: Instant::now() });
+    }
+
+    // Yanıt gelen isteği listeden çıkarır; bu istemcinin göndermediği (ya da
+    // zaten zaman aşımına uğramış) kimlikler için None döner.
+    pub fn complete(&mut self, request_id: &str) -> Option<PendingRequest> {
+        self.in_flight.remove(&request_id.parse().ok()?)
+    }
+
+    // Zaman aşımına uğrayan istekleri çıkarıp döndürür.
+    pub fn expire(&mut self) -> Vec<(u64, PendingRequest)> {
+        let timeout = self.timeout;
+        let expired: Vec<u64> = self
+            .in_flight
+            .iter()
+            .filter(|(_, request)| request.sent_at.elapsed() >= timeout)
+            .map(|(id, _)| *id)
+            .collect();
+        let mut expired: Vec<_> = expired.into_iter().filter_map(|id| Some((id, self.in_flight.remove(&id)?))).collect();
+        expired.sort_by_key(|(id, _)| *id);
+        expired
+    }
+
+    // Bağlantı koptuğunda yanıt gelmeyecek istekleri bırakır.
+    pub fn clear(&mut self) -> usize {
+        let count = self.in_flight.len();
+        self.in_flight.clear();
+        count
+    }
+
+    // Sunucunun henüz onaylamadığı bir belge güncellemesi var mı?
+    pub fn update_in_flight(&self) -> bool {
+        self.in_flight.values().any(|request| request.update.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completed_requests_are_removed() {
+        let mut tracker = RequestTracker::new(Duration::from_secs(60));
+        tracker.track(1, "GET_DOCUMENT a.txt", None);
+        tracker.track(2, "UPDATE_DOCUMENT a.txt\nyeni\nEND_OF_MESSAGE", Some(("a.txt".to_string(), vec!["yeni".to_string()])));
+        assert!(tracker.update_in_flight());
+
+        let request = tracker.complete("2").unwrap();
+        assert_eq!(request.command, "UPDATE_DOCUMENT");
+        assert!(!tracker.update_in_flight());
+        // Aynı yanıt ikinci kez gelirse ya da kimlik tanınmıyorsa
+        assert!(tracker.complete("2").is_none());
+        assert!(tracker.complete("-").is_none());
+        assert!(tracker.expire().is_empty());
+        assert_eq!(tracker.clear(), 1);
+    }
+
+    #[test]
+    fn expired_requests_are_returned_in_order() {
+        let mut tracker = RequestTracker::new(Duration::ZERO);
+        tracker.track(5, "SEARCH kelime", None);
+        tracker.track(3, "HISTORY a.txt", None);
+        let expired: Vec<(u64, String)> = tracker.expire().into_iter().map(|(id, request)| (id, request.command)).collect();
+        assert_eq!(expired, [(3, "HISTORY".to_string()), (5, "SEARCH".to_string())]);
+        assert!(tracker.complete("3").is_none());
+        assert_eq!(tracker.clear(), 0);
+    }
+
+    #[test]
+    fn request_ids_are_unique() {
+        let first = next_request_id();
+        assert!(next_request_id() > first);
+    }
+}
@@ -0,0 +1,255 @@
+use regex::{NoExpand, Regex, RegexBuilder};
+
+// Belgede bulunan bir eşleşme; sütunlar karakter cinsinden, `end` hariç.
+// Eşleşmeler satır sınırını aşmaz.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Arama çubuğunun durumu. Desen her değişiklikte yeniden derlenir; eşleşmeler
+// ise içerik başka kullanıcılarca da değişebildiği için her seferinde
+// güncel satırlar üzerinde aranır.
+#[derive(Debug, Default)]
+pub struct SearchState {
+    pub query: String,
+    pub replacement: String,
+    pub editing_replacement: bool, // Yazılanlar değiştirme alanına mı gidiyor?
+    pub regex: bool,               // Sorgu düzenli ifade olarak mı yorumlansın?
+    pub case_sensitive: bool,
+    pub origin: (usize, usize), // Arama açıldığında imlecin konumu (satır, sütun)
+    pattern: Option<Result<Regex, String>>, // Boş sorguda None
+}
+
+impl SearchState {
+    pub fn new(origin: (usize, usize)) -> Self {
+        SearchState { origin, ..Default::default() }
+    }
+
+    // Yazılan karakteri etkin alana ekler
+    pub fn push(&mut self, c: char) {
+        if self.editing_replacement {
+            self.replacement.push(c);
+        } else {
+            self.query.push(c);
+            self.compile();
+        }
+    }
+
+    pub fn pop(&mut self) {
+        if self.editing_replacement {
+            self.replacement.pop();
+        } else {
+            self.query.pop();
+            self.compile();
+        }
+    }
+
+    pub fn toggle_regex(&mut self) {
+        self.regex = !self.regex;
+        self.compile();
+    }
+
+    pub fn toggle_case_sensitive(&mut self) {
+        self.case_sensitive = !self.case_sensitive;
+        self.compile();
+    }
+
+    fn compile(&mut self) {
+        self.pattern = (!self.query.is_empty()).then(|| {
+            let pattern = if self.regex { self.query.clone() } else { regex::escape(&self.query) };
+            RegexBuilder::new(&pattern)
+                .case_insensitive(!self.case_sensitive)
+                .build()
+                // Sözdizimi hataları deseni de içeren çok satırlı bir metindir; son satır açıklamadır
+                .map_err(|e| e.to_string().lines().last().unwrap_or_default().to_string())
+        });
+    }
+
+    // Geçersiz bir düzenli ifade yazıldıysa hata metni
+    pub fn error(&self) -> Option<&str> {
+        match &self.pattern {
+            Some(Err(e)) => Some(e),
+            _ => None,
+        }
+    }
+
+    fn regex(&self) -> Option<&Regex> {
+        self.pattern.as_ref()?.as_ref().ok()
+    }
+
+    pub fn find_all(&self, lines: &[String]) -> Vec<SearchMatch> {
+        let Some(regex) = self.regex() else {
+            return Vec::new();
+        };
+        lines
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                regex.find_iter(line).map(move |m| SearchMatch {
+                    row,
+                    start: byte_to_char_index(line, m.start()),
+                    end: byte_to_char_index(line, m.end()),
+                })
+            })
+            .collect()
+    }
+
+    // Tek bir eşleşmeyi değiştirir; düzenli ifade kipinde `$1` gibi gruplar
+    // açılır. Değiştirilen metnin bittiği sütunu döndürür.
+    pub fn replace_one(&self, lines: &mut [String], found: SearchMatch) -> Option<usize> {
+        let regex = self.regex()?;
+        let line = lines.get_mut(found.row)?;
+        let start = char_to_byte_index(line, found.start);
+        let captures = regex.captures_at(line, start).filter(|c| c.get(0).is_some_and(|m| m.start() == start))?;
+        let whole = captures.get(0)?;
+        let mut replacement = String::new();
+        if self.regex {
+            captures.expand(&self.replacement, &mut replacement);
+        } else {
+            replacement.push_str(&self.replacement);
+        }
+        let end = whole.end();
+        line.replace_range(start..end, &replacement);
+        Some(found.start + replacement.chars().count())
+    }
+
+    // Tüm eşleşmeleri değiştirir ve kaç tane değiştiğini döndürür
+    pub fn replace_all(&self, lines: &mut [String]) -> usize {
+        let Some(regex) = self.regex() else {
+            return 0;
+        };
+        let mut count = 0;
+        for line in lines.iter_mut() {
+            let found = regex.find_iter(line).count();
+            if found == 0 {
+                continue;
+            }
+            count += found;
+            let replaced = if self.regex {
+                regex.replace_all(line, self.replacement.as_str())
+            } else {
+                regex.replace_all(line, NoExpand(&self.replacement))
+            };
+            *line = replaced.into_owned();
+        }
+        count
+    }
+}
+
+// Verilen konumdan sonraki (`inclusive` ise konumdaki dahil) ilk eşleşme;
+// belgenin sonuna gelince başa döner.
+pub fn next_match(matches: &[SearchMatch], (row, col): (usize, usize), inclusive: bool) -> Option<SearchMatch> {
+    matches
+        .iter()
+        .find(|m| (m.row, m.start) > (row, col) || (inclusive && (m.row, m.start) == (row, col)))
+        .or_else(|| matches.first())
+        .copied()
+}
+
+// Verilen konumdan önceki ilk eşleşme; belgenin başına gelince sona döner.
+pub fn previous_match(matches: &[SearchMatch], (row, col): (usize, usize)) -> Option<SearchMatch> {
+    matches.iter().rev().find(|m| (m.row, m.start) < (row, col)).or_else(|| matches.last()).copied()
+}
+
+fn byte_to_char_index(line: &str, byte_index: usize) -> usize {
+    line[..byte_index].chars().count()
+}
+
+fn char_to_byte_index(line: &str, char_index: usize) -> usize {
+    line.char_indices().nth(char_index).map_or(line.len(), |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn search(query: &str) -> SearchState {
+        let mut search = SearchState::new((0, 0));
+        query.chars().for_each(|c| search.push(c));
+        search
+    }
+
+    fn positions(matches: &[SearchMatch]) -> Vec<(usize, usize, usize)> {
+        matches.iter().map(|m| (m.row, m.start, m.end)).collect()
+    }
+
+    #[test]
+    fn matches_are_found_in_characters_ignoring_case() {
+        let content = lines(&["Şehir ve şehir", "yok", "ŞEHIR"]);
+        assert_eq!(positions(&search("şehir").find_all(&content)), [(0, 0, 5), (0, 9, 14), (2, 0, 5)]);
+        let mut exact = search("şehir");
+        exact.toggle_case_sensitive();
+        assert_eq!(positions(&exact.find_all(&content)), [(0, 9, 14)]);
+    }
+
+    #[test]
+    fn plain_queries_are_not_regular_expressions() {
+        let content = lines(&["a.b axb"]);
+        assert_eq!(positions(&search("a.b").find_all(&content)), [(0, 0, 3)]);
+        let mut regex = search("a.b");
+        regex.toggle_regex();
+        assert_eq!(positions(&regex.find_all(&content)), [(0, 0, 3), (0, 4, 7)]);
+    }
+
+    #[test]
+    fn invalid_regex_reports_an_error_and_finds_nothing() {
+        let mut regex = search("(a");
+        assert!(regex.error().is_none());
+        regex.toggle_regex();
+        assert!(regex.error().is_some());
+        assert!(regex.find_all(&lines(&["(a"])).is_empty());
+        regex.pop();
+        regex.pop();
+        assert!(regex.error().is_none());
+    }
+
+    #[test]
+    fn next_and_previous_match_wrap_around() {
+        let matches = search("x").find_all(&lines(&["x x", "", "x"]));
+        let at = |found: Option<SearchMatch>| found.map(|m| (m.row, m.start));
+        assert_eq!(at(next_match(&matches, (0, 0), true)), Some((0, 0)));
+        assert_eq!(at(next_match(&matches, (0, 0), false)), Some((0, 2)));
+        assert_eq!(at(next_match(&matches, (1, 0), false)), Some((2, 0)));
+        assert_eq!(at(next_match(&matches, (2, 0), false)), Some((0, 0)));
+        assert_eq!(at(previous_match(&matches, (2, 0))), Some((0, 2)));
+        assert_eq!(at(previous_match(&matches, (0, 0))), Some((2, 0)));
+        assert_eq!(next_match(&[], (0, 0), true), None);
+    }
+
+    #[test]
+    fn replace_one_expands_groups_only_in_regex_mode() {
+        let mut content = lines(&["ad: Ayşe"]);
+        let mut regex = search(r"ad: (\w+)");
+        regex.toggle_regex();
+        regex.editing_replacement = true;
+        "isim=$1".chars().for_each(|c| regex.push(c));
+        let found = regex.find_all(&content)[0];
+        assert_eq!(regex.replace_one(&mut content, found), Some(9));
+        assert_eq!(content, ["isim=Ayşe"]);
+
+        let mut content = lines(&["ad"]);
+        let mut plain = search("ad");
+        plain.editing_replacement = true;
+        "$1".chars().for_each(|c| plain.push(c));
+        let found = plain.find_all(&content)[0];
+        plain.replace_one(&mut content, found);
+        assert_eq!(content, ["$1"]);
+    }
+
+    #[test]
+    fn replace_all_counts_every_match() {
+        let mut content = lines(&["a a", "b", "A"]);
+        let mut search = search("a");
+        search.editing_replacement = true;
+        search.push('o');
+        assert_eq!(search.replace_all(&mut content), 3);
+        assert_eq!(content, ["o o", "b", "o"]);
+    }
+}
@@ -1,103 +1,286 @@
-use crate::event::{AppEvent, ServerCommand};
+use crate::event::{AppEvent, RevisionEntry, SearchHit, ServerCommand};
+use crate::requests::next_request_id;
 use editor_protocol::*;
-use std::io::{BufRead, BufReader};
-use std::net::TcpStream;
+use editor_protocol::diff::parse_diff_lines;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{Shutdown, TcpStream};
 use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Yeniden bağlanma denemeleri arasındaki ilk ve en uzun bekleme
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(10);
 
 pub fn start_server_listener_thread(
     stream_reader: TcpStream, // Klonlanmış ve sadece okuma için olan stream
+    stream_writer: Arc<Mutex<TcpStream>>, // Yeniden bağlanınca içindeki stream değiştirilir
+    username: String,
+    keepalive_timeout: Duration, // Bu süre boyunca sunucudan bir şey gelmezse bağlantı ölü sayılır
     event_tx: Sender<AppEvent>,
 ) -> std::thread::JoinHandle<()> {
     std::thread::spawn(move || {
         let mut reader = BufReader::new(stream_reader);
+        let mut resume_token: Option<String> = None;
         loop {
-            let mut line_from_server = String::new();
-            match reader.read_line(&mut line_from_server) {
-                Ok(0) => { // Bağlantı kapandı
-                    let _ = event_tx.send(AppEvent::ServerMessage(ServerCommand::Error(
-                        "Sunucu bağlantısı kesildi.".to_string(),
-                    )));
-                    break;
+            // Yazma zaman aşımı, ölü bir bağlantıya yazarken arayüzün donmasını önler
+            let stream = reader.get_ref();
+            let error = match stream
+                .set_read_timeout(Some(keepalive_timeout))
+                .and_then(|_| stream.set_write_timeout(Some(keepalive_timeout)))
+                .and_then(|_| read_server_messages(&mut reader, &stream_writer, &event_tx, &mut resume_token))
+            {
+                Ok(()) => break, // Ana thread kapandı ya da yönetici bağlantıyı kesti
+                Err(e) => e,
+            };
+            let reason = match error.kind() {
+                ErrorKind::UnexpectedEof => t!(ConnectionClosed),
+                ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+                    t!(ServerNotResponding, keepalive_timeout.as_secs())
                 }
-                Ok(_) => {
-                    let server_message = line_from_server.trim();
-                    if server_message.is_empty() { continue; }
-
-                    let parts: Vec<&str> = server_message.splitn(2, ' ').collect();
-                    let command = parts[0];
-                    let payload = if parts.len() > 1 { parts[1].to_string() } else { String::new() };
-
-                    let app_event_payload = match command {
-                        DOCUMENTS_LIST_MSG => {
-                            let docs = if payload.is_empty() { Vec::new() } else { payload.split(',').map(String::from).collect() };
-                            ServerCommand::UpdateDocumentList(docs)
-                        }
-                        DOCUMENT_CONTENT_MSG => {
-                            let doc_name = payload.clone(); // payload'u sonra kullanacağız
-                            let mut content_buffer = String::new();
-                            loop {
-                                let mut content_line = String::new();
-                                match reader.read_line(&mut content_line) {
-                                    Ok(0) => break, // Beklenmedik bağlantı kesilmesi
-                                    Ok(_) => {
-                                        if content_line.trim() == END_OF_MESSAGE_DELIMITER {
-                                            break;
-                                        }
-                                        content_buffer.push_str(&content_line);
-                                    }
-                                    Err(e) => {
-                                        let _ = event_tx.send(AppEvent::ServerMessage(ServerCommand::Error(format!("İçerik okunurken hata: {}", e))));
-                                        return; // Thread'i sonlandır
-                                    }
-                                }
-                            }
-                            if content_buffer.ends_with('\n') { content_buffer.pop(); } // Son newline karakterini kaldır
-                            ServerCommand::ReceiveDocumentContent { name: doc_name, content: content_buffer }
-                        }
-                        DOCUMENT_UPDATED_MSG => {
-                            let doc_name = payload.clone();
-                            let mut content_buffer = String::new();
-                             loop {
-                                let mut content_line = String::new();
-                                match reader.read_line(&mut content_line) {
-                                    Ok(0) => break,
-                                    Ok(_) => {
-                                        if content_line.trim() == END_OF_MESSAGE_DELIMITER {
-                                            break;
-                                        }
-                                        content_buffer.push_str(&content_line);
-                                    }
-                                    Err(e) => {
-                                         let _ = event_tx.send(AppEvent::ServerMessage(ServerCommand::Error(format!("Güncelleme okunurken hata: {}", e))));
-                                        return;
-                                    }
-                                }
-                            }
-                            if content_buffer.ends_with('\n') { content_buffer.pop(); }
-                            ServerCommand::UpdateDocumentContent{ name: doc_name, content: content_buffer }
-                        }
-                        USER_JOINED_MSG => ServerCommand::UserJoined(payload),
-                        USER_LEFT_MSG => ServerCommand::UserLeft(payload),
-                        NEW_DOCUMENT_AVAILABLE_MSG => ServerCommand::NewDocumentAvailable(payload),
-                        DOCUMENT_SWITCHED_MSG => ServerCommand::SwitchedToDocument { name: payload },
-                        CONNECTED_OK_MSG => ServerCommand::Status("Sunucuya başarıyla bağlanıldı!".to_string()),
-                        DOCUMENT_CREATED_OK_MSG => ServerCommand::Status(format!("'{}' belgesi sunucuda oluşturuldu.", payload)),
-                        DOCUMENT_CREATED_FAIL_MSG => ServerCommand::Error(format!("Belge oluşturma hatası: {}", payload)),
-                        _ => ServerCommand::Status(format!("[SUNUCU BİLİNMEYEN]: {}", server_message)),
-                    };
-
-                    if event_tx.send(AppEvent::ServerMessage(app_event_payload)).is_err() {
-                        // Ana thread muhtemelen kapandı, bu thread'i de sonlandır
-                        break;
-                    }
+                _ => t!(ReadFailed, error),
+            };
+            // Yarı açık kalmış bağlantıyı kapat
+            let _ = reader.get_ref().shutdown(Shutdown::Both);
+            if event_tx.send(AppEvent::ServerMessage(ServerCommand::ConnectionLost(reason))).is_err() {
+                break;
+            }
+            match reconnect(&stream_writer, &username, resume_token.as_deref(), &event_tx) {
+                Some(stream) => reader = BufReader::new(stream),
+                None => break,
+            }
+        }
+    })
+}
+
+// Bağlantı kopana kadar sunucu mesajlarını okuyup ana thread'e iletir.
+// Ok(()) ana thread'in artık dinlemediği ya da bir yöneticinin istemciyi
+// attığı (yeniden bağlanılmamalı) anlamına gelir.
+fn read_server_messages(
+    reader: &mut BufReader<TcpStream>,
+    stream_writer: &Arc<Mutex<TcpStream>>,
+    event_tx: &Sender<AppEvent>,
+    resume_token: &mut Option<String>,
+) -> io::Result<()> {
+    loop {
+        let mut line_from_server = String::new();
+        if reader.read_line(&mut line_from_server)? == 0 {
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+        let server_message = line_from_server.trim();
+        if server_message.is_empty() { continue; }
+
+        let parts: Vec<&str> = server_message.splitn(2, ' ').collect();
+        let command = parts[0];
+        let payload = if parts.len() > 1 { parts[1].to_string() } else { String::new() };
+
+        let app_event_payload = match command {
+            DOCUMENTS_LIST_MSG => {
+                let docs = if payload.is_empty() { Vec::new() } else { payload.split(',').map(String::from).collect() };
+                ServerCommand::UpdateDocumentList(docs)
+            }
+            DOCUMENT_CONTENT_MSG => ServerCommand::ReceiveDocumentContent { name: payload, content: read_message_body(reader)? },
+            DOCUMENT_UPDATED_MSG => ServerCommand::UpdateDocumentContent { name: payload, content: read_message_body(reader)? },
+            HISTORY_LIST_MSG => ServerCommand::HistoryList { revisions: parse_history_list(&read_message_body(reader)?), name: payload },
+            REVISION_CONTENT_MSG => {
+                let content = read_message_body(reader)?;
+                match parse_revision_argument(&payload) {
+                    Some((name, number)) => ServerCommand::RevisionContent { name: name.to_string(), number, content },
+                    None => invalid_message(t!(InvalidRevisionHeader, payload)),
+                }
+            }
+            DIFF_RESULT_MSG => {
+                let body = read_message_body(reader)?;
+                match parse_diff_argument(&payload) {
+                    Some((name, rev_a, rev_b)) => ServerCommand::DiffResult {
+                        name: name.to_string(),
+                        rev_a: rev_a.to_string(),
+                        rev_b: rev_b.to_string(),
+                        lines: parse_diff_lines(&body),
+                    },
+                    None => invalid_message(t!(InvalidDiffHeader, payload)),
                 }
-                Err(e) => {
-                    let _ = event_tx.send(AppEvent::ServerMessage(ServerCommand::Error(format!(
-                        "Sunucudan okuma hatası: {}", e
-                    ))));
-                    break; // Hata durumunda döngüden çık
+            }
+            BLAME_INFO_MSG => {
+                let body = read_message_body(reader)?;
+                ServerCommand::BlameInfo { name: payload, authors: body.lines().map(String::from).collect() }
+            }
+            SEARCH_RESULTS_MSG => ServerCommand::SearchResults { hits: parse_search_results(&read_message_body(reader)?), query: payload },
+            PING_CMD => {
+                // Sunucunun canlılık kontrolüne doğrudan yanıt ver
+                let mut writer_guard = stream_writer.lock().unwrap();
+                writeln!(writer_guard, "{}", format_command_with_arg(PONG_MSG, &payload).trim_end())?;
+                writer_guard.flush()?;
+                continue;
+            }
+            PONG_MSG => match payload.parse::<u64>() {
+                Ok(sent_at) => ServerCommand::Latency(Duration::from_millis(unix_millis().saturating_sub(sent_at))),
+                Err(_) => continue,
+            },
+            SERVER_SHUTTING_DOWN_MSG => ServerCommand::ServerShuttingDown(payload),
+            KICKED_MSG => {
+                let _ = event_tx.send(AppEvent::ServerMessage(ServerCommand::Kicked(payload)));
+                let _ = reader.get_ref().shutdown(Shutdown::Both);
+                return Ok(());
+            }
+            ANNOUNCEMENT_MSG => ServerCommand::Announcement(payload),
+            USER_JOINED_MSG => ServerCommand::UserJoined(payload),
+            USER_LEFT_MSG => ServerCommand::UserLeft(payload),
+            USER_SWITCHED_MSG => match parse_user_switched(&payload) {
+                Some((username, document)) => ServerCommand::UserSwitched { username: username.to_string(), document: document.to_string() },
+                None => invalid_message(t!(UnknownServerMessage, server_message)),
+            },
+            NEW_DOCUMENT_AVAILABLE_MSG => ServerCommand::NewDocumentAvailable(payload),
+            DOCUMENT_SWITCHED_MSG => ServerCommand::SwitchedToDocument { name: payload },
+            CONNECTED_OK_MSG => {
+                // Yeniden bağlanırken oturuma devam edebilmek için jetonu sakla
+                if !payload.is_empty() {
+                    *resume_token = Some(payload);
                 }
+                ServerCommand::Connected
+            }
+            DOCUMENT_CREATED_OK_MSG => ServerCommand::Status(t!(DocumentCreated, payload)),
+            ACK_MSG => ServerCommand::Ack(payload),
+            ERROR_MSG => {
+                let (code, request_id, message) = parse_error(&payload);
+                ServerCommand::Error { code, request_id: request_id.map(String::from), message: message.to_string() }
             }
+            _ => ServerCommand::Status(t!(UnknownServerMessage, server_message)),
+        };
+
+        if event_tx.send(AppEvent::ServerMessage(app_event_payload)).is_err() {
+            // Ana thread muhtemelen kapandı, bu thread'i de sonlandır
+            return Ok(());
         }
+    }
+}
+
+// Her `interval`de sunucuya gönderim zamanını taşıyan bir PING yollar; gelen
+// PONG ile gidiş-dönüş gecikmesi ölçülür. Bağlantı yokken yazma hataları yok sayılır.
+pub fn start_heartbeat_thread(stream_writer: Arc<Mutex<TcpStream>>, interval: Duration) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || loop {
+        thread::sleep(interval);
+        let mut writer_guard = stream_writer.lock().unwrap();
+        let _ = writeln!(writer_guard, "{} {}", PING_CMD, unix_millis());
+        let _ = writer_guard.flush();
     })
-}
\ No newline at end of file
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+// Artan bekleme süreleriyle sunucuya yeniden bağlanmayı dener. Başarılı olunca
+// yazma tarafını yeni bağlantıyla değiştirir, CONNECT'i devam jetonuyla gönderir
+// ve okuma için stream'i döndürür. Ana thread kapandıysa None döner.
+fn reconnect(
+    stream_writer: &Arc<Mutex<TcpStream>>,
+    username: &str,
+    resume_token: Option<&str>,
+    event_tx: &Sender<AppEvent>,
+) -> Option<TcpStream> {
+    let mut delay = RECONNECT_INITIAL_DELAY;
+    for attempt in 1.. {
+        let status = t!(Reconnecting, format!("{:.1}", delay.as_secs_f32()), attempt);
+        event_tx.send(AppEvent::ServerMessage(ServerCommand::Status(status))).ok()?;
+        thread::sleep(delay);
+        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+
+        let Ok(stream) = TcpStream::connect(format!("{}:{}", CLIENT_CONNECT_ADDRESS, PORT)) else {
+            continue;
+        };
+        let (Ok(reader_stream), Ok(writer_stream)) = (stream.try_clone(), stream.try_clone()) else {
+            continue;
+        };
+        let mut writer_guard = stream_writer.lock().unwrap();
+        *writer_guard = writer_stream;
+        let connect_line = match resume_token {
+            Some(token) => format!("{} {} {}", CONNECT_CMD, username, token),
+            None => format!("{} {}", CONNECT_CMD, username),
+        };
+        if writeln!(writer_guard, "{}", format_request(next_request_id(), &connect_line)).and_then(|_| writer_guard.flush()).is_ok() {
+            return Some(reader_stream);
+        }
+    }
+    None
+}
+
+// Çok satırlı bir mesajın gövdesini END_OF_MESSAGE satırına kadar okur.
+fn read_message_body(reader: &mut BufReader<TcpStream>) -> io::Result<String> {
+    let mut content_buffer = String::new();
+    loop {
+        let mut content_line = String::new();
+        if reader.read_line(&mut content_line)? == 0 {
+            // Mesajın ortasında bağlantı kesildi
+            return Err(io::Error::from(ErrorKind::UnexpectedEof));
+        }
+        if content_line.trim() == END_OF_MESSAGE_DELIMITER {
+            break;
+        }
+        content_buffer.push_str(&content_line);
+    }
+    if content_buffer.ends_with('\n') { content_buffer.pop(); } // Son newline karakterini kaldır
+    Ok(content_buffer)
+}
+
+// HISTORY_LIST gövdesindeki `<numara>\t<zaman damgası>\t<yazar>` satırlarını ayrıştırır.
+fn parse_history_list(body: &str) -> Vec<RevisionEntry> {
+    body.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some(RevisionEntry {
+                number: fields.next()?.parse().ok()?,
+                timestamp: fields.next()?.parse().ok()?,
+                author: fields.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+// SEARCH_RESULTS gövdesindeki `<belge>\t<satır>\t<kesit>` satırlarını ayrıştırır.
+fn parse_search_results(body: &str) -> Vec<SearchHit> {
+    body.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some(SearchHit {
+                document: fields.next()?.to_string(),
+                line: fields.next()?.parse().ok()?,
+                snippet: fields.next().unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+// Sunucudan gelen ama çözümlenemeyen bir mesajı hata olarak bildirir.
+fn invalid_message(message: String) -> ServerCommand {
+    ServerCommand::Error { code: ErrorCode::InvalidMessage, request_id: None, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_list_skips_malformed_lines() {
+        let entries = parse_history_list("1\t1700000000\tayse\nbozuk\n2\t1700000100\tmehmet,ayse\n3\t\tx");
+        let summary: Vec<(u64, u64, &str)> =
+            entries.iter().map(|entry| (entry.number, entry.timestamp, entry.author.as_str())).collect();
+        assert_eq!(summary, [(1, 1700000000, "ayse"), (2, 1700000100, "mehmet,ayse")]);
+    }
+
+    #[test]
+    fn history_list_allows_a_missing_author() {
+        let entries = parse_history_list("4\t1700000000");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].author, "");
+    }
+
+    #[test]
+    fn search_results_skip_malformed_lines() {
+        let hits = parse_search_results("my notes.txt\t3\t…ortak düzenleme\tsonu\nbozuk\na.txt\tx\tkesit\nb.txt\t7");
+        let summary: Vec<(&str, usize, &str)> =
+            hits.iter().map(|hit| (hit.document.as_str(), hit.line, hit.snippet.as_str())).collect();
+        assert_eq!(summary, [("my notes.txt", 3, "…ortak düzenleme\tsonu"), ("b.txt", 7, "")]);
+    }
+}
@@ -1,12 +1,18 @@
-use crate::app_state::{AppState, ActiveWindow};
+use crate::app_state::{AppState, ActiveWindow, DiffView};
+use crate::markdown;
+use crate::search::SearchMatch;
+use editor_protocol::diff::{diff_words, DiffLine, DiffTag, WordSpan};
+use editor_protocol::UNKNOWN_AUTHOR;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Tabs, Wrap},
     Frame,
 };
 
+const MAX_BLAME_GUTTER_WIDTH: usize = 12;
+
 pub fn draw_ui(frame: &mut Frame, app_state: &mut AppState) {
     let main_chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -28,11 +34,78 @@ pub fn draw_ui(frame: &mut Frame, app_state: &mut AppState) {
         ])
         .split(main_chunks[1]);
 
-    draw_document_content_panel(frame, app_state, middle_chunks[0]);
+    // Açık belgeler içerik alanının üstünde sekme olarak listelenir
+    let content_area = if app_state.tabs.is_empty() {
+        middle_chunks[0]
+    } else {
+        let tab_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(middle_chunks[0]);
+        draw_tabs_bar(frame, app_state, tab_chunks[0]);
+        tab_chunks[1]
+    };
+
+    // Markdown önizlemesi açıksa içerik alanı ikiye bölünür
+    let (content_area, markdown_area) = if app_state.show_markdown_preview && app_state.diff_view.is_none() {
+        let preview_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(content_area);
+        (preview_chunks[0], Some(preview_chunks[1]))
+    } else {
+        (content_area, None)
+    };
+
+    match (&app_state.diff_view, app_state.search.is_some()) {
+        (Some(diff_view), _) => draw_diff_panel(frame, diff_view, content_area),
+        (None, true) => {
+            let content_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(4)])
+                .split(content_area);
+            draw_panes(frame, app_state, content_chunks[0]);
+            draw_search_panel(frame, app_state, content_chunks[1]);
+        }
+        (None, false) => draw_panes(frame, app_state, content_area),
+    }
+    if let Some(area) = markdown_area {
+        draw_markdown_preview_panel(frame, app_state, area);
+    }
     draw_command_input_panel(frame, app_state, middle_chunks[1]);
     draw_event_log_panel(frame, app_state, middle_chunks[2]);
 
-    draw_users_panel(frame, app_state, main_chunks[2]);
+    // Sağ sütun: kullanıcılar ve açıksa geçmiş ile arama sonuçları panelleri
+    let side_panels = u16::from(app_state.history.is_some()) + u16::from(app_state.search_results.is_some());
+    if side_panels > 0 {
+        let mut constraints = vec![Constraint::Percentage(30)];
+        constraints.extend((0..side_panels).map(|_| Constraint::Percentage(70 / side_panels)));
+        let right_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(main_chunks[2]);
+        draw_users_panel(frame, app_state, right_chunks[0]);
+        let mut next_chunk = 1;
+        if app_state.history.is_some() {
+            draw_history_panel(frame, app_state, right_chunks[next_chunk]);
+            next_chunk += 1;
+        }
+        if app_state.search_results.is_some() {
+            draw_search_results_panel(frame, app_state, right_chunks[next_chunk]);
+        }
+    } else {
+        draw_users_panel(frame, app_state, main_chunks[2]);
+    }
+}
+
+fn draw_tabs_bar(frame: &mut Frame, app_state: &AppState, area: Rect) {
+    let titles: Vec<Line> = app_state.tabs.iter().map(|tab| Line::from(tab.name.clone())).collect();
+    let tabs = Tabs::new(titles)
+        .select(app_state.active_tab_index().unwrap_or(0))
+        .style(Style::default().fg(Color::DarkGray))
+        .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+        .divider("│");
+    frame.render_widget(tabs, area);
 }
 
 fn draw_documents_panel(frame: &mut Frame, app_state: &mut AppState, area: Rect) {
@@ -52,7 +125,7 @@ fn draw_documents_panel(frame: &mut Frame, app_state: &mut AppState, area: Rect)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Belgeler (TAB ile geçiş)")
+                .title(t!(DocumentsTitle))
                 .border_style(border_style),
         )
         .highlight_style(
@@ -76,34 +149,492 @@ fn draw_users_panel(frame: &mut Frame, app_state: &AppState, area: Rect) {
     let list = List::new(items).block(
         Block::default()
             .borders(Borders::ALL)
-            .title("Aktif Kullanıcılar")
+            .title(t!(UsersTitle))
             .border_style(Style::default().fg(Color::DarkGray)),
     );
     frame.render_widget(list, area);
 }
 
-fn draw_document_content_panel(frame: &mut Frame, app_state: &AppState, area: Rect) {
-    let title = match &app_state.current_document_name {
-        Some(name) => format!("İçerik: {} ", name),
-        None => "İçerik (Belge Seçilmedi) ".to_string(),
+fn draw_history_panel(frame: &mut Frame, app_state: &mut AppState, area: Rect) {
+    let border_style = if app_state.active_window == ActiveWindow::History {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::DarkGray)
     };
-    let text: Vec<Line> = app_state
-        .current_document_content
+    let Some(history) = app_state.history.as_mut() else { return };
+
+    let items: Vec<ListItem> = history
+        .revisions
         .iter()
-        .map(|line| Line::from(Span::raw(line.clone())))
+        .map(|revision| {
+            let author = if revision.author.is_empty() { "?" } else { revision.author.as_str() };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("#{} ", revision.number), Style::default().fg(Color::Cyan)),
+                Span::raw(format!("{} ", format_timestamp(revision.timestamp))),
+                Span::styled(author.to_string(), Style::default().fg(Color::Green)),
+            ]))
+        })
         .collect();
 
-    let paragraph = Paragraph::new(text)
+    let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(title)
+                .title(t!(HistoryTitle, history.document_name))
+                .border_style(border_style),
+        )
+        .highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .bg(Color::Gray)
+                .fg(Color::Black),
+        )
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut history.list_state);
+}
+
+fn draw_search_results_panel(frame: &mut Frame, app_state: &mut AppState, area: Rect) {
+    let border_style = if app_state.active_window == ActiveWindow::SearchResults {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+    let Some(results) = app_state.search_results.as_mut() else { return };
+
+    let items: Vec<ListItem> = results
+        .hits
+        .iter()
+        .map(|hit| {
+            ListItem::new(vec![
+                Line::from(vec![
+                    Span::styled(hit.document.clone(), Style::default().fg(Color::Cyan)),
+                    Span::styled(format!(":{}", hit.line), Style::default().fg(Color::Green)),
+                ]),
+                Line::from(Span::raw(format!("  {}", hit.snippet))),
+            ])
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(t!(SearchResultsTitle, results.query, results.hits.len()))
+                .border_style(border_style),
+        )
+        .highlight_style(
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .bg(Color::Gray)
+                .fg(Color::Black),
+        )
+        .highlight_symbol(">> ");
+
+    frame.render_stateful_widget(list, area, &mut results.list_state);
+}
+
+// İçerik alanı bölündüyse bölmeleri eşit paylarla yerleştirir; odaklı bölme
+// düzenlenebilir içerik panelidir.
+fn draw_panes(frame: &mut Frame, app_state: &mut AppState, area: Rect) {
+    if app_state.panes.is_empty() {
+        draw_document_content_panel(frame, app_state, area);
+        return;
+    }
+    let count = app_state.panes.len() as u32;
+    let pane_areas = Layout::default()
+        .direction(app_state.split_direction)
+        .constraints((0..count).map(|_| Constraint::Ratio(1, count)))
+        .split(area);
+    for (index, pane_area) in pane_areas.iter().enumerate() {
+        if index == app_state.focused_pane {
+            draw_document_content_panel(frame, app_state, *pane_area);
+        } else {
+            draw_pane_panel(frame, app_state, index, *pane_area);
+        }
+    }
+}
+
+// Odakta olmayan bir bölme: belgesini kendi kaydırma konumunda, salt okunur gösterir.
+fn draw_pane_panel(frame: &mut Frame, app_state: &mut AppState, index: usize, area: Rect) {
+    // Önbellek bölmeden geçici olarak alınır; satırlar AppState'ten ödünç alınırken kullanılabilsin
+    let mut highlighter = std::mem::take(&mut app_state.panes[index].highlighter);
+    let document = app_state.panes[index].document.clone();
+    let lines = app_state.pane_lines(document.as_deref());
+    let height = area.height.saturating_sub(2) as usize;
+    let number_width = lines.len().max(1).to_string().len();
+    let gutter_width = if app_state.show_line_numbers { number_width + 1 } else { 0 };
+    let width = (area.width.saturating_sub(2) as usize).saturating_sub(gutter_width);
+    let (scroll_row, scroll_col) = app_state.panes[index].scroll;
+    let scroll_row = scroll_row.min(lines.len().saturating_sub(height));
+
+    let styled = document.as_deref().and_then(|name| highlighter.highlight(name, lines, scroll_row + height));
+    let text: Vec<Line> = lines
+        .iter()
+        .enumerate()
+        .skip(scroll_row)
+        .take(height)
+        .map(|(row, line)| {
+            let mut spans = Vec::with_capacity(2);
+            if app_state.show_line_numbers {
+                spans.push(line_number_span(row, number_width, false));
+            }
+            let base = styled
+                .and_then(|styled| styled.get(row).cloned())
+                .unwrap_or_else(|| vec![Span::raw(line.clone())]);
+            spans.extend(clip_spans(base, scroll_col, width));
+            Line::from(spans)
+        })
+        .collect();
+    let title = match &document {
+        Some(name) => t!(ContentTitle, name),
+        None => t!(NoDocumentTitle),
+    };
+    let paragraph = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+    frame.render_widget(paragraph, area);
+    draw_content_scrollbar(frame, area, lines.len(), height, scroll_row);
+    app_state.panes[index].highlighter = highlighter;
+}
+
+fn line_number_span(row: usize, width: usize, current: bool) -> Span<'static> {
+    let style = if current { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::DarkGray) };
+    Span::styled(format!("{:>width$} ", row + 1, width = width), style)
+}
+
+// Panele sığmayan belgelerde sağ kenarda kaydırma çubuğu
+fn draw_content_scrollbar(frame: &mut Frame, area: Rect, line_count: usize, height: usize, position: usize) {
+    if line_count > height {
+        let mut scrollbar_state = ScrollbarState::new(line_count - height + 1)
+            .viewport_content_length(height)
+            .position(position);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight).begin_symbol(None).end_symbol(None),
+            area.inner(&Margin { vertical: 1, horizontal: 0 }),
+            &mut scrollbar_state,
+        );
+    }
+}
+
+fn draw_document_content_panel(frame: &mut Frame, app_state: &mut AppState, area: Rect) {
+    let preview_scroll = app_state.history.as_ref().and_then(|h| h.preview.as_ref().map(|_| h.preview_scroll));
+    let line_count = match app_state.history.as_ref().and_then(|h| h.preview.as_ref()) {
+        Some((_, lines)) => lines.len(),
+        None => app_state.current_document_content.len(),
+    };
+    // Yazar sütunu yalnızca canlı içerikte anlamlı
+    let show_blame = app_state.show_blame && preview_scroll.is_none();
+    let blame_width = app_state
+        .line_authors
+        .iter()
+        .map(|author| author.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp(UNKNOWN_AUTHOR.len(), MAX_BLAME_GUTTER_WIDTH);
+    let number_width = line_count.max(1).to_string().len();
+    let mut gutter_width = 0;
+    if app_state.show_line_numbers {
+        gutter_width += number_width + 1;
+    }
+    if show_blame {
+        gutter_width += blame_width + 3;
+    }
+
+    // Kenarlıkların içinde kalan alan. Canlı içerikte görünen kısım imleci
+    // izler; önizleme geçmiş panelinden kaydırılır.
+    let height = area.height.saturating_sub(2) as usize;
+    let width = (area.width.saturating_sub(2) as usize).saturating_sub(gutter_width);
+    app_state.content_height = height;
+    let (scroll_row, scroll_col) = match (preview_scroll, app_state.history.as_mut()) {
+        (Some(scroll), Some(history)) => {
+            history.preview_scroll = scroll.min(line_count.saturating_sub(height));
+            (history.preview_scroll, 0)
+        }
+        _ => {
+            app_state.scroll_to_cursor(height, width);
+            (app_state.scroll_row, app_state.scroll_col)
+        }
+    };
+    // Canlı içerikte görünen satırlar belgenin uzantısına göre renklendirilir;
+    // bilinmeyen türler düz metin kalır
+    let syntax_lines: Option<Vec<Vec<Span<'static>>>> = match (&app_state.current_document_name, preview_scroll) {
+        (Some(name), None) => app_state
+            .highlighter
+            .highlight(name, &app_state.current_document_content, scroll_row + height)
+            .map(|styled| styled.iter().skip(scroll_row).cloned().collect()),
+        _ => None,
+    };
+    let app_state = &*app_state;
+
+    // Geçmiş panelinde bir revizyon önizleniyorsa onu göster
+    let preview = app_state.history.as_ref().and_then(|h| h.preview.as_ref().map(|p| (h, p)));
+    let (title, lines) = match (preview, &app_state.current_document_name) {
+        (Some((history, (number, lines))), _) => {
+            (t!(PreviewTitle, history.document_name, number), lines)
+        }
+        (None, Some(name)) => (t!(ContentTitle, name), &app_state.current_document_content),
+        (None, None) => (t!(NoDocumentTitle), &app_state.current_document_content),
+    };
+    let title = if show_blame { title + &t!(BlameTag) } else { title };
+    // Arama açıksa canlı içerikteki eşleşmeler vurgulanır
+    let matches = if preview.is_none() { app_state.search_matches() } else { Vec::new() };
+    let cursor = (app_state.cursor_row, app_state.cursor_col);
+    let text: Vec<Line> = lines
+        .iter()
+        .enumerate()
+        .skip(scroll_row)
+        .take(height)
+        .map(|(index, line)| {
+            let mut spans = Vec::with_capacity(3);
+            if app_state.show_line_numbers {
+                spans.push(line_number_span(index, number_width, preview.is_none() && index == app_state.cursor_row));
+            }
+            if show_blame {
+                let author = app_state.line_authors.get(index).map_or(UNKNOWN_AUTHOR, String::as_str);
+                let author: String = author.chars().take(blame_width).collect();
+                spans.push(Span::styled(
+                    format!("{:<width$} │ ", author, width = blame_width),
+                    Style::default().fg(author_color(&author)),
+                ));
+            }
+            let base = syntax_lines
+                .as_ref()
+                .and_then(|styled| styled.get(index - scroll_row).cloned())
+                .unwrap_or_else(|| vec![Span::raw(line.clone())]);
+            let line_matches: Vec<&SearchMatch> = matches.iter().filter(|m| m.row == index).collect();
+            spans.extend(clip_spans(highlighted_spans(base, &line_matches, cursor), scroll_col, width));
+            Line::from(spans)
+        })
+        .collect();
+
+    let editing = app_state.active_window == ActiveWindow::ContentView && preview.is_none();
+    let border_style = if editing {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+
+    let paragraph = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(border_style),
+    );
+    frame.render_widget(paragraph, area);
+
+    draw_content_scrollbar(frame, area, line_count, height, scroll_row);
+
+    if editing && app_state.current_document_name.is_some() {
+        // Satır numarası ve yazar sütunları açıksa imleci sütunların sağına kaydır
+        frame.set_cursor(
+            area.x + 1 + (gutter_width + app_state.cursor_col - scroll_col) as u16,
+            area.y + 1 + (app_state.cursor_row - scroll_row) as u16,
+        );
+    }
+}
+
+// Aktif belgenin Markdown olarak biçimlendirilmiş hali. Her çizimde canlı
+// içerikten üretildiği için uzak düzenlemeler de hemen yansır; görünen kısım
+// içerik panelinin kaydırmasını oransal olarak izler.
+fn draw_markdown_preview_panel(frame: &mut Frame, app_state: &AppState, area: Rect) {
+    let title = match &app_state.current_document_name {
+        Some(name) => t!(MarkdownTitle, name),
+        None => t!(NoDocumentTitle),
+    };
+    let width = area.width.saturating_sub(2) as usize;
+    let lines = markdown::render(&app_state.current_document_content, width);
+    let line_count = app_state.current_document_content.len().max(1);
+    let scroll = (lines.len() * app_state.scroll_row / line_count).min(u16::MAX as usize) as u16;
+    let paragraph = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::DarkGray)))
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0));
+    frame.render_widget(paragraph, area);
+}
+
+// Biçimlendirilmiş parçalardan yalnızca [skip, skip + take) karakter aralığını bırakır.
+fn clip_spans(spans: Vec<Span<'static>>, skip: usize, take: usize) -> Vec<Span<'static>> {
+    let mut clipped = Vec::with_capacity(spans.len());
+    let mut position = 0;
+    for span in spans {
+        let len = span.content.chars().count();
+        let (start, end) = (skip.max(position), (skip + take).min(position + len));
+        if start < end {
+            let text: String = span.content.chars().skip(start - position).take(end - start).collect();
+            clipped.push(Span::styled(text, span.style));
+        }
+        position += len;
+    }
+    clipped
+}
+
+// Satırın parçalarını eşleşme sınırlarından bölerek eşleşmeleri vurgular;
+// imlecin başında durduğu eşleşme (geçerli eşleşme) ayrı renkte gösterilir.
+fn highlighted_spans(spans: Vec<Span<'static>>, matches: &[&SearchMatch], cursor: (usize, usize)) -> Vec<Span<'static>> {
+    if matches.is_empty() {
+        return spans;
+    }
+    let mut highlighted = Vec::with_capacity(spans.len() + matches.len() * 2);
+    let mut position = 0;
+    for span in spans {
+        let chars: Vec<char> = span.content.chars().collect();
+        let span_end = position + chars.len();
+        let mut offset = position;
+        while offset < span_end {
+            let (next, style) = match matches.iter().find(|m| m.start <= offset && offset < m.end) {
+                Some(found) => {
+                    let style = if (found.row, found.start) == cursor {
+                        Style::default().fg(Color::Black).bg(Color::LightRed).add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(Color::Black).bg(Color::Yellow)
+                    };
+                    (found.end.min(span_end), span.style.patch(style))
+                }
+                None => {
+                    let next_start = matches.iter().map(|m| m.start).filter(|&start| start > offset).min();
+                    (next_start.unwrap_or(span_end).min(span_end), span.style)
+                }
+            };
+            highlighted.push(Span::styled(chars[offset - position..next - position].iter().collect::<String>(), style));
+            offset = next;
+        }
+        position = span_end;
+    }
+    highlighted
+}
+
+fn draw_search_panel(frame: &mut Frame, app_state: &AppState, area: Rect) {
+    let Some(search) = app_state.search.as_ref() else { return };
+    let active = app_state.active_window == ActiveWindow::Search;
+    let border_style = if active {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+    let matches = app_state.search_matches();
+    let status = if let Some(error) = search.error() {
+        t!(SearchInvalidRegex, error)
+    } else if search.query.is_empty() {
+        String::new()
+    } else if matches.is_empty() {
+        t!(SearchNoMatches)
+    } else {
+        let cursor = (app_state.cursor_row, app_state.cursor_col);
+        let current = matches.iter().position(|m| (m.row, m.start) == cursor);
+        t!(SearchMatchCount, current.map_or("-".to_string(), |i| (i + 1).to_string()), matches.len())
+    };
+    let regex_tag = if search.regex { t!(SearchRegexTag) } else { String::new() };
+    let case_tag = if search.case_sensitive { t!(SearchCaseTag) } else { String::new() };
+    let title = t!(SearchTitle, regex_tag, case_tag, status);
+
+    let (find_label, replace_label) = (t!(SearchFindLabel), t!(SearchReplaceLabel));
+    let label_style = Style::default().fg(Color::DarkGray);
+    let text = vec![
+        Line::from(vec![Span::styled(find_label.clone(), label_style), Span::raw(search.query.clone())]),
+        Line::from(vec![Span::styled(replace_label.clone(), label_style), Span::raw(search.replacement.clone())]),
+    ];
+    let paragraph = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_bottom(t!(SearchHelp))
+            .border_style(border_style),
+    );
+    frame.render_widget(paragraph, area);
+
+    if active {
+        let (label, field, row) = if search.editing_replacement {
+            (&replace_label, &search.replacement, 1)
+        } else {
+            (&find_label, &search.query, 0)
+        };
+        frame.set_cursor(
+            area.x + 1 + (label.chars().count() + field.chars().count()) as u16,
+            area.y + 1 + row,
+        );
+    }
+}
+
+// Her yazara isminden türetilen sabit bir renk verir.
+fn author_color(author: &str) -> Color {
+    const PALETTE: [Color; 6] = [Color::Cyan, Color::Green, Color::Magenta, Color::Yellow, Color::Blue, Color::LightRed];
+    if author == UNKNOWN_AUTHOR {
+        return Color::DarkGray;
+    }
+    let hash = author.bytes().fold(0usize, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as usize));
+    PALETTE[hash % PALETTE.len()]
+}
+
+fn draw_diff_panel(frame: &mut Frame, diff_view: &DiffView, area: Rect) {
+    let paragraph = Paragraph::new(diff_view_lines(&diff_view.lines))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(t!(DiffTitle, diff_view.title))
                 .border_style(Style::default().fg(Color::DarkGray)),
         )
-        .wrap(Wrap { trim: false }); 
+        .wrap(Wrap { trim: false });
     frame.render_widget(paragraph, area);
 }
 
+// Fark satırlarını renklendirir; art arda gelen silinen/eklenen satırlar
+// eşleştirilip değişen kelimeler ayrıca vurgulanır.
+fn diff_view_lines(lines: &[DiffLine]) -> Vec<Line<'static>> {
+    let mut rendered = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].tag == DiffTag::Equal {
+            rendered.push(Line::from(Span::raw(format!("  {}", lines[i].text))));
+            i += 1;
+            continue;
+        }
+        // Bir değişiklik bloğu: önce silinenler, sonra eklenenler
+        let deleted_end = i + lines[i..].iter().take_while(|l| l.tag == DiffTag::Delete).count();
+        let inserted_end = deleted_end + lines[deleted_end..].iter().take_while(|l| l.tag == DiffTag::Insert).count();
+        let (deleted, inserted) = (&lines[i..deleted_end], &lines[deleted_end..inserted_end]);
+        let mut deleted_lines = Vec::new();
+        let mut inserted_lines = Vec::new();
+        for (index, line) in deleted.iter().enumerate() {
+            match inserted.get(index) {
+                Some(pair) => {
+                    let (old_spans, new_spans) = diff_words(&line.text, &pair.text);
+                    deleted_lines.push(word_diff_line('-', &old_spans, Color::Red));
+                    inserted_lines.push(word_diff_line('+', &new_spans, Color::Green));
+                }
+                None => deleted_lines.push(plain_diff_line('-', &line.text, Color::Red)),
+            }
+        }
+        for line in inserted.iter().skip(deleted.len()) {
+            inserted_lines.push(plain_diff_line('+', &line.text, Color::Green));
+        }
+        rendered.extend(deleted_lines);
+        rendered.extend(inserted_lines);
+        i = inserted_end;
+    }
+    rendered
+}
+
+fn plain_diff_line(marker: char, text: &str, color: Color) -> Line<'static> {
+    Line::from(Span::styled(format!("{} {}", marker, text), Style::default().fg(color)))
+}
+
+fn word_diff_line(marker: char, spans: &[WordSpan], color: Color) -> Line<'static> {
+    let mut line_spans = vec![Span::styled(format!("{} ", marker), Style::default().fg(color))];
+    line_spans.extend(spans.iter().map(|span| {
+        let style = if span.changed {
+            Style::default().fg(Color::Black).bg(color).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(color)
+        };
+        Span::styled(span.text.clone(), style)
+    }));
+    Line::from(line_spans)
+}
+
 fn draw_command_input_panel(frame: &mut Frame, app_state: &AppState, area: Rect) {
     let border_style = if app_state.active_window == ActiveWindow::CommandInput {
         Style::default().fg(Color::Yellow)
@@ -116,10 +647,23 @@ fn draw_command_input_panel(frame: &mut Frame, app_state: &AppState, area: Rect)
     // Block kenarları için genellikle 1 karakter her yönden gider.
     let input_display_text = format!("> {}", app_state.command_input);
 
+    let mut status_note = if app_state.connected { String::new() } else { t!(StatusOffline) };
+    if !app_state.offline_queue.is_empty() {
+        status_note.push_str(&t!(StatusEditsPending, app_state.offline_queue.len()));
+    }
+    if app_state.connected && app_state.current_document_name.is_some() {
+        status_note.push_str(&if app_state.requests.update_in_flight() {
+            t!(StatusSaving)
+        } else if app_state.save_failed {
+            t!(StatusNotSaved)
+        } else {
+            t!(StatusSaved)
+        });
+    }
     let paragraph = Paragraph::new(Span::raw(input_display_text.clone())).block(
         Block::default()
             .borders(Borders::ALL)
-            .title("Komut (TAB ile geçiş)")
+            .title(t!(CommandTitle, status_note))
             .border_style(border_style),
     );
     frame.render_widget(paragraph, area);
@@ -140,13 +684,41 @@ fn draw_event_log_panel(frame: &mut Frame, app_state: &AppState, area: Rect) {
         .map(|msg| Line::from(Span::raw(msg.clone())))
         .collect();
 
+    let title = match app_state.latency {
+        Some(latency) => t!(EventsTitleWithLatency, latency.as_millis()),
+        None => t!(EventsTitle),
+    };
     let paragraph = Paragraph::new(messages)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Olaylar / Durum")
+                .title(title)
                 .border_style(Style::default().fg(Color::DarkGray)),
         )
         .wrap(Wrap { trim: true });
     frame.render_widget(paragraph, area);
-}
\ No newline at end of file
+}
+
+// Unix zaman damgasını "YYYY-AA-GG SS:DD" (UTC) biçimine çevirir.
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds_of_day = timestamp % 86_400;
+    // Howard Hinnant'ın civil_from_days algoritması
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3_600,
+        (seconds_of_day % 3_600) / 60
+    )
+}
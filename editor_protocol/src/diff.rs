@@ -0,0 +1,416 @@
+//! Line and word diffs shared by the server (`DIFF_DOCUMENT`) and the client diff view.
+
+/// One step of an edit script, holding indices into the old/new sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffTag {
+    Equal,
+    Delete,
+    Insert,
+}
+
+impl DiffTag {
+    /// Prefix used for this tag in unified diff output.
+    pub fn marker(self) -> char {
+        match self {
+            DiffTag::Equal => ' ',
+            DiffTag::Delete => '-',
+            DiffTag::Insert => '+',
+        }
+    }
+}
+
+/// A line of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub text: String,
+}
+
+/// A piece of a line in a word diff; `changed` marks words missing on the other side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordSpan {
+    pub text: String,
+    pub changed: bool,
+}
+
+/// Edit cost beyond which a range is given up on and diffed as "delete all,
+/// insert all". Keeps unrelated texts (a document replaced wholesale) from
+/// costing O((N+M)·D) time; ranges that differ less still get a shortest script.
+const MAX_EDIT_COST: isize = 2048;
+
+/// Computes a shortest edit script between two sequences (Myers' algorithm,
+/// linear-space variant), falling back to a coarser one past [`MAX_EDIT_COST`].
+pub fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    diff_range(old, new, 0, 0, &mut edits);
+    edits
+}
+
+/// Appends the edits turning `old` into `new`; `old_start`/`new_start` are
+/// their positions in the full sequences.
+fn diff_range<T: PartialEq>(old: &[T], new: &[T], old_start: usize, new_start: usize, edits: &mut Vec<Edit>) {
+    // Common prefix and suffix do not need the full search.
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    edits.extend((0..prefix).map(|i| Edit::Equal(old_start + i, new_start + i)));
+
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+    let (old_offset, new_offset) = (old_start + prefix, new_start + prefix);
+    match middle_snake(old_middle, new_middle) {
+        Some((x, y)) => {
+            diff_range(&old_middle[..x], &new_middle[..y], old_offset, new_offset, edits);
+            diff_range(&old_middle[x..], &new_middle[y..], old_offset + x, new_offset + y, edits);
+        }
+        None => {
+            edits.extend((0..old_middle.len()).map(|i| Edit::Delete(old_offset + i)));
+            edits.extend((0..new_middle.len()).map(|j| Edit::Insert(new_offset + j)));
+        }
+    }
+
+    let (old_tail, new_tail) = (old.len() - suffix, new.len() - suffix);
+    edits.extend((0..suffix).map(|i| Edit::Equal(old_start + old_tail + i, new_start + new_tail + i)));
+}
+
+/// Searches forwards from the start and backwards from the end at once until
+/// the paths meet, and returns the meeting point, which splits the problem in
+/// two halves of about half the cost. Only two diagonal vectors are kept, so
+/// memory stays linear. Returns `None` when either side is empty (nothing to
+/// split) or the cost exceeds [`MAX_EDIT_COST`].
+fn middle_snake<T: PartialEq>(old: &[T], new: &[T]) -> Option<(usize, usize)> {
+    if old.is_empty() || new.is_empty() {
+        return None;
+    }
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max_d = (n + m + 1) / 2;
+    let offset = max_d;
+    let length = 2 * max_d + 2;
+    // Furthest x reached on each diagonal k = x - y, forwards and backwards
+    let mut forward = vec![-1isize; length as usize];
+    let mut backward = vec![-1isize; length as usize];
+    forward[(offset + 1) as usize] = 0;
+    backward[(offset + 1) as usize] = 0;
+    let delta = n - m;
+    // With an odd delta the paths meet during a forward step, otherwise during a backward one
+    let front = delta % 2 != 0;
+    // Diagonals that ran off the edge of the grid are skipped from then on
+    let (mut forward_start, mut forward_end, mut backward_start, mut backward_end) = (0, 0, 0, 0);
+
+    for d in 0..max_d.min(MAX_EDIT_COST) {
+        let mut k = -d + forward_start;
+        while k <= d - forward_end {
+            let index = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && forward[index - 1] < forward[index + 1]) {
+                forward[index + 1]
+            } else {
+                forward[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[index] = x;
+            if x > n {
+                forward_end += 2;
+            } else if y > m {
+                forward_start += 2;
+            } else if front {
+                let other = offset + delta - k;
+                if (0..length).contains(&other) && backward[other as usize] != -1 && x >= n - backward[other as usize] {
+                    return Some((x as usize, y as usize));
+                }
+            }
+            k += 2;
+        }
+
+        let mut k = -d + backward_start;
+        while k <= d - backward_end {
+            let index = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && backward[index - 1] < backward[index + 1]) {
+                backward[index + 1]
+            } else {
+                backward[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[index] = x;
+            if x > n {
+                backward_end += 2;
+            } else if y > m {
+                backward_start += 2;
+            } else if !front {
+                let other = offset + delta - k;
+                if (0..length).contains(&other) && forward[other as usize] != -1 {
+                    let forward_x = forward[other as usize];
+                    let forward_y = offset + forward_x - other;
+                    if forward_x >= n - x {
+                        return Some((forward_x as usize, forward_y as usize));
+                    }
+                }
+            }
+            k += 2;
+        }
+    }
+    None
+}
+
+/// Line-by-line diff of two texts. Lines are split with [`str::lines`], so a
+/// single trailing newline does not count as an extra empty line.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    diff(&old_lines, &new_lines)
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Equal(i, _) => DiffLine { tag: DiffTag::Equal, text: old_lines[i].to_string() },
+            Edit::Delete(i) => DiffLine { tag: DiffTag::Delete, text: old_lines[i].to_string() },
+            Edit::Insert(j) => DiffLine { tag: DiffTag::Insert, text: new_lines[j].to_string() },
+        })
+        .collect()
+}
+
+/// Word-level diff of a changed line pair, returned as spans of the old and new line.
+pub fn diff_words(old: &str, new: &str) -> (Vec<WordSpan>, Vec<WordSpan>) {
+    let old_words = split_words(old);
+    let new_words = split_words(new);
+    let mut old_spans: Vec<WordSpan> = Vec::new();
+    let mut new_spans: Vec<WordSpan> = Vec::new();
+    for edit in diff(&old_words, &new_words) {
+        match edit {
+            Edit::Equal(i, j) => {
+                push_span(&mut old_spans, old_words[i], false);
+                push_span(&mut new_spans, new_words[j], false);
+            }
+            Edit::Delete(i) => push_span(&mut old_spans, old_words[i], true),
+            Edit::Insert(j) => push_span(&mut new_spans, new_words[j], true),
+        }
+    }
+    (old_spans, new_spans)
+}
+
+fn push_span(spans: &mut Vec<WordSpan>, text: &str, changed: bool) {
+    match spans.last_mut() {
+        Some(last) if last.changed == changed => last.text.push_str(text),
+        _ => spans.push(WordSpan { text: text.to_string(), changed }),
+    }
+}
+
+/// Splits a line into words, whitespace runs and single punctuation characters.
+fn split_words(line: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let end = if is_word(c) || c.is_whitespace() {
+            let same_kind = |next: char| if is_word(c) { is_word(next) } else { next.is_whitespace() };
+            let mut end = i + c.len_utf8();
+            while let Some(&(j, next)) = chars.peek() {
+                if !same_kind(next) {
+                    break;
+                }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+            end
+        } else {
+            i + c.len_utf8()
+        };
+        words.push(&line[start..end]);
+        start = end;
+    }
+    words
+}
+
+/// Serialises a diff as unified diff lines (`' '`, `'-'` or `'+'` followed by the text).
+pub fn format_diff_lines(lines: &[DiffLine]) -> String {
+    lines
+        .iter()
+        .map(|line| format!("{}{}", line.tag.marker(), line.text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses the output of [`format_diff_lines`].
+pub fn parse_diff_lines(body: &str) -> Vec<DiffLine> {
+    body.lines()
+        .filter_map(|line| {
+            let mut chars = line.chars();
+            let tag = match chars.next()? {
+                '-' => DiffTag::Delete,
+                '+' => DiffTag::Insert,
+                _ => DiffTag::Equal,
+            };
+            Some(DiffLine { tag, text: chars.as_str().to_string() })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rebuilds `new` from an edit script, checking that every index is used in order.
+    fn apply<T: Clone + PartialEq + std::fmt::Debug>(old: &[T], new: &[T], edits: &[Edit]) -> Vec<T> {
+        let (mut next_old, mut next_new) = (0, 0);
+        let mut result = Vec::new();
+        for edit in edits {
+            match *edit {
+                Edit::Equal(i, j) => {
+                    assert_eq!((i, j), (next_old, next_new));
+                    assert_eq!(old[i], new[j]);
+                    result.push(old[i].clone());
+                    next_old += 1;
+                    next_new += 1;
+                }
+                Edit::Delete(i) => {
+                    assert_eq!(i, next_old);
+                    next_old += 1;
+                }
+                Edit::Insert(j) => {
+                    assert_eq!(j, next_new);
+                    result.push(new[j].clone());
+                    next_new += 1;
+                }
+            }
+        }
+        assert_eq!((next_old, next_new), (old.len(), new.len()));
+        result
+    }
+
+    fn cost(edits: &[Edit]) -> usize {
+        edits.iter().filter(|edit| !matches!(edit, Edit::Equal(..))).count()
+    }
+
+    /// Shortest edit script length from the longest common subsequence.
+    fn lcs_cost<T: PartialEq>(old: &[T], new: &[T]) -> usize {
+        let mut table = vec![vec![0; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                table[i][j] = if old[i] == new[j] { table[i + 1][j + 1] + 1 } else { table[i + 1][j].max(table[i][j + 1]) };
+            }
+        }
+        old.len() + new.len() - 2 * table[0][0]
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert!(diff::<u8>(&[], &[]).is_empty());
+        assert_eq!(diff(&[], &[1, 2]), [Edit::Insert(0), Edit::Insert(1)]);
+        assert_eq!(diff(&[1, 2], &[]), [Edit::Delete(0), Edit::Delete(1)]);
+    }
+
+    #[test]
+    fn identical_inputs_are_all_equal() {
+        let lines = ["a", "b", "c"];
+        assert_eq!(diff(&lines, &lines), [Edit::Equal(0, 0), Edit::Equal(1, 1), Edit::Equal(2, 2)]);
+    }
+
+    #[test]
+    fn fully_different_inputs_delete_and_insert_everything() {
+        let (old, new) = (["a", "b"], ["x", "y", "z"]);
+        let edits = diff(&old, &new);
+        assert_eq!(cost(&edits), 5);
+        assert_eq!(apply(&old, &new, &edits), new);
+    }
+
+    #[test]
+    fn scripts_are_valid_and_shortest() {
+        // Small pseudo-random sequences over a tiny alphabet, so they share a lot
+        let mut seed: u32 = 12345;
+        let mut next = move |bound: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % bound
+        };
+        for _ in 0..500 {
+            let old: Vec<u32> = (0..next(12)).map(|_| next(4)).collect();
+            let new: Vec<u32> = (0..next(12)).map(|_| next(4)).collect();
+            let edits = diff(&old, &new);
+            assert_eq!(apply(&old, &new, &edits), new, "{:?} -> {:?}", old, new);
+            assert_eq!(cost(&edits), lcs_cost(&old, &new), "{:?} -> {:?}", old, new);
+        }
+    }
+
+    #[test]
+    fn large_unrelated_inputs_fall_back_quickly() {
+        let old: Vec<u32> = (0..200_000).collect();
+        let new: Vec<u32> = (1_000_000..1_150_000).collect();
+        let edits = diff(&old, &new);
+        assert_eq!(cost(&edits), old.len() + new.len());
+        assert_eq!(apply(&old, &new, &edits), new);
+    }
+
+    #[test]
+    fn large_inputs_with_few_changes_stay_exact() {
+        let old: Vec<u32> = (0..100_000).collect();
+        let mut new = old.clone();
+        new[10] = 7_000_000;
+        new.remove(50_000);
+        new.insert(90_000, 8_000_000);
+        let edits = diff(&old, &new);
+        assert_eq!(cost(&edits), 4);
+        assert_eq!(apply(&old, &new, &edits), new);
+    }
+
+    #[test]
+    fn line_diff_marks_changed_lines() {
+        let lines = diff_lines("a\nb\nc", "a\nB\nc\nd");
+        let tagged: Vec<(char, &str)> = lines.iter().map(|line| (line.tag.marker(), line.text.as_str())).collect();
+        assert_eq!(tagged, [(' ', "a"), ('-', "b"), ('+', "B"), (' ', "c"), ('+', "d")]);
+    }
+
+    #[test]
+    fn line_diff_of_empty_texts() {
+        assert!(diff_lines("", "").is_empty());
+        let lines = diff_lines("", "a");
+        assert_eq!(lines, [DiffLine { tag: DiffTag::Insert, text: "a".to_string() }]);
+    }
+
+    #[test]
+    fn single_trailing_newline_is_not_a_line() {
+        assert!(diff_lines("a\nb\n", "a\nb").iter().all(|line| line.tag == DiffTag::Equal));
+        // A second newline is an empty last line
+        let lines = diff_lines("a\n", "a\n\n");
+        assert_eq!(lines.last(), Some(&DiffLine { tag: DiffTag::Insert, text: String::new() }));
+    }
+
+    #[test]
+    fn word_diff_marks_only_changed_words() {
+        let (old, new) = diff_words("let x = 1;", "let y = 1;");
+        let spans = |spans: &[WordSpan]| spans.iter().map(|span| (span.text.clone(), span.changed)).collect::<Vec<_>>();
+        assert_eq!(spans(&old), [("let ".to_string(), false), ("x".to_string(), true), (" = 1;".to_string(), false)]);
+        assert_eq!(spans(&new), [("let ".to_string(), false), ("y".to_string(), true), (" = 1;".to_string(), false)]);
+    }
+
+    #[test]
+    fn word_diff_of_identical_and_empty_lines() {
+        let (old, new) = diff_words("same text", "same text");
+        assert_eq!(old, [WordSpan { text: "same text".to_string(), changed: false }]);
+        assert_eq!(old, new);
+        let (old, new) = diff_words("", "new");
+        assert!(old.is_empty());
+        assert_eq!(new, [WordSpan { text: "new".to_string(), changed: true }]);
+    }
+
+    #[test]
+    fn diff_lines_round_trip_through_the_wire_format() {
+        let lines = diff_lines("a\nb", "a\nc");
+        assert_eq!(parse_diff_lines(&format_diff_lines(&lines)), lines);
+    }
+}
@@ -1,3 +1,7 @@
+pub mod diff;
+pub mod locale;
+pub mod ops;
+
 pub const PORT: u16 = 12345;
 pub const SERVER_ADDRESS: &str = "0.0.0.0"; // Listen on all available network interfaces
 pub const CLIENT_CONNECT_ADDRESS: &str = "127.0.0.1"; // Client will connect to localhost
@@ -11,20 +15,204 @@ pub const UPDATE_DOCUMENT_CMD: &str = "UPDATE_DOCUMENT";
 pub const LIST_DOCUMENTS_CMD: &str = "LIST_DOCUMENTS";
 pub const CREATE_DOCUMENT_CMD: &str = "CREATE_DOCUMENT";
 pub const SWITCH_DOCUMENT_CMD: &str = "SWITCH_DOCUMENT";
+pub const HISTORY_CMD: &str = "HISTORY";
+pub const GET_REVISION_CMD: &str = "GET_REVISION";
+pub const RESTORE_REVISION_CMD: &str = "RESTORE_REVISION";
+pub const DIFF_DOCUMENT_CMD: &str = "DIFF_DOCUMENT";
+pub const BLAME_CMD: &str = "BLAME";
+pub const UNDO_CMD: &str = "UNDO";
+pub const REDO_CMD: &str = "REDO";
+/// `SEARCH <query>`: full-text search over all documents, answered with `SEARCH_RESULTS`.
+pub const SEARCH_CMD: &str = "SEARCH";
+/// `CLOSE_DOCUMENT <document>`: stops the updates of a document opened with
+/// `SWITCH_DOCUMENT`/`GET_DOCUMENT`. A session can keep several documents open.
+pub const CLOSE_DOCUMENT_CMD: &str = "CLOSE_DOCUMENT";
+
+/// Client requests may start with `#<request-id> `, see `format_request`. The
+/// server answers each such request with `ACK <request-id>` once it has been
+/// handled, or with an `ERROR` carrying the same id if it was rejected; any
+/// data the request asked for is sent before the `ACK`. Heartbeat messages
+/// are not requests and carry no id.
+pub const REQUEST_ID_PREFIX: char = '#';
 
 // Messages from Server to Client
 pub const CONNECTED_OK_MSG: &str = "CONNECTED_OK";
 pub const USER_JOINED_MSG: &str = "USER_JOINED";
 pub const USER_LEFT_MSG: &str = "USER_LEFT";
+/// `USER_SWITCHED <username>\t<document>`: another user switched to a document.
+/// A tab separates the two since both may contain spaces; see `format_user_switched`.
+pub const USER_SWITCHED_MSG: &str = "USER_SWITCHED";
 pub const DOCUMENT_CONTENT_MSG: &str = "DOCUMENT_CONTENT";
 pub const DOCUMENT_UPDATED_MSG: &str = "DOCUMENT_UPDATED";
 pub const DOCUMENTS_LIST_MSG: &str = "DOCUMENTS_LIST";
 pub const DOCUMENT_CREATED_OK_MSG: &str = "DOCUMENT_CREATED_OK";
-pub const DOCUMENT_CREATED_FAIL_MSG: &str = "DOCUMENT_CREATED_FAIL";
 pub const DOCUMENT_SWITCHED_MSG: &str = "DOCUMENT_SWITCHED";
 pub const NEW_DOCUMENT_AVAILABLE_MSG: &str = "NEW_DOCUMENT_AVAILABLE";
+pub const HISTORY_LIST_MSG: &str = "HISTORY_LIST";
+pub const REVISION_CONTENT_MSG: &str = "REVISION_CONTENT";
+pub const DIFF_RESULT_MSG: &str = "DIFF_RESULT";
+pub const BLAME_INFO_MSG: &str = "BLAME_INFO";
+/// `SEARCH_RESULTS <query>` followed by one `<document>\t<line>\t<snippet>`
+/// line per hit (lines are 1-based) and END_OF_MESSAGE.
+pub const SEARCH_RESULTS_MSG: &str = "SEARCH_RESULTS";
+/// `ACK <request-id>`: the request with this id was handled successfully.
+pub const ACK_MSG: &str = "ACK";
+/// `ERROR <code> <request-id> <message>`, see `ErrorCode` and `format_error`.
+pub const ERROR_MSG: &str = "ERROR";
+/// `SERVER_SHUTTING_DOWN <reason>`: sent to everyone right before the server closes all connections.
+pub const SERVER_SHUTTING_DOWN_MSG: &str = "SERVER_SHUTTING_DOWN";
+/// `KICKED <reason>`: the session was ended by an administrator; the client should not reconnect.
+pub const KICKED_MSG: &str = "KICKED";
+/// `ANNOUNCEMENT <text>`: a notice from an administrator or about a document.
+pub const ANNOUNCEMENT_MSG: &str = "ANNOUNCEMENT";
+
+// Heartbeat, sent in both directions: either side may send `PING [payload]`
+// and the other answers `PONG [payload]` with the same payload.
+pub const PING_CMD: &str = "PING";
+pub const PONG_MSG: &str = "PONG";
+
+// Admin channel (a local Unix socket, see `editor_admin`): one command per
+// line; each reply starts with `OK` or an `ERROR` line, may carry more lines
+// and ends with END_OF_MESSAGE.
+pub const ADMIN_SESSIONS_CMD: &str = "SESSIONS";
+pub const ADMIN_KICK_CMD: &str = "KICK";
+pub const ADMIN_BROADCAST_CMD: &str = "BROADCAST";
+pub const ADMIN_SAVE_CMD: &str = "SAVE";
+pub const ADMIN_RELOAD_CONFIG_CMD: &str = "RELOAD_CONFIG";
+pub const ADMIN_LOCK_CMD: &str = "LOCK";
+pub const ADMIN_UNLOCK_CMD: &str = "UNLOCK";
+pub const ADMIN_OK: &str = "OK";
 
 pub const END_OF_MESSAGE_DELIMITER: &str = "END_OF_MESSAGE";
+/// Length of the hex session token sent with `CONNECTED_OK` and echoed back in
+/// `CONNECT <username> <token>` to resume a session after a reconnect; 128
+/// random bits.
+pub const RESUME_TOKEN_LEN: usize = 32;
+/// Revision specifier meaning the server's live (possibly unsaved) content.
+pub const CURRENT_REVISION: &str = "current";
+/// Placeholder author in `BLAME_INFO` for lines whose author is not known.
+pub const UNKNOWN_AUTHOR: &str = "-";
+/// Request id placeholder in `ERROR` messages that do not answer a specific request.
+pub const NO_REQUEST_ID: &str = "-";
+
+/// Stable machine-readable reason carried by every `ERROR` message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// Missing or malformed argument.
+    BadRequest,
+    UnknownCommand,
+    /// A message could not be parsed (used by either side).
+    InvalidMessage,
+    /// The command needs a `CONNECT` first.
+    NotConnected,
+    /// A second `CONNECT` on a connection that is already signed in.
+    AlreadyConnected,
+    DocumentNotFound,
+    DocumentExists,
+    /// `UPDATE_DOCUMENT` for a document the client has not switched to.
+    NotEditingDocument,
+    /// An administrator locked the document.
+    DocumentLocked,
+    RevisionNotFound,
+    /// Nothing to undo/redo, or the change can no longer be reverted.
+    UndoFailed,
+    StorageFailed,
+    UserNotFound,
+    LineTooLong,
+    DocumentTooLarge,
+    RateLimited,
+    TooManyConnections,
+    /// `CREATE_DOCUMENT` with a file extension the server does not allow.
+    ExtensionNotAllowed,
+    /// An argument the server refuses, such as a document name containing a
+    /// path separator or control characters.
+    InvalidArgument,
+    Internal,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 20] = [
+        ErrorCode::BadRequest,
+        ErrorCode::UnknownCommand,
+        ErrorCode::InvalidMessage,
+        ErrorCode::NotConnected,
+        ErrorCode::AlreadyConnected,
+        ErrorCode::DocumentNotFound,
+        ErrorCode::DocumentExists,
+        ErrorCode::NotEditingDocument,
+        ErrorCode::DocumentLocked,
+        ErrorCode::RevisionNotFound,
+        ErrorCode::UndoFailed,
+        ErrorCode::StorageFailed,
+        ErrorCode::UserNotFound,
+        ErrorCode::LineTooLong,
+        ErrorCode::DocumentTooLarge,
+        ErrorCode::RateLimited,
+        ErrorCode::TooManyConnections,
+        ErrorCode::ExtensionNotAllowed,
+        ErrorCode::InvalidArgument,
+        ErrorCode::Internal,
+    ];
+
+    /// The code as it appears on the wire.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "BAD_REQUEST",
+            ErrorCode::UnknownCommand => "UNKNOWN_COMMAND",
+            ErrorCode::InvalidMessage => "INVALID_MESSAGE",
+            ErrorCode::NotConnected => "NOT_CONNECTED",
+            ErrorCode::AlreadyConnected => "ALREADY_CONNECTED",
+            ErrorCode::DocumentNotFound => "DOCUMENT_NOT_FOUND",
+            ErrorCode::DocumentExists => "DOCUMENT_EXISTS",
+            ErrorCode::NotEditingDocument => "NOT_EDITING_DOCUMENT",
+            ErrorCode::DocumentLocked => "DOCUMENT_LOCKED",
+            ErrorCode::RevisionNotFound => "REVISION_NOT_FOUND",
+            ErrorCode::UndoFailed => "UNDO_FAILED",
+            ErrorCode::StorageFailed => "STORAGE_FAILED",
+            ErrorCode::UserNotFound => "USER_NOT_FOUND",
+            ErrorCode::LineTooLong => "LINE_TOO_LONG",
+            ErrorCode::DocumentTooLarge => "DOCUMENT_TOO_LARGE",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::TooManyConnections => "TOO_MANY_CONNECTIONS",
+            ErrorCode::ExtensionNotAllowed => "EXTENSION_NOT_ALLOWED",
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<ErrorCode> {
+        ErrorCode::ALL.into_iter().find(|known| known.as_str() == code)
+    }
+}
+
+/// Formats `ERROR <code> <request-id> <message>`; `request_id` is `-` when the
+/// error does not answer a particular request.
+pub fn format_error(code: ErrorCode, request_id: Option<&str>, message: &str) -> String {
+    format!("{} {} {} {}", ERROR_MSG, code.as_str(), request_id.unwrap_or(NO_REQUEST_ID), message)
+}
+
+/// Splits the payload of an `ERROR` message into its code, request id and
+/// message. Codes this build does not know are reported as `Internal`.
+pub fn parse_error(payload: &str) -> (ErrorCode, Option<&str>, &str) {
+    let mut parts = payload.splitn(3, ' ');
+    let code = parts.next().and_then(ErrorCode::parse).unwrap_or(ErrorCode::Internal);
+    let request_id = parts.next().filter(|id| *id != NO_REQUEST_ID && !id.is_empty());
+    (code, request_id, parts.next().unwrap_or_default())
+}
+
+/// Tags a client message with a request id. For multi-line messages only the
+/// first line carries it.
+pub fn format_request(request_id: u64, message: &str) -> String {
+    format!("{}{} {}", REQUEST_ID_PREFIX, request_id, message)
+}
+
+/// Splits an optional `#<request-id>` prefix off a client line.
+pub fn split_request_id(line: &str) -> (Option<&str>, &str) {
+    match line.strip_prefix(REQUEST_ID_PREFIX).and_then(|rest| rest.split_once(' ')) {
+        Some((request_id, rest)) if !request_id.is_empty() => (Some(request_id), rest.trim_start()),
+        _ => (None, line),
+    }
+}
 
 /// Formats a message with a document name and content.
 pub fn format_document_message(command: &str, doc_name: &str, content: &str) -> String {
@@ -36,7 +224,90 @@ pub fn format_command_with_arg(command: &str, argument: &str) -> String {
     format!("{} {}", command, argument)
 }
 
+/// Splits a `<doc_name> <revision>` argument. The revision is taken from the
+/// end so document names may contain spaces.
+pub fn parse_revision_argument(argument: &str) -> Option<(&str, u64)> {
+    let (doc_name, revision) = argument.rsplit_once(' ')?;
+    Some((doc_name, revision.parse().ok()?))
+}
+
+/// Splits a `<doc_name> <rev_a> <rev_b>` argument, taking the revisions from the end.
+pub fn parse_diff_argument(argument: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = argument.rsplitn(3, ' ');
+    let rev_b = parts.next()?;
+    let rev_a = parts.next()?;
+    let doc_name = parts.next()?;
+    Some((doc_name, rev_a, rev_b))
+}
+
+/// Splits a `<username> [<resume_token>]` argument. A trailing word is only
+/// taken as a token if it looks like one, so usernames may contain spaces.
+pub fn parse_connect_argument(argument: &str) -> (&str, Option<&str>) {
+    match argument.rsplit_once(' ') {
+        Some((username, token))
+            if token.len() == RESUME_TOKEN_LEN && token.chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            (username, Some(token))
+        }
+        _ => (argument, None),
+    }
+}
+
+/// Formats a `USER_SWITCHED` message.
+pub fn format_user_switched(username: &str, doc_name: &str) -> String {
+    format!("{} {}\t{}", USER_SWITCHED_MSG, username, doc_name)
+}
+
+/// Splits a `USER_SWITCHED` payload into the username and the document.
+pub fn parse_user_switched(payload: &str) -> Option<(&str, &str)> {
+    payload.split_once('\t').filter(|(username, doc_name)| !username.is_empty() && !doc_name.is_empty())
+}
+
 /// Formats a simple command without arguments.
 pub fn format_simple_command(command: &str) -> String {
     command.to_string()
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_codes_round_trip() {
+        for code in ErrorCode::ALL {
+            let message = format_error(code, Some("7"), "some detail");
+            let payload = message.strip_prefix(ERROR_MSG).unwrap().trim_start();
+            assert_eq!(parse_error(payload), (code, Some("7"), "some detail"));
+        }
+    }
+
+    #[test]
+    fn error_without_request_id() {
+        let message = format_error(ErrorCode::RateLimited, None, "slow down");
+        let payload = message.strip_prefix(ERROR_MSG).unwrap().trim_start();
+        assert_eq!(parse_error(payload), (ErrorCode::RateLimited, None, "slow down"));
+    }
+
+    #[test]
+    fn malformed_errors_are_parsed_leniently() {
+        assert_eq!(parse_error("NOT_A_CODE 3 text with spaces"), (ErrorCode::Internal, Some("3"), "text with spaces"));
+        assert_eq!(parse_error("DOCUMENT_LOCKED"), (ErrorCode::DocumentLocked, None, ""));
+        assert_eq!(parse_error(""), (ErrorCode::Internal, None, ""));
+    }
+
+    #[test]
+    fn request_ids_are_split_off_the_first_line() {
+        let line = format_request(12, "GET_DOCUMENT my notes.txt");
+        assert_eq!(split_request_id(&line), (Some("12"), "GET_DOCUMENT my notes.txt"));
+        assert_eq!(split_request_id("GET_DOCUMENT a.txt"), (None, "GET_DOCUMENT a.txt"));
+        assert_eq!(split_request_id("# GET_DOCUMENT"), (None, "# GET_DOCUMENT"));
+    }
+
+    #[test]
+    fn user_switched_keeps_spaces_in_names() {
+        let message = format_user_switched("Ayşe Yılmaz", "my notes.txt");
+        let payload = message.strip_prefix(USER_SWITCHED_MSG).unwrap().trim_start();
+        assert_eq!(parse_user_switched(payload), Some(("Ayşe Yılmaz", "my notes.txt")));
+        assert_eq!(parse_user_switched("ayse belgesine geçti."), None);
+        assert_eq!(parse_user_switched("ayse\t"), None);
+    }
+}
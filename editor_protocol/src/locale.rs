@@ -0,0 +1,56 @@
+//! Language selection shared by the client's UI catalog and the server's
+//! error texts.
+
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Tr,
+}
+
+impl Locale {
+    /// Accepts a bare language (`tr`) or a POSIX locale name (`tr_TR.UTF-8`).
+    pub fn parse(value: &str) -> Option<Locale> {
+        let language = value.split(['_', '-', '.', '@']).next().unwrap_or_default();
+        match language.to_ascii_lowercase().as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "tr" => Some(Locale::Tr),
+            _ => None,
+        }
+    }
+
+    /// The language from `LC_ALL`, `LC_MESSAGES` or `LANG`, whichever is set
+    /// first, if it is one we have texts for.
+    pub fn from_env() -> Option<Locale> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Locale::parse(&value))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Tr => "tr",
+        }
+    }
+}
+
+/// Replaces each `{}` in a catalog text with the next argument. Missing
+/// arguments leave the placeholder empty; extra ones are ignored.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut pieces = template.split("{}");
+    out.push_str(pieces.next().unwrap_or_default());
+    for piece in pieces {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(piece);
+    }
+    out
+}
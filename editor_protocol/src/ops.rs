@@ -0,0 +1,161 @@
+//! Line-based edit operations used for undo/redo and for rebasing edits over
+//! concurrent changes.
+
+/// Replacement of a contiguous block of lines: the `removed` lines starting at
+/// `start` are replaced by `inserted`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineEdit {
+    pub start: usize,
+    pub removed: Vec<String>,
+    pub inserted: Vec<String>,
+}
+
+impl LineEdit {
+    /// The single edit turning `old` into `new`, found by trimming the common
+    /// prefix and suffix. Returns `None` when both are equal.
+    pub fn between<S: AsRef<str>>(old: &[S], new: &[S]) -> Option<LineEdit> {
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a.as_ref() == b.as_ref()).count();
+        if prefix == old.len() && prefix == new.len() {
+            return None;
+        }
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a.as_ref() == b.as_ref())
+            .count();
+        let to_strings = |lines: &[S]| lines.iter().map(|line| line.as_ref().to_string()).collect();
+        Some(LineEdit {
+            start: prefix,
+            removed: to_strings(&old[prefix..old.len() - suffix]),
+            inserted: to_strings(&new[prefix..new.len() - suffix]),
+        })
+    }
+
+    /// The edit that reverts this one.
+    pub fn inverse(&self) -> LineEdit {
+        LineEdit { start: self.start, removed: self.inserted.clone(), inserted: self.removed.clone() }
+    }
+
+    /// Applies the edit, failing if the lines it expects to remove are not there.
+    pub fn apply(&self, lines: &mut Vec<String>) -> Result<(), String> {
+        let end = self.start + self.removed.len();
+        if end > lines.len() || lines[self.start..end] != self.removed[..] {
+            return Err(format!("lines {}..{} no longer match", self.start + 1, end + 1));
+        }
+        lines.splice(self.start..end, self.inserted.iter().cloned());
+        Ok(())
+    }
+
+    /// Rebases this edit over `other`, where both were made against the same
+    /// lines and `other` has been applied first. Returns `None` when the two
+    /// touch overlapping lines.
+    pub fn transform(&self, other: &LineEdit) -> Option<LineEdit> {
+        let other_end = other.start + other.removed.len();
+        let own_end = self.start + self.removed.len();
+        if other_end <= self.start {
+            // `other` is entirely above: shift by the change in line count.
+            let start = self.start + other.inserted.len() - other.removed.len();
+            Some(LineEdit { start, ..self.clone() })
+        } else if other.start >= own_end {
+            Some(self.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Merges `next`, made right after this edit, into a single edit. Only
+    /// possible when `next` stays within the lines this edit inserted.
+    pub fn compose(&self, next: &LineEdit) -> Option<LineEdit> {
+        let inserted_end = self.start + self.inserted.len();
+        if next.start < self.start || next.start + next.removed.len() > inserted_end {
+            return None;
+        }
+        let mut inserted = self.inserted.clone();
+        LineEdit { start: next.start - self.start, ..next.clone() }.apply(&mut inserted).ok()?;
+        Some(LineEdit { start: self.start, removed: self.removed.clone(), inserted })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    fn edit(start: usize, removed: &[&str], inserted: &[&str]) -> LineEdit {
+        let to_strings = |lines: &[&str]| lines.iter().map(|line| line.to_string()).collect();
+        LineEdit { start, removed: to_strings(removed), inserted: to_strings(inserted) }
+    }
+
+    #[test]
+    fn between_finds_the_changed_block() {
+        assert_eq!(LineEdit::between(&["a", "b", "c"], &["a", "x", "y", "c"]), Some(edit(1, &["b"], &["x", "y"])));
+        assert_eq!(LineEdit::between(&["a"], &["a", "b"]), Some(edit(1, &[], &["b"])));
+        assert_eq!(LineEdit::between::<&str>(&[], &[]), None);
+        assert_eq!(LineEdit::between(&["a", "b"], &["a", "b"]), None);
+    }
+
+    #[test]
+    fn between_does_not_overlap_prefix_and_suffix() {
+        // "a" is both a common prefix and suffix candidate
+        assert_eq!(LineEdit::between(&["a"], &["a", "a"]), Some(edit(1, &[], &["a"])));
+    }
+
+    #[test]
+    fn apply_and_inverse_round_trip() {
+        let original = lines("a\nb\nc");
+        let change = LineEdit::between(&original, &lines("a\nx\ny\nc")).unwrap();
+        let mut text = original.clone();
+        change.apply(&mut text).unwrap();
+        assert_eq!(text, lines("a\nx\ny\nc"));
+        change.inverse().apply(&mut text).unwrap();
+        assert_eq!(text, original);
+    }
+
+    #[test]
+    fn apply_rejects_lines_that_changed() {
+        let mut text = lines("a\nB\nc");
+        assert!(edit(1, &["b"], &["x"]).apply(&mut text).is_err());
+        assert!(edit(3, &["d"], &[]).apply(&mut text).is_err());
+        assert_eq!(text, lines("a\nB\nc"));
+    }
+
+    #[test]
+    fn transform_shifts_over_edits_above() {
+        let own = edit(3, &["d"], &["D"]);
+        assert_eq!(own.transform(&edit(0, &["a"], &["a1", "a2"])), Some(edit(4, &["d"], &["D"])));
+        assert_eq!(own.transform(&edit(1, &["b", "c"], &[])), Some(edit(1, &["d"], &["D"])));
+    }
+
+    #[test]
+    fn transform_ignores_edits_below_and_rejects_overlaps() {
+        let own = edit(1, &["b", "c"], &["x"]);
+        assert_eq!(own.transform(&edit(3, &["d"], &[])), Some(own.clone()));
+        assert_eq!(own.transform(&edit(2, &["c"], &["C"])), None);
+        assert_eq!(own.transform(&edit(0, &["a", "b"], &[])), None);
+    }
+
+    #[test]
+    fn compose_merges_edits_within_the_inserted_lines() {
+        let first = edit(1, &["b"], &["bx"]);
+        let second = edit(1, &["bx"], &["bxy", "z"]);
+        let composed = first.compose(&second).unwrap();
+        assert_eq!(composed, edit(1, &["b"], &["bxy", "z"]));
+
+        let mut text = lines("a\nb\nc");
+        composed.apply(&mut text).unwrap();
+        assert_eq!(text, lines("a\nbxy\nz\nc"));
+    }
+
+    #[test]
+    fn compose_refuses_edits_outside_the_inserted_lines() {
+        let first = edit(1, &["b"], &["bx"]);
+        assert_eq!(first.compose(&edit(0, &["a"], &["A"])), None);
+        assert_eq!(first.compose(&edit(1, &["bx", "c"], &[])), None);
+        // Removed text that does not match what was inserted
+        assert_eq!(first.compose(&edit(1, &["other"], &[])), None);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::client_handler::{self, ClientWriter, SharedServerState};
+use crate::config::{ServerConfig, SharedConfig};
+use crate::document_manager::PersistenceHandle;
+use crate::i18n::{self, error_message};
+use crate::logging;
+use crate::metrics::METRICS;
+use editor_protocol::*;
+use std::collections::BTreeSet;
+use std::fs::{self, DirBuilder};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tracing::{error, info, info_span, warn};
+
+/// Everything an admin command may need to touch.
+pub struct AdminContext {
+    pub shared_state: SharedServerState,
+    pub persistence: PersistenceHandle,
+    pub config: SharedConfig,
+}
+
+/// Listens for admin connections on a local Unix socket. Access is controlled
+/// by the socket file's permissions, so only the server's user can use it.
+pub fn start_admin_listener(path: &str, context: AdminContext) -> io::Result<JoinHandle<()>> {
+    let listener = bind_socket(path)?;
+    info!(path, "Admin channel listening");
+    let context = Arc::new(context);
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let context = Arc::clone(&context);
+                    thread::spawn(move || handle_admin_connection(stream, &context));
+                }
+                Err(e) => warn!(error = %e, "Admin connection failed"),
+            }
+        }
+    }))
+}
+
+/// Binds the admin socket with 0600 permissions. The socket is created inside
+/// a fresh 0700 directory next to `path` and only renamed into place once its
+/// mode is fixed, so nobody else can connect in between.
+fn bind_socket(path: &str) -> io::Result<UnixListener> {
+    remove_stale_socket(path)?;
+    let private_dir = format!("{}.{}.tmp", path, std::process::id());
+    DirBuilder::new().mode(0o700).create(&private_dir)?;
+    let private_path = Path::new(&private_dir).join("socket");
+    let listener = UnixListener::bind(&private_path)
+        .and_then(|listener| {
+            fs::set_permissions(&private_path, fs::Permissions::from_mode(0o600))?;
+            fs::rename(&private_path, path)?;
+            Ok(listener)
+        })
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&private_path);
+        });
+    let _ = fs::remove_dir(&private_dir);
+    listener
+}
+
+/// A socket file left behind by a crashed server would make bind fail, so it
+/// is removed. Anything else at the path, or a socket another server still
+/// listens on, is left alone and reported.
+fn remove_stale_socket(path: &str) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "path exists and is not a socket"));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AddrInUse, "another server is listening on the socket"));
+    }
+    fs::remove_file(path)
+}
+
+/// Removes the socket file on shutdown.
+pub fn remove_socket(path: &str) {
+    if let Err(e) = fs::remove_file(path) {
+        warn!(path, error = %e, "Could not remove admin socket");
+    }
+}
+
+fn handle_admin_connection(stream: UnixStream, context: &AdminContext) {
+    let _span = info_span!("admin").entered();
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            warn!(error = %e, "Admin connection failed");
+            return;
+        }
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        info!(command = line, "Admin command received");
+        let reply = match execute(line, context) {
+            Ok(lines) => std::iter::once(ADMIN_OK.to_string()).chain(lines).collect::<Vec<_>>().join("\n"),
+            Err((code, detail)) => {
+                warn!(command = line, code = code.as_str(), detail = %detail, "Admin command failed");
+                format_error(code, None, &error_message(code, detail))
+            }
+        };
+        if writeln!(writer, "{}\n{}", reply, END_OF_MESSAGE_DELIMITER).and_then(|_| writer.flush()).is_err() {
+            break;
+        }
+    }
+}
+
+/// Admin command failures carry the same error codes (and texts) as the
+/// client protocol: the code and the detail filling its text.
+type AdminError = (ErrorCode, String);
+
+fn execute(line: &str, context: &AdminContext) -> Result<Vec<String>, AdminError> {
+    let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+    let argument = argument.trim();
+    match command {
+        ADMIN_SESSIONS_CMD => Ok(list_sessions(context)),
+        ADMIN_KICK_CMD if !argument.is_empty() => kick(context, argument),
+        ADMIN_BROADCAST_CMD if !argument.is_empty() => {
+            let writers: Vec<ClientWriter> = {
+                let state = context.shared_state.lock().unwrap();
+                state.clients.values().map(|client| client.writer.clone()).collect()
+            };
+            send_to_all(&writers, &format_command_with_arg(ANNOUNCEMENT_MSG, argument));
+            Ok(vec![format!("Announcement sent to {} client(s).", writers.len())])
+        }
+        ADMIN_SAVE_CMD => Ok(vec![format!("{} document(s) saved.", context.persistence.flush())]),
+        ADMIN_RELOAD_CONFIG_CMD => Ok(reload_config(context)),
+        ADMIN_LOCK_CMD | ADMIN_UNLOCK_CMD if !argument.is_empty() => {
+            let locked = command == ADMIN_LOCK_CMD;
+            let editors: Vec<ClientWriter> = {
+                let mut state = context.shared_state.lock().unwrap();
+                let document = state
+                    .documents
+                    .get_mut(argument)
+                    .ok_or_else(|| (ErrorCode::DocumentNotFound, argument.to_string()))?;
+                document.locked = locked;
+                state
+                    .clients
+                    .values()
+                    .filter(|client| client.open_documents.contains(argument))
+                    .map(|client| client.writer.clone())
+                    .collect()
+            };
+            let status = if locked { "locked" } else { "unlocked" };
+            send_to_all(
+                &editors,
+                &format_command_with_arg(ANNOUNCEMENT_MSG, &format!("Document '{}' was {} by an administrator.", argument, status)),
+            );
+            Ok(vec![format!("Document '{}' {}.", argument, status)])
+        }
+        ADMIN_KICK_CMD | ADMIN_BROADCAST_CMD | ADMIN_LOCK_CMD | ADMIN_UNLOCK_CMD => {
+            Err((ErrorCode::BadRequest, format!("{} <argument>", command)))
+        }
+        _ => Err((ErrorCode::UnknownCommand, command.to_string())),
+    }
+}
+
+/// One line per connected client and per detached (resumable) session:
+/// `<username>\t<address>\t<documents>\t<state>`, where `<documents>` is the
+/// comma-separated list of open documents.
+fn list_sessions(context: &AdminContext) -> Vec<String> {
+    let state = context.shared_state.lock().unwrap();
+    let mut lines: Vec<String> = state
+        .clients
+        .iter()
+        .map(|(addr, client)| {
+            format!(
+                "{}\t{}\t{}\tconnected",
+                client.username.as_deref().unwrap_or("-"),
+                addr,
+                open_documents(&client.open_documents),
+            )
+        })
+        .collect();
+    lines.extend(state.sessions.values().filter_map(|session| {
+        let detached_at = session.detached_at?;
+        Some(format!(
+            "{}\t-\t{}\tdetached {}s",
+            session.username,
+            open_documents(&session.open_documents),
+            detached_at.elapsed().as_secs(),
+        ))
+    }));
+    lines.sort();
+    lines
+}
+
+fn open_documents(documents: &BTreeSet<String>) -> String {
+    if documents.is_empty() {
+        "-".to_string()
+    } else {
+        documents.iter().cloned().collect::<Vec<_>>().join(",")
+    }
+}
+
+/// Ends every session of `username`. The client is told not to reconnect and
+/// its resumable sessions are dropped.
+fn kick(context: &AdminContext, username: &str) -> Result<Vec<String>, AdminError> {
+    let clients: Vec<(ClientWriter, Option<TcpStream>)> = {
+        let mut state = context.shared_state.lock().unwrap();
+        state.sessions.retain(|_, session| session.username != username);
+        state
+            .clients
+            .values()
+            .filter(|client| client.username.as_deref() == Some(username))
+            .map(|client| (client.writer.clone(), client.connection.try_clone().ok()))
+            .collect()
+    };
+    if clients.is_empty() {
+        return Err((ErrorCode::UserNotFound, username.to_string()));
+    }
+    let message = format_command_with_arg(KICKED_MSG, "disconnected by an administrator");
+    for (writer, connection) in &clients {
+        client_handler::send_message(writer, &message);
+        if let Some(connection) = connection {
+            let _ = connection.shutdown(Shutdown::Both);
+        }
+    }
+    Ok(vec![format!("Kicked {} connection(s) of '{}'.", clients.len(), username)])
+}
+
+/// Sends `message` to every writer. Called after the state lock is released,
+/// so a client that stops reading cannot hold up other requests.
+fn send_to_all(writers: &[ClientWriter], message: &str) {
+    for writer in writers {
+        client_handler::send_message(writer, message);
+    }
+    METRICS.broadcast(message, writers.len());
+}
+
+/// Re-reads the config file. Flush, keepalive, language, allowed extensions and
+/// log level/redaction settings apply right away (keepalive timeouts and
+/// connection limits only to new connections); storage, admin socket, metrics
+/// address and log format changes need a restart and are kept as they are.
+fn reload_config(context: &AdminContext) -> Vec<String> {
+    let (mut new_config, mut notes) = ServerConfig::load();
+    for problem in &notes {
+        warn!("{}", problem);
+    }
+    let mut config = context.config.write().unwrap();
+    if new_config.storage_backend != config.storage_backend
+        || new_config.documents_dir != config.documents_dir
+        || new_config.sqlite_path != config.sqlite_path
+    {
+        notes.push("Storage settings changed; restart the server to apply them.".to_string());
+        new_config.storage_backend = config.storage_backend;
+        new_config.documents_dir = config.documents_dir.clone();
+        new_config.sqlite_path = config.sqlite_path.clone();
+    }
+    if new_config.admin_socket != config.admin_socket {
+        notes.push("Admin socket changed; restart the server to apply it.".to_string());
+        new_config.admin_socket = config.admin_socket.clone();
+    }
+    if new_config.metrics_address != config.metrics_address {
+        notes.push("Metrics address changed; restart the server to apply it.".to_string());
+        new_config.metrics_address = config.metrics_address.clone();
+    }
+    if new_config.log_format != config.log_format {
+        notes.push("Log format changed; restart the server to apply it.".to_string());
+        new_config.log_format = config.log_format;
+    }
+    i18n::set_locale(new_config.language);
+    if let Err(e) = logging::apply(&new_config) {
+        error!(error = %e, "Could not apply log settings");
+        notes.push(format!("Log settings not applied: {}", e));
+        new_config.log_level = config.log_level.clone();
+    }
+    info!("Config reloaded");
+    *config = new_config;
+    notes.insert(0, "Config reloaded.".to_string());
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A socket path under the system temp dir, removed on drop.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(test: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("editor_admin_{}_{}", std::process::id(), test));
+            let _ = fs::remove_file(&path);
+            TempPath(path)
+        }
+
+        fn as_str(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn socket_is_private_to_the_owner() {
+        let path = TempPath::new("mode");
+        let _listener = bind_socket(path.as_str()).unwrap();
+        let mode = fs::metadata(&path.0).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!Path::new(&format!("{}.{}.tmp", path.as_str(), std::process::id())).exists());
+    }
+
+    #[test]
+    fn stale_socket_is_replaced() {
+        let path = TempPath::new("stale");
+        drop(UnixListener::bind(&path.0).unwrap());
+        let _listener = bind_socket(path.as_str()).unwrap();
+        assert!(UnixStream::connect(&path.0).is_ok());
+    }
+
+    #[test]
+    fn live_socket_is_left_alone() {
+        let path = TempPath::new("live");
+        let _other = UnixListener::bind(&path.0).unwrap();
+        let error = bind_socket(path.as_str()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AddrInUse);
+        assert!(UnixStream::connect(&path.0).is_ok());
+    }
+
+    #[test]
+    fn other_files_are_not_removed() {
+        let path = TempPath::new("file");
+        fs::write(&path.0, "notes").unwrap();
+        let error = bind_socket(path.as_str()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path.0).unwrap(), "notes");
+    }
+}
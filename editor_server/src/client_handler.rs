@@ -1,265 +1,851 @@
 use editor_protocol::*;
-use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
-use std::net::TcpStream;
+use editor_protocol::diff::{diff_lines, format_diff_lines};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+use std::io::{BufReader, ErrorKind, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream};
 use std::sync::{Arc, Mutex};
-use crate::document_manager;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{debug, field, info, info_span, trace, warn};
+use crate::config::{ConnectionLimits, SharedConfig};
+use crate::document::Document;
+use crate::document_manager::PersistenceHandle;
+use crate::i18n::error_message;
+use crate::limits::{read_line_limited, BodyError, LineRead, RateLimiter};
+use crate::logging;
+use crate::metrics::METRICS;
+use crate::search_index;
 
 // Sunucunun paylaşılan durumunu (belgeler ve aktif istemciler) temsil eder.
-// Bu yapı, main.rs içinde tanımlanıp Arc<Mutex<>> ile sarmalanacak.
+// Bu yapı, main.rs içinde tanımlanıp Arc<Mutex<>> ile sarmalanır.
 pub struct ServerSharedState {
-    pub documents: HashMap<String, String>, // belge_adı -> içerik
-    pub clients: Vec<ClientInfo>, // Aktif istemcilerin bilgileri
+    pub documents: HashMap<String, Document>, // belge_adı -> belge
+    pub clients: HashMap<SocketAddr, ClientInfo>, // Aktif istemcilerin bilgileri
+    pub sessions: HashMap<String, Session>, // devam_jetonu -> oturum
 }
 
+// Kopan bir bağlantının ardından aynı jetonla devam ettirilebilen oturum.
+pub struct Session {
+    pub username: String,
+    pub open_documents: BTreeSet<String>,
+    // Bağlantı koptuysa ne zaman koptuğu; bağlıyken None
+    pub detached_at: Option<Instant>,
+}
+
+// Kopmuş bir oturumun devam ettirilebileceği süre
+const SESSION_RESUME_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Bir SEARCH yanıtında gönderilecek en fazla sonuç
+const MAX_SEARCH_RESULTS: usize = 100;
+
 // Her bir bağlı istemcinin bilgisini tutar.
-#[derive(Clone)]
 pub struct ClientInfo {
-    pub username: String,
-    // İstemciye mesaj göndermek için TcpStream'in yazma kısmını klonlamak yerine,
-    // her istemciye özgü bir ID veya stream'i doğrudan tutabiliriz.
-    // Ancak yayın yaparken tüm client'ların stream'lerine erişmemiz gerekecek.
-    // Şimdilik, her client handler kendi stream'ini yönetecek ve yayınlar ana döngüden yapılacak.
-    // Bu yüzden burada stream'i tutmayalım, bunun yerine ClientHandler'a stream'i doğrudan verelim.
-    pub current_document_name: Option<String>,
-    // İstemciye mesaj göndermek için bir kanal (mpsc) veya stream'in bir kopyası (Arc<Mutex<TcpStream>>) kullanılabilir.
-    // Basitlik adına, broadcast işlemleri için tüm client stream'lerinin bir listesi ana sunucu yapısında tutulacak.
-    // Bu struct şimdilik sadece username ve aktif belgeyi tutsun.
+    pub username: Option<String>,
+    // Güncellemelerine abone olunan (istemcide sekme olarak açık) belgeler
+    pub open_documents: BTreeSet<String>,
+    // İstemciye mesaj göndermek için paylaşılan yazıcı
+    pub writer: ClientWriter,
+    // Sunucu kapanırken bağlantıyı kapatmak için stream'in bir kopyası
+    pub connection: TcpStream,
 }
 
+pub type SharedServerState = Arc<Mutex<ServerSharedState>>;
 
 // İstemci için paylaşılan mesaj gönderme yeteneği
-type ClientWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+pub type ClientWriter = Arc<Mutex<Box<dyn Write + Send>>>;
 
 // Her bir istemci bağlantısını yönetir.
 pub fn handle_client(
     stream: TcpStream,
-    server_documents_arc: Arc<Mutex<HashMap<String, String>>>,
-    // Tüm istemcilere yayın yapmak için bir istemci listesi (yazıcıları ile birlikte)
-    // Her client için bir ID ve ona ait bir writer tutulabilir.
-    // Veya daha basitçe, her client_handler kendi stream'inin bir klonunu (yazma kısmı) tutar
-    // ve global bir client listesi de bu yazıcıları tutar.
-    // Şimdilik, yayınlar için tüm client_handler'ların writer'larına bir şekilde erişilmesi gerekecek.
-    // client_writers: Arc<Mutex<Vec<ClientWriter>>>,
-    // Her client için ayrı bir ID ve ona ait bir writer tutmak daha yönetilebilir olabilir.
-    // (peer_addr -> writer) şeklinde bir HashMap kullanılabilir.
-    all_clients_writers_arc: Arc<Mutex<HashMap<std::net::SocketAddr, ClientWriter>>>
+    shared_state: SharedServerState,
+    persistence: PersistenceHandle,
+    keepalive_timeout: Duration,
+    limits: ConnectionLimits,
+    config: SharedConfig,
 ) {
     let peer_addr = stream.peer_addr().expect("Bağlı istemcinin adresi alınamadı.");
-    println!("Yeni istemci bağlandı: {}", peer_addr);
+    // Bu bağlantıdaki tüm log kayıtları adresi ve (CONNECT'ten sonra) kullanıcı adını taşır
+    let span = info_span!("session", peer = %peer_addr, user = field::Empty);
+    let _entered = span.enter();
+    info!("Client connected");
 
+    // Bu süre boyunca hiçbir şey (PING dahil) gelmezse bağlantı ölü sayılır;
+    // yazma zaman aşımı da ölü bir istemcinin yayınları kilitlemesini önler.
+    if let Err(e) = stream
+        .set_read_timeout(Some(keepalive_timeout))
+        .and_then(|_| stream.set_write_timeout(Some(keepalive_timeout)))
+    {
+        warn!(error = %e, "Could not set socket timeouts");
+    }
     let reader_stream = stream.try_clone().expect("Stream klonlanamadı (okuma).");
+    let connection = stream.try_clone().expect("Stream klonlanamadı (kapatma).");
     let writer_stream = stream; // Orijinal stream yazma için kullanılır.
 
     let mut reader = BufReader::new(reader_stream);
-    let mut writer: ClientWriter = Arc::new(Mutex::new(Box::new(writer_stream)));
-    
-    // Bu istemcinin yazıcısını global listeye ekle
-    all_clients_writers_arc.lock().unwrap().insert(peer_addr, writer.clone());
+    let writer: ClientWriter = Arc::new(Mutex::new(Box::new(writer_stream)));
 
+    // Bu istemciyi global listeye ekle (aynı adresten çok fazla bağlantı yoksa)
+    {
+        let mut state = shared_state.lock().unwrap();
+        let from_same_ip = state.clients.keys().filter(|addr| addr.ip() == peer_addr.ip()).count();
+        if limits.max_connections_per_ip > 0 && from_same_ip >= limits.max_connections_per_ip {
+            drop(state);
+            METRICS.limit_violation("connections_per_ip");
+            warn!(connections = from_same_ip, "Too many connections from this address, rejecting");
+            send_error(&writer, ErrorCode::TooManyConnections, limits.max_connections_per_ip);
+            return;
+        }
+        state.clients.insert(peer_addr, ClientInfo {
+            username: None,
+            open_documents: BTreeSet::new(),
+            writer: writer.clone(),
+            connection,
+        });
+    }
 
     let mut current_username: Option<String> = None;
-    let mut current_document_name_for_client: Option<String> = None;
+    let mut open_documents: BTreeSet<String> = BTreeSet::new();
+    let mut session_token: Option<String> = None;
+    let mut disconnect_requested = false;
+    let mut rate_limiter = RateLimiter::new(limits.max_messages_per_second);
+    let mut violations = 0;
 
     loop {
         let mut command_line = String::new();
-        match reader.read_line(&mut command_line) {
-            Ok(0) => { // Bağlantı kapandı
-                println!("İstemci {} bağlantıyı kesti (EOF).", peer_addr);
+        match read_line_limited(&mut reader, &mut command_line, limits.max_line_length) {
+            Ok(LineRead::Eof) => { // Bağlantı kapandı
+                debug!("Client closed the connection");
                 break;
             }
-            Ok(_) => {
-                let command_line = command_line.trim();
+            Ok(LineRead::TooLong(bytes)) => {
+                METRICS.bytes_received(bytes);
+                // Satır okunmadığı için isteğin kimliği de bilinmiyor
+                let error = BodyError::LineTooLong(limits.max_line_length);
+                if record_violation(&mut Reply::new(&writer, None), &mut violations, &limits, error.limit(), error.code(), error.max()) {
+                    disconnect_requested = true;
+                    break;
+                }
+            }
+            Ok(LineRead::Line(bytes)) => {
+                METRICS.bytes_received(bytes);
+                let (request_id, command_line) = split_request_id(command_line.trim());
                 if command_line.is_empty() { continue; }
+                let mut reply = Reply::new(&writer, request_id);
 
                 let parts: Vec<&str> = command_line.splitn(2, ' ').collect();
                 let command = parts[0];
                 let argument = if parts.len() > 1 { parts[1] } else { "" };
 
-                println!("{}'dan alındı: {}", current_username.as_deref().unwrap_or("Bilinmeyen"), command_line);
+                match CLIENT_COMMANDS.iter().find(|known| **known == command) {
+                    Some(known) => {
+                        METRICS.command_received(known);
+                        if command != PING_CMD && command != PONG_MSG {
+                            debug!(command, argument = %logged_argument(command, argument), "Command received");
+                        }
+                    }
+                    None => {
+                        METRICS.command_received("unknown");
+                        // Bilinmeyen bir satır, kaybolmuş belge içeriği olabilir
+                        warn!(line = %logging::content(command_line), "Unknown command received");
+                    }
+                }
+
+                // Gövde, sınırlar denetlenmeden önce okunur; reddedilen bir güncellemenin
+                // satırları komut olarak yorumlanmamalı.
+                let mut update_body = (command == UPDATE_DOCUMENT_CMD).then(|| read_update_body(&mut reader, &limits));
+                if let Some(body) = &update_body {
+                    let logged = body.as_deref().map_or_else(|e| e.to_string(), |content| logging::content(content).to_string());
+                    debug!(document = argument, content = %logged, "Update received");
+                }
+
+                // PONG, sunucunun kendi PING'ine yanıttır; hıza sayılmaz
+                if command != PONG_MSG && !rate_limiter.allow() {
+                    if record_violation(&mut reply, &mut violations, &limits, "message_rate", ErrorCode::RateLimited, "") {
+                        disconnect_requested = true;
+                        break;
+                    }
+                    if update_body.is_some() {
+                        send_server_content(&writer, &shared_state, argument);
+                    }
+                    continue;
+                }
+
+                // Belge adı alan her komutta ad, durum ya da depolama kullanılmadan önce denetlenir
+                if let Some(doc_name) = document_argument(command, argument) {
+                    if let Err((code, detail)) = check_document_name(doc_name) {
+                        reply.error(code, detail);
+                        continue;
+                    }
+                }
 
                 match command {
                     CONNECT_CMD => {
-                        if !argument.is_empty() {
-                            current_username = Some(argument.to_string());
-                            send_message(&writer, CONNECTED_OK_MSG);
-                            println!("Kullanıcı {} bağlandı.", argument);
+                        let (username, resume_token) = parse_connect_argument(argument);
+                        if let Some(ref uname) = current_username {
+                            // Bağlantı başına tek oturum; yoksa önceki oturum hiç kopmuş
+                            // sayılmadan sonsuza kadar kalırdı
+                            reply.error(ErrorCode::AlreadyConnected, uname);
+                        } else if !username.is_empty() {
+                            let mut state = shared_state.lock().unwrap();
+                            let state = &mut *state;
+                            prune_expired_sessions(&mut state.sessions);
+                            // Aynı kullanıcının kopmuş oturumu varsa abonelikleriyle birlikte devam ettir
+                            let resumed = resume_token.and_then(|token| find_detached_session(&state.sessions, username, token));
+                            let token = match resumed.clone().map_or_else(new_resume_token, Ok) {
+                                Ok(token) => token,
+                                Err(e) => {
+                                    warn!(error = %e, "Could not generate a session token");
+                                    reply.error(ErrorCode::Internal, e);
+                                    continue;
+                                }
+                            };
+                            span.record("user", username);
+                            current_username = Some(username.to_string());
+                            let session = state.sessions.entry(token.clone()).or_insert_with(|| Session {
+                                username: username.to_string(),
+                                open_documents: BTreeSet::new(),
+                                detached_at: None,
+                            });
+                            session.detached_at = None;
+                            if resumed.is_some() {
+                                open_documents = session
+                                    .open_documents
+                                    .iter()
+                                    .filter(|doc_name| state.documents.contains_key(*doc_name))
+                                    .cloned()
+                                    .collect();
+                                info!(documents = ?open_documents, "Session resumed");
+                            } else {
+                                info!("User signed in");
+                            }
+                            session_token = Some(token.clone());
+                            if let Some(client) = state.clients.get_mut(&peer_addr) {
+                                client.username = current_username.clone();
+                                client.open_documents = open_documents.clone();
+                            }
+                            send_message(&writer, &format_command_with_arg(CONNECTED_OK_MSG, &token));
                             // Kullanıcıya mevcut belge listesini gönder
-                            send_available_documents(&writer, &server_documents_arc.lock().unwrap());
+                            send_available_documents(&writer, &state.documents);
                             // Diğerlerine haber ver
                             broadcast_message_to_others(
-                                &all_clients_writers_arc.lock().unwrap(),
+                                &state.clients,
                                 peer_addr,
-                                &format_command_with_arg(USER_JOINED_MSG, argument)
+                                &format_command_with_arg(USER_JOINED_MSG, username)
                             );
                         } else {
-                            send_message(&writer, "HATA Kullanıcı adı sağlanmadı");
+                            reply.error(ErrorCode::BadRequest, "CONNECT <username>");
                         }
                     }
                     LIST_DOCUMENTS_CMD => {
-                        send_available_documents(&writer, &server_documents_arc.lock().unwrap());
+                        send_available_documents(&writer, &shared_state.lock().unwrap().documents);
                     }
                     CREATE_DOCUMENT_CMD => {
                         if !argument.is_empty() {
-                            let mut doc_name = argument.to_string();
-                            if !doc_name.ends_with(".txt") {
-                                doc_name.push_str(".txt");
-                            }
-                            let mut docs = server_documents_arc.lock().unwrap();
-                            if !docs.contains_key(&doc_name) {
-                                docs.insert(doc_name.clone(), String::new());
-                                if document_manager::save_document(&doc_name, "").is_ok() {
+                            // Uzantılar her istekte okunur; RELOAD_CONFIG açık bağlantılara da yansır
+                            let allowed_extensions = config.read().unwrap().allowed_extensions.clone();
+                            let doc_name = match document_file_name(argument, &allowed_extensions) {
+                                Ok(doc_name) => doc_name,
+                                Err((code, detail)) => {
+                                    reply.error(code, detail);
+                                    continue;
+                                }
+                            };
+                            let mut state = shared_state.lock().unwrap();
+                            if !state.documents.contains_key(&doc_name) {
+                                state.documents.insert(doc_name.clone(), Document::new(String::new()));
+                                if persistence.save_now(&doc_name, "", current_username.as_deref().unwrap_or("")).is_ok() {
                                     send_message(&writer, &format_command_with_arg(DOCUMENT_CREATED_OK_MSG, &doc_name));
                                     // Diğer istemcilere bildir
                                      broadcast_message_to_all(
-                                        &all_clients_writers_arc.lock().unwrap(),
+                                        &state.clients,
                                         &format_command_with_arg(NEW_DOCUMENT_AVAILABLE_MSG, &doc_name)
                                     );
                                 } else {
-                                    send_message(&writer, &format!("{} Belge diske kaydedilemedi.", DOCUMENT_CREATED_FAIL_MSG));
-                                    docs.remove(&doc_name); // Başarısız olursa geri al
+                                    reply.error(ErrorCode::StorageFailed, &doc_name);
+                                    state.documents.remove(&doc_name); // Başarısız olursa geri al
+                                    let _ = persistence.delete(&doc_name);
                                 }
                             } else {
-                                send_message(&writer, &format!("{} Belge zaten var.", DOCUMENT_CREATED_FAIL_MSG));
+                                reply.error(ErrorCode::DocumentExists, &doc_name);
                             }
                         } else {
-                            send_message(&writer, "HATA Belge adı sağlanmadı");
+                            reply.error(ErrorCode::BadRequest, "CREATE_DOCUMENT <document>");
                         }
                     }
                     SWITCH_DOCUMENT_CMD => {
                         if !argument.is_empty() {
                             let doc_name_to_switch = argument.to_string();
-                            let docs = server_documents_arc.lock().unwrap();
-                            if let Some(content) = docs.get(&doc_name_to_switch) {
-                                current_document_name_for_client = Some(doc_name_to_switch.clone());
+                            let mut state = shared_state.lock().unwrap();
+                            let state = &mut *state;
+                            if let Some(document) = state.documents.get(&doc_name_to_switch) {
+                                // Önceki belgeler de açık kalır; istemci onları sekmelerde tutar
+                                open_documents.insert(doc_name_to_switch.clone());
+                                if let Some(client) = state.clients.get_mut(&peer_addr) {
+                                    client.open_documents = open_documents.clone();
+                                }
                                 send_message(&writer, &format_command_with_arg(DOCUMENT_SWITCHED_MSG, &doc_name_to_switch));
-                                send_full_document_content(&writer, &doc_name_to_switch, content);
+                                send_full_document_content(&writer, &doc_name_to_switch, &document.content);
                                 if let Some(ref uname) = current_username {
-                                     broadcast_message_to_others( // Belki farklı bir mesaj ("USER_SWITCHED_DOC")
-                                        &all_clients_writers_arc.lock().unwrap(),
+                                    broadcast_message_to_others(
+                                        &state.clients,
                                         peer_addr,
-                                        &format!("{} {} {} belgesine geçti.", USER_JOINED_MSG, uname, doc_name_to_switch)
+                                        &format_user_switched(uname, &doc_name_to_switch)
                                     );
                                 }
                             } else {
-                                send_message(&writer, &format!("HATA Belge bulunamadı: {}", doc_name_to_switch));
+                                reply.error(ErrorCode::DocumentNotFound, &doc_name_to_switch);
                             }
                         } else {
-                            send_message(&writer, "HATA Geçiş yapılacak belge adı sağlanmadı");
+                            reply.error(ErrorCode::BadRequest, "SWITCH_DOCUMENT <document>");
                         }
                     }
                      GET_DOCUMENT_CMD => { // SWITCH_DOCUMENT ile benzer, ama belki sadece içeriği gönderir.
                         if !argument.is_empty() {
                             let doc_name_to_get = argument.to_string();
-                            let docs = server_documents_arc.lock().unwrap();
-                            if let Some(content) = docs.get(&doc_name_to_get) {
+                            let mut state = shared_state.lock().unwrap();
+                            let state = &mut *state;
+                            if let Some(document) = state.documents.get(&doc_name_to_get) {
                                 // İstemcinin aktif belgesini değiştirmeden sadece içeriği gönder.
-                                // Veya SWITCH gibi davranabilir. Java kodunda GET_DOCUMENT sonrası currentDocumentName ayarlanıyor.
-                                current_document_name_for_client = Some(doc_name_to_get.clone());
-                                send_full_document_content(&writer, &doc_name_to_get, content);
+                                // SWITCH gibi belgeyi açık belgelere ekler (arka plandaki bir sekme).
+                                open_documents.insert(doc_name_to_get.clone());
+                                if let Some(client) = state.clients.get_mut(&peer_addr) {
+                                    client.open_documents = open_documents.clone();
+                                }
+                                send_full_document_content(&writer, &doc_name_to_get, &document.content);
                             } else {
-                                send_message(&writer, &format!("HATA Belge bulunamadı: {}", doc_name_to_get));
+                                reply.error(ErrorCode::DocumentNotFound, &doc_name_to_get);
                             }
                         } else {
-                             send_message(&writer, "HATA Belge adı GET_DOCUMENT için sağlanmadı");
+                            reply.error(ErrorCode::BadRequest, "GET_DOCUMENT <document>");
+                        }
+                    }
+                    CLOSE_DOCUMENT_CMD => {
+                        if open_documents.remove(argument) {
+                            if let Some(client) = shared_state.lock().unwrap().clients.get_mut(&peer_addr) {
+                                client.open_documents = open_documents.clone();
+                            }
+                        } else if !argument.is_empty() {
+                            reply.error(ErrorCode::NotEditingDocument, argument);
+                        } else {
+                            reply.error(ErrorCode::BadRequest, "CLOSE_DOCUMENT <document>");
                         }
                     }
                     UPDATE_DOCUMENT_CMD => {
+                        let new_content_buffer = match update_body.take() {
+                            Some(Ok(content)) => content,
+                            // Bağlantı koptu; döngü bir sonraki okumada fark eder
+                            Some(Err(BodyError::Incomplete)) => continue,
+                            Some(Err(e)) => {
+                                if record_violation(&mut reply, &mut violations, &limits, e.limit(), e.code(), e.max()) {
+                                    disconnect_requested = true;
+                                    break;
+                                }
+                                send_server_content(&writer, &shared_state, argument);
+                                continue;
+                            }
+                            None => continue,
+                        };
+
                         if !argument.is_empty() {
                             let doc_to_update = argument.to_string();
-                             // İstemcinin aktif olarak düzenlediği belgeyi güncellemesine izin ver
-                            if Some(doc_to_update.clone()) == current_document_name_for_client {
-                                let mut new_content_buffer = String::new();
-                                loop {
-                                    let mut line = String::new();
-                                    match reader.read_line(&mut line) {
-                                        Ok(0) => break, // Bağlantı koptu
-                                        Ok(_) => {
-                                            if line.trim() == END_OF_MESSAGE_DELIMITER {
-                                                break;
-                                            }
-                                            new_content_buffer.push_str(&line);
-                                        }
-                                        Err(_) => break, // Hata
-                                    }
+                            // İstemcinin açık belgelerinden birini güncellemesine izin ver
+                            if open_documents.contains(&doc_to_update) {
+                                let mut state = shared_state.lock().unwrap();
+                                let author = current_username.as_deref().unwrap_or("");
+                                if let Some(document) = state.documents.get(&doc_to_update).filter(|document| document.locked) {
+                                    // Reddedilen değişikliği geri almak için güncel içeriği geri gönder
+                                    reply.error(ErrorCode::DocumentLocked, &doc_to_update);
+                                    send_message(&writer, &format_document_message(DOCUMENT_UPDATED_MSG, &doc_to_update, &document.content));
+                                } else if let Some(document) = state.documents.get_mut(&doc_to_update) {
+                                    document.update(new_content_buffer.clone(), author);
+                                    // Diske yazma işini arka plandaki kalıcılık işçisine bırak
+                                    persistence.mark_dirty(&doc_to_update, author, new_content_buffer.len());
+                                    // Belgeyi açmış diğer istemcilere bildir (güncelleyen hariç)
+                                    let update_msg = format_document_message(DOCUMENT_UPDATED_MSG, &doc_to_update, &new_content_buffer);
+                                    send_message_to_editors(&state.clients, Some(peer_addr), &doc_to_update, &update_msg);
+                                } else {
+                                    reply.error(ErrorCode::DocumentNotFound, &doc_to_update);
                                 }
-                                // Son \n'i kaldır (Java'daki gibi)
-                                if new_content_buffer.ends_with('\n') {
-                                    new_content_buffer.pop();
+                            } else {
+                                reply.error(ErrorCode::NotEditingDocument, &doc_to_update);
+                            }
+                        } else {
+                            reply.error(ErrorCode::BadRequest, "UPDATE_DOCUMENT <document>");
+                        }
+                    }
+                    HISTORY_CMD => {
+                        if !argument.is_empty() {
+                            // Geçmiş yalnızca bilinen belgeler için okunur; ad dosya yoluna dönüşür
+                            if !document_exists(&shared_state, argument) {
+                                reply.error(ErrorCode::DocumentNotFound, argument);
+                                continue;
+                            }
+                            match persistence.history(argument) {
+                                Ok(revisions) => {
+                                    // Her satır: <numara>\t<zaman damgası>\t<yazar>
+                                    let history_body = revisions
+                                        .iter()
+                                        .map(|r| format!("{}\t{}\t{}", r.number, r.timestamp, r.author))
+                                        .collect::<Vec<_>>()
+                                        .join("\n");
+                                    send_message(&writer, &format_document_message(HISTORY_LIST_MSG, argument, &history_body));
                                 }
-
-                                let mut docs = server_documents_arc.lock().unwrap();
-                                if docs.contains_key(&doc_to_update) {
-                                    docs.insert(doc_to_update.clone(), new_content_buffer.clone());
-                                    if document_manager::save_document(&doc_to_update, &new_content_buffer).is_ok() {
-                                        // Diğer istemcilere (aynı belgeyi düzenleyenlere) bildir
-                                        let update_msg = format_document_message(DOCUMENT_UPDATED_MSG, &doc_to_update, &new_content_buffer);
-                                        let all_writers = all_clients_writers_arc.lock().unwrap();
-                                        for (client_addr, client_writer_arc) in all_writers.iter() {
-                                            // TODO: Sadece aynı belgeyi düzenleyenlere göndermek için
-                                            // her istemcinin aktif belgesini de bilmemiz gerek.
-                                            // Şimdilik herkese (güncelleyen hariç) gönderelim, ama bu ideal değil.
-                                            // Daha iyisi: `current_document_name_for_client` bilgisini global client listesinde tutmak.
-                                            if *client_addr != peer_addr { // Kendisine gönderme
-                                                 // İdealde: if client_is_editing(doc_to_update) ...
-                                                send_message(client_writer_arc, &update_msg);
-                                            }
-                                        }
-                                        // Geri bildirim (Java'da yoktu ama faydalı olabilir)
-                                        // send_message(&writer, "UPDATE_ACKNOWLEDGED");
+                                Err(e) => reply.error(ErrorCode::StorageFailed, e),
+                            }
+                        } else {
+                            reply.error(ErrorCode::BadRequest, "HISTORY <document>");
+                        }
+                    }
+                    GET_REVISION_CMD => {
+                        if let Some((doc_name, revision)) = parse_revision_argument(argument) {
+                            if !document_exists(&shared_state, doc_name) {
+                                reply.error(ErrorCode::DocumentNotFound, doc_name);
+                                continue;
+                            }
+                            match persistence.load_revision(doc_name, revision) {
+                                Ok(content) => send_message(
+                                    &writer,
+                                    &format_document_message(REVISION_CONTENT_MSG, &format!("{} {}", doc_name, revision), &content),
+                                ),
+                                Err(e) => reply.error(ErrorCode::RevisionNotFound, format!("{} ({})", revision, e)),
+                            }
+                        } else {
+                            reply.error(ErrorCode::BadRequest, "GET_REVISION <document> <revision>");
+                        }
+                    }
+                    RESTORE_REVISION_CMD => {
+                        if let Some((doc_name, revision)) = parse_revision_argument(argument) {
+                            // Revizyon, belge denetlenmeden okunmaz
+                            let locked = match shared_state.lock().unwrap().documents.get(doc_name) {
+                                Some(document) => document.locked,
+                                None => {
+                                    reply.error(ErrorCode::DocumentNotFound, doc_name);
+                                    continue;
+                                }
+                            };
+                            if locked {
+                                reply.error(ErrorCode::DocumentLocked, doc_name);
+                                continue;
+                            }
+                            match persistence.load_revision(doc_name, revision) {
+                                Ok(content) => {
+                                    let mut state = shared_state.lock().unwrap();
+                                    let author = current_username.as_deref().unwrap_or("");
+                                    if state.documents.get(doc_name).is_some_and(|document| document.locked) {
+                                        reply.error(ErrorCode::DocumentLocked, doc_name);
+                                    } else if let Some(document) = state.documents.get_mut(doc_name) {
+                                        document.update(content.clone(), author);
+                                        persistence.mark_dirty(doc_name, author, content.len());
+                                        // Geri yükleyen dahil belgeyi açmış herkes yeni içeriği almalı
+                                        send_message_to_editors(
+                                            &state.clients,
+                                            None,
+                                            doc_name,
+                                            &format_document_message(DOCUMENT_UPDATED_MSG, doc_name, &content),
+                                        );
+                                        info!(document = doc_name, revision, "Document restored");
                                     } else {
-                                        send_message(&writer, "HATA Sunucuda belge kaydedilemedi.");
+                                        reply.error(ErrorCode::DocumentNotFound, doc_name);
                                     }
-                                } else {
-                                     send_message(&writer, "HATA Güncellenecek belge sunucuda bulunamadı.");
                                 }
+                                Err(e) => reply.error(ErrorCode::RevisionNotFound, format!("{} ({})", revision, e)),
+                            }
+                        } else {
+                            reply.error(ErrorCode::BadRequest, "RESTORE_REVISION <document> <revision>");
+                        }
+                    }
+                    DIFF_DOCUMENT_CMD => {
+                        if let Some((doc_name, rev_a, rev_b)) = parse_diff_argument(argument) {
+                            let old_content = resolve_revision(&shared_state, &persistence, doc_name, rev_a);
+                            let new_content = resolve_revision(&shared_state, &persistence, doc_name, rev_b);
+                            match (old_content, new_content) {
+                                (Ok(old_content), Ok(new_content)) => {
+                                    let diff_body = format_diff_lines(&diff_lines(&old_content, &new_content));
+                                    let header = format!("{} {} {}", doc_name, rev_a, rev_b);
+                                    send_message(&writer, &format_document_message(DIFF_RESULT_MSG, &header, &diff_body));
+                                }
+                                (Err((code, e)), _) | (_, Err((code, e))) => reply.error(code, e),
+                            }
+                        } else {
+                            reply.error(ErrorCode::BadRequest, "DIFF_DOCUMENT <document> <revA> <revB>");
+                        }
+                    }
+                    BLAME_CMD => {
+                        if !argument.is_empty() {
+                            let state = shared_state.lock().unwrap();
+                            if let Some(document) = state.documents.get(argument) {
+                                // Her satır için yazar; bilinmeyenler için yer tutucu
+                                let blame_body = document
+                                    .line_authors
+                                    .iter()
+                                    .map(|author| if author.is_empty() { UNKNOWN_AUTHOR } else { author.as_str() })
+                                    .collect::<Vec<_>>()
+                                    .join("\n");
+                                send_message(&writer, &format_document_message(BLAME_INFO_MSG, argument, &blame_body));
                             } else {
-                                send_message(&writer, &format!("HATA: {} belgesini düzenleme yetkiniz yok. Önce geçiş yapın.", doc_to_update));
+                                reply.error(ErrorCode::DocumentNotFound, argument);
                             }
                         } else {
-                            send_message(&writer, "HATA Belge adı UPDATE_DOCUMENT için sağlanmadı");
+                            reply.error(ErrorCode::BadRequest, "BLAME <document>");
                         }
                     }
+                    SEARCH_CMD => {
+                        if !argument.is_empty() {
+                            let state = shared_state.lock().unwrap();
+                            // Her satır: <belge>\t<satır numarası>\t<satırdan kesit>
+                            let results_body = search_index::search(&state.documents, argument, MAX_SEARCH_RESULTS)
+                                .iter()
+                                .map(|hit| format!("{}\t{}\t{}", hit.document, hit.line, hit.snippet))
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            send_message(&writer, &format_document_message(SEARCH_RESULTS_MSG, argument, &results_body));
+                        } else {
+                            reply.error(ErrorCode::BadRequest, "SEARCH <query>");
+                        }
+                    }
+                    UNDO_CMD | REDO_CMD => {
+                        let Some(ref username) = current_username else {
+                            reply.error(ErrorCode::NotConnected, "");
+                            continue;
+                        };
+                        let mut state = shared_state.lock().unwrap();
+                        let state = &mut *state;
+                        match state.documents.get_mut(argument) {
+                            Some(document) if document.locked => reply.error(ErrorCode::DocumentLocked, argument),
+                            Some(document) => {
+                                let result = if command == UNDO_CMD { document.undo(username) } else { document.redo(username) };
+                                match result {
+                                    Ok(()) => {
+                                        persistence.mark_dirty(argument, username, document.content.len());
+                                        // Geri alan dahil belgeyi açmış herkes yeni içeriği almalı
+                                        send_message_to_editors(
+                                            &state.clients,
+                                            None,
+                                            argument,
+                                            &format_document_message(DOCUMENT_UPDATED_MSG, argument, &document.content),
+                                        );
+                                    }
+                                    Err(e) => reply.error(ErrorCode::UndoFailed, e),
+                                }
+                            }
+                            None => reply.error(ErrorCode::DocumentNotFound, argument),
+                        }
+                    }
+                    PING_CMD => send_message(&writer, &format_command_with_arg(PONG_MSG, argument)),
+                    PONG_MSG => {} // Canlılık yanıtı; okuma zaman aşımını sıfırlamak yeterli
                     DISCONNECT_CMD => {
-                        println!("İstemci {} bağlantıyı sonlandırma isteği gönderdi.", peer_addr);
+                        debug!("Client requested disconnect");
+                        disconnect_requested = true;
                         break;
                     }
                     _ => {
-                        send_message(&writer, &format!("HATA Bilinmeyen komut: {}", command));
+                        reply.error(ErrorCode::UnknownCommand, command);
                     }
                 }
+                reply.finish();
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                info!(timeout_secs = keepalive_timeout.as_secs(), "Client timed out, closing the connection");
+                break;
             }
             Err(e) => {
-                eprintln!("İstemci {}'dan okuma hatası: {}", peer_addr, e);
+                warn!(error = %e, "Read from client failed");
                 break;
             }
         }
     }
 
     // Temizlik
-    all_clients_writers_arc.lock().unwrap().remove(&peer_addr);
+    info!(resumable = !disconnect_requested && session_token.is_some(), "Client disconnected");
+    let mut state = shared_state.lock().unwrap();
+    state.clients.remove(&peer_addr);
+    if let Some(token) = session_token {
+        if disconnect_requested {
+            state.sessions.remove(&token);
+        } else if let Some(session) = state.sessions.get_mut(&token) {
+            // Beklenmedik kopma: istemci aynı jetonla dönerse kaldığı yerden devam eder
+            session.open_documents = open_documents;
+            session.detached_at = Some(Instant::now());
+        }
+    }
     if let Some(username) = current_username {
-        println!("Kullanıcı {} ({}) bağlantısı kesildi.", username, peer_addr);
         broadcast_message_to_all(
-            &all_clients_writers_arc.lock().unwrap(),
+            &state.clients,
             &format_command_with_arg(USER_LEFT_MSG, &username)
         );
-    } else {
-        println!("İstemci {} bağlantısı kesildi (kullanıcı adı yok).", peer_addr);
     }
 }
 
-fn send_message(writer_arc: &ClientWriter, message: &str) {
+// UPDATE_DOCUMENT gövdesini END_OF_MESSAGE'a kadar okur. Sınırı aşan gövdeler de
+// sonuna kadar okunur (ama saklanmaz), böylece akış bir sonraki komutla devam eder.
+fn read_update_body(reader: &mut BufReader<TcpStream>, limits: &ConnectionLimits) -> Result<String, BodyError> {
+    let mut content = String::new();
+    let mut error = None;
+    loop {
+        let mut line = String::new();
+        match read_line_limited(reader, &mut line, limits.max_line_length) {
+            // Bağlantı END_OF_MESSAGE gelmeden koptu; yarım içerik uygulanmamalı
+            Ok(LineRead::Eof) | Err(_) => return Err(BodyError::Incomplete),
+            Ok(LineRead::TooLong(bytes)) => {
+                METRICS.bytes_received(bytes);
+                error.get_or_insert(BodyError::LineTooLong(limits.max_line_length));
+            }
+            Ok(LineRead::Line(bytes)) => {
+                METRICS.bytes_received(bytes);
+                if line.trim() == END_OF_MESSAGE_DELIMITER {
+                    break;
+                }
+                if error.is_none() {
+                    if limits.max_document_size > 0 && content.len() + line.len() > limits.max_document_size + 1 {
+                        error = Some(BodyError::DocumentTooLarge(limits.max_document_size));
+                        content = String::new();
+                    } else {
+                        content.push_str(&line);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(error) = error {
+        return Err(error);
+    }
+    // Son \n'i kaldır (Java'daki gibi)
+    if content.ends_with('\n') {
+        content.pop();
+    }
+    Ok(content)
+}
+
+// Bir sınır aşımını istemciye bildirir ve sayar. İzin verilenden fazla ihlal
+// olduysa istemciyi atar (yeniden bağlanmaması için KICKED) ve true döner.
+fn record_violation(
+    reply: &mut Reply,
+    violations: &mut u32,
+    limits: &ConnectionLimits,
+    limit: &'static str,
+    code: ErrorCode,
+    detail: impl Display,
+) -> bool {
+    *violations += 1;
+    METRICS.limit_violation(limit);
+    warn!(limit, violations = *violations, "Connection limit exceeded");
+    reply.error(code, detail);
+    if limits.max_violations > 0 && *violations >= limits.max_violations {
+        warn!("Too many limit violations, disconnecting");
+        send_message(reply.writer, &format_command_with_arg(KICKED_MSG, "too many limit violations"));
+        return true;
+    }
+    false
+}
+
+// Belge adları dosya adına dönüşür: ad, belgeler dizininin dışına çıkamamalı ve
+// gizli dosya olmamalı; virgül belge listesini, kontrol karakterleri de satır
+// tabanlı protokolü bozar. Reddedilen ad için istemciye gönderilecek hata kodunu
+// ve ayrıntıyı döndürür.
+fn check_document_name(name: &str) -> Result<(), (ErrorCode, String)> {
+    let invalid = name.is_empty()
+        || name.starts_with('.')
+        || name.contains("..")
+        || name.chars().any(|c| matches!(c, '/' | '\\' | ',') || c.is_control());
+    if invalid {
+        return Err((ErrorCode::InvalidArgument, format!("{:?}", name)));
+    }
+    Ok(())
+}
+
+// Belge adı alan komutlarda argümandaki ad. Revizyon komutlarında ad, sondaki
+// revizyonlardan önceki kısımdır. Ad eksikse komut kendi kullanım hatasını verir.
+fn document_argument<'a>(command: &str, argument: &'a str) -> Option<&'a str> {
+    let doc_name = match command {
+        CREATE_DOCUMENT_CMD | SWITCH_DOCUMENT_CMD | GET_DOCUMENT_CMD | CLOSE_DOCUMENT_CMD | UPDATE_DOCUMENT_CMD
+        | HISTORY_CMD | BLAME_CMD | UNDO_CMD | REDO_CMD => argument,
+        GET_REVISION_CMD | RESTORE_REVISION_CMD => parse_revision_argument(argument)?.0,
+        DIFF_DOCUMENT_CMD => parse_diff_argument(argument)?.0,
+        _ => return None,
+    };
+    (!doc_name.is_empty()).then_some(doc_name)
+}
+
+// CREATE_DOCUMENT ile istenen adı belge dosyasının adına çevirir: izin verilen
+// bir uzantı varsa korunur, uzantısız adlara listedeki ilk uzantı eklenir.
+fn document_file_name(requested: &str, allowed_extensions: &[String]) -> Result<String, (ErrorCode, String)> {
+    check_document_name(requested)?;
+    let extension = requested
+        .rsplit_once('.')
+        .filter(|(stem, extension)| !stem.is_empty() && !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|(_, extension)| extension);
+    match extension {
+        Some(extension) if allowed_extensions.iter().any(|allowed| allowed.eq_ignore_ascii_case(extension)) => Ok(requested.to_string()),
+        Some(extension) => Err((ErrorCode::ExtensionNotAllowed, format!(".{} ({})", extension, allowed_extensions.join(", ")))),
+        None => Ok(format!("{}.{}", requested, allowed_extensions.first().map_or("txt", String::as_str))),
+    }
+}
+
+// Reddedilen bir güncellemeden sonra istemcinin tamponunu sunucudaki içerikle eşitler.
+fn send_server_content(writer: &ClientWriter, shared_state: &SharedServerState, doc_name: &str) {
+    if let Some(document) = shared_state.lock().unwrap().documents.get(doc_name) {
+        send_message(writer, &format_document_message(DOCUMENT_UPDATED_MSG, doc_name, &document.content));
+    }
+}
+
+// Bir komutun argümanının loglanacak hali. Arama sorgusu belge içeriğinden
+// parçalar taşıyabileceği için içerik gibi gizlenir; CONNECT'teki devam jetonu
+// ise oturumu devralmaya yettiği için hiçbir zaman loglanmaz.
+fn logged_argument(command: &str, argument: &str) -> String {
+    match command {
+        SEARCH_CMD => logging::content(argument).to_string(),
+        CONNECT_CMD => match parse_connect_argument(argument) {
+            (username, Some(_)) => format!("{} <resume token redacted>", username),
+            (username, None) => username.to_string(),
+        },
+        _ => argument.to_string(),
+    }
+}
+
+// İstemcilerin gönderebileceği komutlar; diğer satırlar içerik olabileceği için
+// loglanırken gizlenir ve metriklerde tek bir "unknown" etiketiyle sayılır.
+const CLIENT_COMMANDS: [&str; 18] = [
+    CONNECT_CMD, DISCONNECT_CMD, GET_DOCUMENT_CMD, UPDATE_DOCUMENT_CMD, LIST_DOCUMENTS_CMD,
+    CREATE_DOCUMENT_CMD, SWITCH_DOCUMENT_CMD, CLOSE_DOCUMENT_CMD, HISTORY_CMD, GET_REVISION_CMD,
+    RESTORE_REVISION_CMD, DIFF_DOCUMENT_CMD, BLAME_CMD, UNDO_CMD, REDO_CMD,
+    SEARCH_CMD, PING_CMD, PONG_MSG,
+];
+
+// Sunucu kapanırken herkese nedenini bildirir ve tüm bağlantıları kapatır;
+// istemci thread'leri okuma hatasıyla döngüden çıkıp kendi temizliklerini yapar.
+// Yavaş bir istemciye yazmak genel kilidi tutmasın diye yazıcılar kilit
+// bırakıldıktan sonra kullanılır.
+pub fn disconnect_all_clients(shared_state: &SharedServerState, reason: &str) {
+    let clients: Vec<(ClientWriter, Option<TcpStream>)> = {
+        let state = shared_state.lock().unwrap();
+        state.clients.values().map(|client| (client.writer.clone(), client.connection.try_clone().ok())).collect()
+    };
+    let message = format_command_with_arg(SERVER_SHUTTING_DOWN_MSG, reason);
+    for (writer, connection) in &clients {
+        send_message(writer, &message);
+        if let Some(connection) = connection {
+            let _ = connection.shutdown(Shutdown::Both);
+        }
+    }
+    METRICS.broadcast(&message, clients.len());
+}
+
+// Her `keepalive_interval`de tüm istemcilere PING gönderir (istemciler PONG ile
+// yanıt verdikçe okuma zaman aşımına uğramazlar) ve süresi dolan kopuk oturumları siler.
+// PING'ler genel kilit bırakıldıktan sonra yazılır; yanıt vermeyen bir istemci
+// yazma zaman aşımına kadar diğer istekleri bekletmesin.
+pub fn start_heartbeat(shared_state: SharedServerState, config: SharedConfig) -> JoinHandle<()> {
+    thread::spawn(move || loop {
+        let interval = config.read().unwrap().keepalive_interval;
+        thread::sleep(interval);
+        let writers: Vec<ClientWriter> = {
+            let mut state = shared_state.lock().unwrap();
+            prune_expired_sessions(&mut state.sessions);
+            state.clients.values().map(|client| client.writer.clone()).collect()
+        };
+        for writer in &writers {
+            send_message(writer, PING_CMD);
+        }
+        METRICS.broadcast(PING_CMD, writers.len());
+    })
+}
+
+fn prune_expired_sessions(sessions: &mut HashMap<String, Session>) {
+    sessions.retain(|_, session| session.detached_at.is_none_or(|at| at.elapsed() < SESSION_RESUME_TIMEOUT));
+}
+
+// Oturum devam jetonu üretir: işletim sisteminin rastgele sayı üretecinden
+// 128 bit. Jetonu bilen oturumu ve kimliğini devralabildiği için tahmin
+// edilememesi gerekir.
+fn new_resume_token() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; RESUME_TOKEN_LEN / 2];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+// Verilen jetona ve kullanıcıya ait kopmuş oturumun anahtarı. Jetonlar sabit
+// sürede karşılaştırılır; eşleşmenin kaçıncı baytta bozulduğu zamanlamadan anlaşılmasın.
+fn find_detached_session(sessions: &HashMap<String, Session>, username: &str, token: &str) -> Option<String> {
+    sessions
+        .iter()
+        .find(|(candidate, session)| {
+            tokens_match(candidate, token) && session.username == username && session.detached_at.is_some()
+        })
+        .map(|(candidate, _)| candidate.clone())
+}
+
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// Bir isteğin yanıtını izler: istek hatayla reddedilmediyse `finish` kimliğiyle
+// birlikte ACK gönderir. Kimliği olmayan isteklere ACK gönderilmez.
+struct Reply<'a> {
+    writer: &'a ClientWriter,
+    request_id: Option<&'a str>,
+    failed: bool,
+}
+
+impl<'a> Reply<'a> {
+    fn new(writer: &'a ClientWriter, request_id: Option<&'a str>) -> Self {
+        Reply { writer, request_id, failed: false }
+    }
+
+    fn error(&mut self, code: ErrorCode, detail: impl Display) {
+        self.failed = true;
+        send_message(self.writer, &format_error(code, self.request_id, &error_message(code, detail)));
+    }
+
+    fn finish(self) {
+        if let (false, Some(request_id)) = (self.failed, self.request_id) {
+            send_message(self.writer, &format_command_with_arg(ACK_MSG, request_id));
+        }
+    }
+}
+
+// İstemciye kodlu bir ERROR mesajı gönderir; metin sunucunun dilindedir.
+pub fn send_error(writer: &ClientWriter, code: ErrorCode, detail: impl Display) {
+    send_message(writer, &format_error(code, None, &error_message(code, detail)));
+}
+
+pub fn send_message(writer_arc: &ClientWriter, message: &str) {
     let mut writer_guard = writer_arc.lock().unwrap();
-    if let Err(e) = writeln!(writer_guard, "{}", message) {
-        // eprintln!("Mesaj gönderilemedi: {}", e); // Çok fazla log olabilir
+    // Kopmuş istemcilere yazarken çok sık oluşur, bu yüzden en düşük seviyede
+    match writeln!(writer_guard, "{}", message).and_then(|_| writer_guard.flush()) {
+        Ok(()) => METRICS.bytes_sent(message.len() + 1),
+        Err(e) => trace!(error = %e, "Could not send message"),
     }
-    if let Err(e) = writer_guard.flush() {
-        // eprintln!("Flush hatası: {}", e);
+}
+
+// Bir revizyon belirtecini (numara veya "current") belge içeriğine çözümler.
+fn resolve_revision(
+    shared_state: &SharedServerState,
+    persistence: &PersistenceHandle,
+    doc_name: &str,
+    revision: &str,
+) -> Result<String, (ErrorCode, String)> {
+    {
+        let state = shared_state.lock().unwrap();
+        let document = state.documents.get(doc_name).ok_or_else(|| (ErrorCode::DocumentNotFound, doc_name.to_string()))?;
+        if revision == CURRENT_REVISION {
+            return Ok(document.content.clone());
+        }
     }
+    let number: u64 = revision
+        .parse()
+        .map_err(|_| (ErrorCode::RevisionNotFound, revision.to_string()))?;
+    persistence
+        .load_revision(doc_name, number)
+        .map_err(|e| (ErrorCode::RevisionNotFound, format!("{} ({})", number, e)))
 }
 
-fn send_available_documents(writer: &ClientWriter, docs: &HashMap<String, String>) {
+// Belge sunucuda açık mı? Geçmiş komutları adı depoya vermeden önce bunu denetler.
+fn document_exists(shared_state: &SharedServerState, doc_name: &str) -> bool {
+    shared_state.lock().unwrap().documents.contains_key(doc_name)
+}
+
+fn send_available_documents(writer: &ClientWriter, docs: &HashMap<String, Document>) {
     let doc_names: Vec<String> = docs.keys().cloned().collect();
     let doc_list_string = doc_names.join(",");
     send_message(writer, &format_command_with_arg(DOCUMENTS_LIST_MSG, &doc_list_string));
@@ -271,22 +857,273 @@ fn send_full_document_content(writer: &ClientWriter, doc_name: &str, content: &s
 
 // Belirli bir istemci hariç diğer tüm istemcilere mesaj yayınlar.
 fn broadcast_message_to_others(
-    client_writers: &HashMap<std::net::SocketAddr, ClientWriter>,
-    exclude_addr: std::net::SocketAddr,
+    clients: &HashMap<SocketAddr, ClientInfo>,
+    exclude_addr: SocketAddr,
     message: &str
 ) {
-    for (addr, writer_arc) in client_writers.iter() {
+    let mut recipients = 0;
+    for (addr, client) in clients.iter() {
         if *addr != exclude_addr {
-            send_message(writer_arc, message);
+            send_message(&client.writer, message);
+            recipients += 1;
         }
     }
+    METRICS.broadcast(message, recipients);
 }
 // Tüm istemcilere mesaj yayınlar.
-fn broadcast_message_to_all(
-    client_writers: &HashMap<std::net::SocketAddr, ClientWriter>,
+pub fn broadcast_message_to_all(
+    clients: &HashMap<SocketAddr, ClientInfo>,
+    message: &str
+) {
+    for client in clients.values() {
+        send_message(&client.writer, message);
+    }
+    METRICS.broadcast(message, clients.len());
+}
+
+// Belirli bir belgeyi açmış tüm istemcilere (verilmişse biri hariç) mesaj gönderir.
+pub fn send_message_to_editors(
+    clients: &HashMap<SocketAddr, ClientInfo>,
+    exclude_addr: Option<SocketAddr>,
+    doc_name: &str,
     message: &str
 ) {
-    for writer_arc in client_writers.values() {
-        send_message(writer_arc, message);
+    let mut recipients = 0;
+    for (addr, client) in clients.iter() {
+        if Some(*addr) != exclude_addr && client.open_documents.contains(doc_name) {
+            send_message(&client.writer, message);
+            recipients += 1;
+        }
+    }
+    METRICS.broadcast(message, recipients);
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::document_manager::PersistenceWorker;
+    use crate::storage::{DocumentStore, Revision};
+    use std::io::{self, BufRead};
+    use std::net::TcpListener;
+    use std::sync::RwLock;
+
+    fn session(username: &str, detached: bool) -> Session {
+        Session {
+            username: username.to_string(),
+            open_documents: BTreeSet::new(),
+            detached_at: detached.then(Instant::now),
+        }
+    }
+
+    #[test]
+    fn resume_tokens_are_random_hex_of_the_protocol_length() {
+        let token = new_resume_token().unwrap();
+        assert_eq!(token.len(), RESUME_TOKEN_LEN);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, new_resume_token().unwrap());
+        assert_eq!(parse_connect_argument(&format!("ayse {}", token)), ("ayse", Some(token.as_str())));
+    }
+
+    #[test]
+    fn tokens_match_only_when_identical() {
+        assert!(tokens_match("0123abcd", "0123abcd"));
+        assert!(!tokens_match("0123abcd", "0123abce"));
+        assert!(!tokens_match("0123abcd", "0123abc"));
+    }
+
+    #[test]
+    fn only_a_detached_session_of_the_same_user_is_resumed() {
+        let mut sessions = HashMap::new();
+        sessions.insert("aa".repeat(16), session("ayse", true));
+        sessions.insert("bb".repeat(16), session("ayse", false));
+        assert_eq!(find_detached_session(&sessions, "ayse", &"aa".repeat(16)), Some("aa".repeat(16)));
+        assert_eq!(find_detached_session(&sessions, "mehmet", &"aa".repeat(16)), None);
+        // A session still in use cannot be taken over
+        assert_eq!(find_detached_session(&sessions, "ayse", &"bb".repeat(16)), None);
+        assert_eq!(find_detached_session(&sessions, "ayse", &"cc".repeat(16)), None);
+    }
+
+    #[test]
+    fn resume_tokens_are_not_logged() {
+        let token = "ab".repeat(16);
+        let logged = logged_argument(CONNECT_CMD, &format!("ayse {}", token));
+        assert_eq!(logged, "ayse <resume token redacted>");
+        assert_eq!(logged_argument(CONNECT_CMD, "ayse"), "ayse");
+        assert_eq!(logged_argument(GET_DOCUMENT_CMD, "notlar.txt"), "notlar.txt");
     }
-}
\ No newline at end of file
+
+    fn extensions() -> Vec<String> {
+        vec!["txt".to_string(), "md".to_string()]
+    }
+
+    fn rejected(name: &str) -> Option<ErrorCode> {
+        document_file_name(name, &extensions()).err().map(|(code, _)| code)
+    }
+
+    #[test]
+    fn document_names_get_an_allowed_extension() {
+        assert_eq!(document_file_name("notlar", &extensions()), Ok("notlar.txt".to_string()));
+        assert_eq!(document_file_name("my notes.MD", &extensions()), Ok("my notes.MD".to_string()));
+        assert_eq!(rejected("script.sh"), Some(ErrorCode::ExtensionNotAllowed));
+    }
+
+    #[test]
+    fn document_names_with_path_separators_are_rejected() {
+        assert_eq!(rejected("../secret"), Some(ErrorCode::InvalidArgument));
+        assert_eq!(rejected("dir/notes.txt"), Some(ErrorCode::InvalidArgument));
+        assert_eq!(rejected("/etc/passwd"), Some(ErrorCode::InvalidArgument));
+        assert_eq!(rejected("dir\\notes.txt"), Some(ErrorCode::InvalidArgument));
+    }
+
+    #[test]
+    fn document_names_with_dot_dot_are_rejected() {
+        assert_eq!(rejected(".."), Some(ErrorCode::InvalidArgument));
+        assert_eq!(rejected("notes..txt"), Some(ErrorCode::InvalidArgument));
+    }
+
+    #[test]
+    fn document_names_with_commas_are_rejected() {
+        assert_eq!(rejected("a,b.txt"), Some(ErrorCode::InvalidArgument));
+    }
+
+    #[test]
+    fn document_names_with_control_characters_are_rejected() {
+        assert_eq!(rejected("a\tb.txt"), Some(ErrorCode::InvalidArgument));
+        assert_eq!(rejected("a\u{7f}.txt"), Some(ErrorCode::InvalidArgument));
+        assert_eq!(rejected("a\u{85}.txt"), Some(ErrorCode::InvalidArgument));
+    }
+
+    #[test]
+    fn document_names_with_an_empty_stem_are_rejected() {
+        assert_eq!(rejected(".txt"), Some(ErrorCode::InvalidArgument));
+        assert_eq!(rejected(".history"), Some(ErrorCode::InvalidArgument));
+        assert_eq!(rejected(""), Some(ErrorCode::InvalidArgument));
+    }
+
+    /// A store that keeps nothing; the tests below only look at the in-memory state.
+    struct NullStore;
+
+    impl DocumentStore for NullStore {
+        fn list(&self) -> io::Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+        fn load(&self, _name: &str) -> io::Result<String> {
+            Ok(String::new())
+        }
+        fn save(&self, _name: &str, _content: &str, _author: &str) -> io::Result<u64> {
+            Ok(1)
+        }
+        fn delete(&self, _name: &str) -> io::Result<()> {
+            Ok(())
+        }
+        fn history(&self, _name: &str) -> io::Result<Vec<Revision>> {
+            Ok(Vec::new())
+        }
+        fn load_revision(&self, _name: &str, _number: u64) -> io::Result<String> {
+            Ok(String::new())
+        }
+    }
+
+    /// One client connection served by `handle_client` on a loopback socket.
+    struct TestConnection {
+        client: TcpStream,
+        replies: BufReader<TcpStream>,
+        state: SharedServerState,
+        config: SharedConfig,
+        worker: PersistenceWorker,
+        handler: JoinHandle<()>,
+    }
+
+    impl TestConnection {
+        fn open(documents: &[(&str, &str)]) -> Self {
+            let documents = documents.iter().map(|(name, content)| (name.to_string(), Document::new(content.to_string()))).collect();
+            let state: SharedServerState =
+                Arc::new(Mutex::new(ServerSharedState { documents, clients: HashMap::new(), sessions: HashMap::new() }));
+            let config: SharedConfig =
+                Arc::new(RwLock::new(ServerConfig { allowed_extensions: extensions(), ..Default::default() }));
+            let worker = PersistenceWorker::start(Arc::clone(&state), Arc::new(NullStore), Arc::clone(&config));
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+            client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            let (stream, _) = listener.accept().unwrap();
+            let handler = {
+                let (state, persistence, config) = (Arc::clone(&state), worker.handle(), Arc::clone(&config));
+                thread::spawn(move || {
+                    handle_client(stream, state, persistence, Duration::from_secs(5), ConnectionLimits::default(), config)
+                })
+            };
+            let replies = BufReader::new(client.try_clone().unwrap());
+            TestConnection { client, replies, state, config, worker, handler }
+        }
+
+        fn send(&mut self, message: &str) {
+            writeln!(self.client, "{}", message).unwrap();
+        }
+
+        /// The next `ERROR` reply, skipping everything else.
+        fn next_error(&mut self) -> ErrorCode {
+            loop {
+                let mut line = String::new();
+                assert!(self.replies.read_line(&mut line).unwrap() > 0, "connection closed without an error");
+                if let Some(payload) = line.trim_end().strip_prefix(ERROR_MSG).and_then(|rest| rest.strip_prefix(' ')) {
+                    return parse_error(payload).0;
+                }
+            }
+        }
+
+        /// Closes the client side and waits until the server is done with the connection.
+        fn close(self) -> SharedServerState {
+            let _ = self.client.shutdown(Shutdown::Write);
+            self.handler.join().unwrap();
+            self.worker.shutdown();
+            self.state
+        }
+    }
+
+    #[test]
+    fn commands_naming_a_path_are_refused() {
+        let mut connection = TestConnection::open(&[("a.txt", "")]);
+        connection.send("CONNECT ayse");
+        for command in [
+            "HISTORY ../x",
+            "GET_REVISION ../x 1",
+            "RESTORE_REVISION ../../etc/passwd 1",
+            "DIFF_DOCUMENT ../x 1 current",
+            "SWITCH_DOCUMENT dir/a.txt",
+            "BLAME .hidden",
+            "UNDO ../x",
+        ] {
+            connection.send(command);
+            assert_eq!(connection.next_error(), ErrorCode::InvalidArgument, "{}", command);
+        }
+        connection.send("UPDATE_DOCUMENT ../x\nnew\nEND_OF_MESSAGE");
+        assert_eq!(connection.next_error(), ErrorCode::InvalidArgument);
+        // Usage errors are still reported as such
+        connection.send("HISTORY");
+        assert_eq!(connection.next_error(), ErrorCode::BadRequest);
+        connection.close();
+    }
+
+    #[test]
+    fn reloaded_extensions_apply_to_open_connections() {
+        let mut connection = TestConnection::open(&[]);
+        connection.send("CREATE_DOCUMENT notes.toml");
+        assert_eq!(connection.next_error(), ErrorCode::ExtensionNotAllowed);
+        connection.config.write().unwrap().allowed_extensions.push("toml".to_string());
+        connection.send("CREATE_DOCUMENT notes.toml");
+        connection.send("HISTORY");
+        assert_eq!(connection.next_error(), ErrorCode::BadRequest);
+        let state = connection.close();
+        assert!(state.lock().unwrap().documents.contains_key("notes.toml"));
+    }
+
+    #[test]
+    fn update_cut_off_before_end_of_message_is_dropped() {
+        let mut connection = TestConnection::open(&[("a.txt", "first\nsecond")]);
+        connection.send("CONNECT ayse");
+        connection.send("GET_DOCUMENT a.txt");
+        connection.send("UPDATE_DOCUMENT a.txt\nfirst");
+        let state = connection.close();
+        assert_eq!(state.lock().unwrap().documents["a.txt"].content, "first\nsecond");
+    }
+}
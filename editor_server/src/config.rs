@@ -0,0 +1,245 @@
+use editor_protocol::locale::Locale;
+use editor_protocol::DOCUMENTS_DIR;
+use std::fs;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Config file read at startup unless `EDITOR_SERVER_CONFIG` points elsewhere.
+const DEFAULT_CONFIG_PATH: &str = "server.conf";
+
+/// Heartbeat defaults; also used when the configured timeout is not longer than the interval.
+const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How log records are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/// Per-connection limits. A value of 0 disables that limit.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionLimits {
+    /// Longest accepted protocol line, in bytes.
+    pub max_line_length: usize,
+    /// Largest accepted `UPDATE_DOCUMENT` body, in bytes.
+    pub max_document_size: usize,
+    pub max_messages_per_second: u32,
+    pub max_connections_per_ip: usize,
+    /// Violations after which the client is disconnected.
+    pub max_violations: u32,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        ConnectionLimits {
+            max_line_length: 64 * 1024,
+            max_document_size: 8 * 1024 * 1024,
+            max_messages_per_second: 50,
+            max_connections_per_ip: 16,
+            max_violations: 10,
+        }
+    }
+}
+
+/// Where documents (and their metadata) are persisted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    File,
+    Sqlite,
+}
+
+/// Server settings, loaded from a simple `key = value` file.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub storage_backend: StorageBackend,
+    pub documents_dir: String,
+    pub sqlite_path: String,
+    pub flush_interval: Duration,
+    pub flush_size_threshold: usize,
+    /// Revisions kept per document; older ones are pruned on save. 0 keeps all.
+    pub history_limit: usize,
+    pub keepalive_interval: Duration,
+    pub keepalive_timeout: Duration,
+    /// Applied to connections accepted after the config is (re)loaded.
+    pub limits: ConnectionLimits,
+    /// Unix socket for the admin channel; off unless set.
+    pub admin_socket: Option<String>,
+    /// Address of the HTTP `/metrics` endpoint; off unless set.
+    pub metrics_address: Option<String>,
+    /// Filter directive such as `info` or `editor_server=debug`; `RUST_LOG` overrides it.
+    pub log_level: String,
+    pub log_format: LogFormat,
+    /// Whether document bodies may appear in logs. Off by default; only sizes are logged.
+    pub log_document_content: bool,
+    /// Language of the error texts sent to clients; defaults to `LANG`, then English.
+    pub language: Locale,
+    /// File extensions documents may have, lowercase and without the dot. The
+    /// first one is added to names created without an extension.
+    pub allowed_extensions: Vec<String>,
+}
+
+/// Live config shared with the threads that read it, replaced on reload.
+pub type SharedConfig = Arc<RwLock<ServerConfig>>;
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            storage_backend: StorageBackend::File,
+            documents_dir: DOCUMENTS_DIR.to_string(),
+            sqlite_path: "shared_documents.db".to_string(),
+            flush_interval: Duration::from_secs(2),
+            flush_size_threshold: 256 * 1024,
+            history_limit: 100,
+            keepalive_interval: DEFAULT_KEEPALIVE_INTERVAL,
+            keepalive_timeout: DEFAULT_KEEPALIVE_TIMEOUT,
+            limits: ConnectionLimits::default(),
+            admin_socket: None,
+            metrics_address: None,
+            log_level: "info".to_string(),
+            log_format: LogFormat::Text,
+            log_document_content: false,
+            language: Locale::from_env().unwrap_or_default(),
+            allowed_extensions: ["txt", "md", "rs", "toml"].map(String::from).to_vec(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Loads the config file, falling back to defaults for anything missing or
+    /// invalid. Problems are returned instead of logged, since logging itself is
+    /// set up from the loaded config.
+    pub fn load() -> (Self, Vec<String>) {
+        let mut config = ServerConfig::default();
+        let problems = match fs::read_to_string(config_path()) {
+            Ok(contents) => config.apply(&contents),
+            Err(_) => Vec::new(),
+        };
+        (config, problems)
+    }
+
+    fn apply(&mut self, contents: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (line_no, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                problems.push(format!("Config line {}: expected `key = value`", line_no + 1));
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if let Err(e) = self.set(key, value) {
+                problems.push(format!("Config line {}: {}", line_no + 1, e));
+            }
+        }
+        // A client answers each PING, so it can only time out if no PING arrives
+        // within the timeout; an interval of 0 would spin the heartbeat thread.
+        if self.keepalive_interval.is_zero() || self.keepalive_timeout <= self.keepalive_interval {
+            problems.push(format!(
+                "keepalive_timeout_ms ({}) must be greater than keepalive_interval_ms ({}) and the interval above 0; using {} and {}",
+                self.keepalive_timeout.as_millis(),
+                self.keepalive_interval.as_millis(),
+                DEFAULT_KEEPALIVE_TIMEOUT.as_millis(),
+                DEFAULT_KEEPALIVE_INTERVAL.as_millis(),
+            ));
+            self.keepalive_interval = DEFAULT_KEEPALIVE_INTERVAL;
+            self.keepalive_timeout = DEFAULT_KEEPALIVE_TIMEOUT;
+        }
+        problems
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "storage" => {
+                self.storage_backend = match value {
+                    "file" => StorageBackend::File,
+                    "sqlite" => StorageBackend::Sqlite,
+                    _ => return Err(format!("unknown storage backend '{}'", value)),
+                }
+            }
+            "documents_dir" => {
+                self.documents_dir = value.to_string();
+                if !self.documents_dir.ends_with('/') {
+                    self.documents_dir.push('/');
+                }
+            }
+            "sqlite_path" => self.sqlite_path = value.to_string(),
+            "flush_interval_ms" => self.flush_interval = Duration::from_millis(parse_number(key, value)?),
+            "flush_size_threshold" => self.flush_size_threshold = parse_number(key, value)?,
+            "history_limit" => self.history_limit = parse_number(key, value)?,
+            "keepalive_interval_ms" => self.keepalive_interval = Duration::from_millis(parse_number(key, value)?),
+            "keepalive_timeout_ms" => self.keepalive_timeout = Duration::from_millis(parse_number(key, value)?),
+            "max_line_length" => self.limits.max_line_length = parse_number(key, value)?,
+            "max_document_size" => self.limits.max_document_size = parse_number(key, value)?,
+            "max_messages_per_second" => self.limits.max_messages_per_second = parse_number(key, value)?,
+            "max_connections_per_ip" => self.limits.max_connections_per_ip = parse_number(key, value)?,
+            "max_violations" => self.limits.max_violations = parse_number(key, value)?,
+            "admin_socket" => self.admin_socket = Some(value.to_string()).filter(|path| !path.is_empty()),
+            "metrics_address" => self.metrics_address = Some(value.to_string()).filter(|address| !address.is_empty()),
+            "log_level" => self.log_level = value.to_string(),
+            "log_format" => {
+                self.log_format = match value {
+                    "text" => LogFormat::Text,
+                    "json" => LogFormat::Json,
+                    _ => return Err(format!("unknown log format '{}'", value)),
+                }
+            }
+            "log_document_content" => self.log_document_content = parse_bool(key, value)?,
+            "language" => {
+                self.language = Locale::parse(value).ok_or_else(|| format!("unsupported language '{}'", value))?
+            }
+            "allowed_extensions" => {
+                let extensions: Vec<String> = value
+                    .split(',')
+                    .map(|extension| extension.trim().trim_start_matches('.').to_ascii_lowercase())
+                    .filter(|extension| !extension.is_empty())
+                    .collect();
+                if extensions.is_empty() {
+                    return Err("allowed_extensions needs at least one extension".to_string());
+                }
+                self.allowed_extensions = extensions;
+            }
+            _ => return Err(format!("unknown key '{}'", key)),
+        }
+        Ok(())
+    }
+}
+
+/// The config file: `EDITOR_SERVER_CONFIG`, or `server.conf` in the working directory.
+pub fn config_path() -> String {
+    std::env::var("EDITOR_SERVER_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string())
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("invalid boolean for '{}': {}", key, value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid number for '{}': {}", key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keepalive_timeout_must_exceed_the_interval() {
+        let mut config = ServerConfig::default();
+        assert!(config.apply("keepalive_interval_ms = 1000\nkeepalive_timeout_ms = 3000").is_empty());
+        assert_eq!(config.keepalive_timeout, Duration::from_millis(3000));
+
+        for contents in ["keepalive_interval_ms = 20000", "keepalive_timeout_ms = 5000", "keepalive_interval_ms = 0"] {
+            let mut config = ServerConfig::default();
+            assert_eq!(config.apply(contents).len(), 1, "{}", contents);
+            assert_eq!(config.keepalive_interval, DEFAULT_KEEPALIVE_INTERVAL);
+            assert_eq!(config.keepalive_timeout, DEFAULT_KEEPALIVE_TIMEOUT);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use editor_protocol::ops::LineEdit;
+use crate::search_index::LineIndex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// How many operations are kept per document for undo/redo.
+const MAX_OPERATIONS: usize = 1000;
+/// Consecutive edits by the same user closer together than this are undone as one.
+const UNDO_GROUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// An edit in the document's operation log.
+struct Operation {
+    author: String,
+    edit: LineEdit,
+    at: Instant,
+    /// Sequence number of the operation this one reverted (for undo/redo results).
+    reverts: Option<u64>,
+}
+
+/// Why an undo or redo could not be applied. Shown to the user through
+/// `i18n::undo_error_message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoError {
+    NothingToUndo,
+    NothingToRedo,
+    /// The edit has already left the operation log.
+    TooOld,
+    /// The named user has since changed the same lines.
+    Conflict(String),
+    /// The reverted edit no longer fits the content.
+    Invalid(String),
+}
+
+impl std::fmt::Display for UndoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&crate::i18n::undo_error_message(self))
+    }
+}
+
+/// In-memory state of a document.
+pub struct Document {
+    pub content: String,
+    /// Last author of each line; empty when unknown (e.g. loaded from storage).
+    pub line_authors: Vec<String>,
+    /// Word index of `content` for `SEARCH`.
+    pub index: LineIndex,
+    /// Recent operations, oldest first; `first_operation` is the sequence number of the front.
+    operations: VecDeque<Operation>,
+    first_operation: u64,
+    /// Per-user stacks of operation sequence numbers. Keyed by username, so
+    /// they survive reconnects.
+    undo_stacks: HashMap<String, Vec<u64>>,
+    redo_stacks: HashMap<String, Vec<u64>>,
+    /// Set by an administrator; a locked document rejects edits.
+    pub locked: bool,
+}
+
+impl Document {
+    pub fn new(content: String) -> Self {
+        let line_authors = vec![String::new(); content.lines().count()];
+        Document {
+            index: LineIndex::build(&content),
+            content,
+            line_authors,
+            operations: VecDeque::new(),
+            first_operation: 0,
+            undo_stacks: HashMap::new(),
+            redo_stacks: HashMap::new(),
+            locked: false,
+        }
+    }
+
+    /// Replaces the content with an edit made by `author`, recording it for undo.
+    pub fn update(&mut self, new_content: String, author: &str) {
+        let edit = {
+            let old_lines: Vec<&str> = self.content.lines().collect();
+            let new_lines: Vec<&str> = new_content.lines().collect();
+            LineEdit::between(&old_lines, &new_lines)
+        };
+        let Some(edit) = edit else {
+            // Same lines; at most a trailing newline differs
+            self.content = new_content;
+            return;
+        };
+        self.set_content(new_content, &edit, author);
+        if !self.merge_into_last_operation(author, &edit) {
+            let seq = self.record_operation(author, edit, None);
+            self.undo_stacks.entry(author.to_string()).or_default().push(seq);
+        }
+        self.redo_stacks.remove(author);
+    }
+
+    /// Reverts the most recent edit of `author`, rebased over everything that happened since.
+    pub fn undo(&mut self, author: &str) -> Result<(), UndoError> {
+        let seq = self.undo_stacks.get_mut(author).and_then(Vec::pop).ok_or(UndoError::NothingToUndo)?;
+        let revert_seq = self.revert_operation(seq, author)?;
+        self.redo_stacks.entry(author.to_string()).or_default().push(revert_seq);
+        Ok(())
+    }
+
+    /// Re-applies the most recently undone edit of `author`.
+    pub fn redo(&mut self, author: &str) -> Result<(), UndoError> {
+        let seq = self.redo_stacks.get_mut(author).and_then(Vec::pop).ok_or(UndoError::NothingToRedo)?;
+        let revert_seq = self.revert_operation(seq, author)?;
+        self.undo_stacks.entry(author.to_string()).or_default().push(revert_seq);
+        Ok(())
+    }
+
+    /// Applies the inverse of operation `seq` to the current content and
+    /// records it as a new operation, returning its sequence number.
+    fn revert_operation(&mut self, seq: u64, author: &str) -> Result<u64, UndoError> {
+        let index = seq
+            .checked_sub(self.first_operation)
+            .map(|index| index as usize)
+            .filter(|index| *index < self.operations.len())
+            .ok_or(UndoError::TooOld)?;
+
+        // An edit and the undo that reverted it cancel out, so when both
+        // happened after `seq` there is nothing to rebase over.
+        let mut cancelled = HashSet::new();
+        for (offset, later) in self.operations.iter().enumerate().skip(index + 1).rev() {
+            let later_seq = self.first_operation + offset as u64;
+            if let Some(target) = later.reverts.filter(|target| *target > seq && !cancelled.contains(target)) {
+                if !cancelled.contains(&later_seq) {
+                    cancelled.insert(later_seq);
+                    cancelled.insert(target);
+                }
+            }
+        }
+
+        let mut edit = self.operations[index].edit.inverse();
+        for (offset, later) in self.operations.iter().enumerate().skip(index + 1) {
+            if cancelled.contains(&(self.first_operation + offset as u64)) {
+                continue;
+            }
+            edit = edit
+                .transform(&later.edit)
+                .ok_or_else(|| UndoError::Conflict(later.author.clone()))?;
+        }
+
+        let mut lines: Vec<String> = self.content.lines().map(String::from).collect();
+        edit.apply(&mut lines).map_err(UndoError::Invalid)?;
+        let mut new_content = lines.join("\n");
+        // `lines()` drops the final newline; an undo should not change it
+        if self.content.ends_with('\n') && !lines.is_empty() {
+            new_content.push('\n');
+        }
+        self.set_content(new_content, &edit, author);
+        Ok(self.record_operation(author, edit, Some(seq)))
+    }
+
+    /// Folds `edit` into the author's previous operation when it directly
+    /// continues it (e.g. typing the next character).
+    fn merge_into_last_operation(&mut self, author: &str, edit: &LineEdit) -> bool {
+        let next_seq = self.first_operation + self.operations.len() as u64;
+        let on_top_of_undo_stack = self
+            .undo_stacks
+            .get(author)
+            .and_then(|stack| stack.last())
+            .is_some_and(|seq| seq + 1 == next_seq);
+        let Some(last) = self.operations.back_mut() else { return false };
+        if !on_top_of_undo_stack || last.reverts.is_some() || last.author != author || last.at.elapsed() > UNDO_GROUP_TIMEOUT {
+            return false;
+        }
+        match last.edit.compose(edit) {
+            Some(composed) => {
+                last.edit = composed;
+                last.at = Instant::now();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn record_operation(&mut self, author: &str, edit: LineEdit, reverts: Option<u64>) -> u64 {
+        if self.operations.len() == MAX_OPERATIONS {
+            self.operations.pop_front();
+            self.first_operation += 1;
+        }
+        self.operations.push_back(Operation {
+            author: author.to_string(),
+            edit,
+            at: Instant::now(),
+            reverts,
+        });
+        self.first_operation + self.operations.len() as u64 - 1
+    }
+
+    /// Replaces the content with the result of `edit`, attributing the lines
+    /// it inserted or changed to `author`. Only the edited block is touched in
+    /// the blame and the search index, so the cost does not grow with the document.
+    fn set_content(&mut self, new_content: String, edit: &LineEdit, author: &str) {
+        let start = edit.start.min(self.line_authors.len());
+        let end = (edit.start + edit.removed.len()).min(self.line_authors.len());
+        let authors: Vec<String> = edit
+            .inserted
+            .iter()
+            .enumerate()
+            .map(|(i, line)| match self.line_authors.get(start + i) {
+                // A line the edit rewrote to the same text keeps its author
+                Some(previous) if edit.removed.get(i) == Some(line) => previous.clone(),
+                _ => author.to_string(),
+            })
+            .collect();
+        self.line_authors.splice(start..end, authors);
+        self.index.apply(edit);
+        self.content = new_content;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authors(document: &Document) -> Vec<&str> {
+        document.line_authors.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn loaded_lines_have_no_author() {
+        let document = Document::new("a\nb".to_string());
+        assert_eq!(authors(&document), ["", ""]);
+    }
+
+    #[test]
+    fn edited_and_inserted_lines_are_attributed_to_the_editor() {
+        let mut document = Document::new("a\nb\nc".to_string());
+        document.update("a\nB\nc".to_string(), "ayse");
+        assert_eq!(authors(&document), ["", "ayse", ""]);
+        document.update("a\nB\nnew\nc".to_string(), "mehmet");
+        assert_eq!(authors(&document), ["", "ayse", "mehmet", ""]);
+        document.update("B\nnew\nc".to_string(), "mehmet");
+        assert_eq!(authors(&document), ["ayse", "mehmet", ""]);
+    }
+
+    #[test]
+    fn unchanged_lines_inside_an_edited_block_keep_their_author() {
+        let mut document = Document::new("a\nb\nc".to_string());
+        document.update("x\nb\ny".to_string(), "ayse");
+        assert_eq!(authors(&document), ["ayse", "", "ayse"]);
+    }
+
+    #[test]
+    fn search_index_follows_edits_and_undo() {
+        let found = |document: &Document, word: &str| document.index.matching_lines(&[word.to_string()]);
+        let mut document = Document::new("alpha\nbeta".to_string());
+        document.update("alpha\ngamma\nbeta".to_string(), "ayse");
+        assert_eq!(found(&document, "beta"), [2].into());
+        assert_eq!(found(&document, "gamma"), [1].into());
+        document.undo("ayse").unwrap();
+        assert_eq!(found(&document, "beta"), [1].into());
+        assert!(found(&document, "gamma").is_empty());
+    }
+
+    #[test]
+    fn undo_and_redo_revert_only_the_users_own_edit() {
+        let mut document = Document::new("a\nb\nc".to_string());
+        document.update("A\nb\nc".to_string(), "ayse");
+        document.update("A\nb\nC".to_string(), "mehmet");
+        document.undo("ayse").unwrap();
+        assert_eq!(document.content, "a\nb\nC");
+        document.redo("ayse").unwrap();
+        assert_eq!(document.content, "A\nb\nC");
+        assert_eq!(document.redo("ayse"), Err(UndoError::NothingToRedo));
+    }
+
+    #[test]
+    fn undo_is_rebased_over_later_edits_above() {
+        let mut document = Document::new("a\nb".to_string());
+        document.update("a\nB".to_string(), "ayse");
+        document.update("top\na\nB".to_string(), "mehmet");
+        document.undo("ayse").unwrap();
+        assert_eq!(document.content, "top\na\nb");
+    }
+
+    #[test]
+    fn undo_fails_when_someone_changed_the_same_lines() {
+        let mut document = Document::new("a".to_string());
+        document.update("b".to_string(), "ayse");
+        document.update("c".to_string(), "mehmet");
+        assert_eq!(document.undo("ayse"), Err(UndoError::Conflict("mehmet".to_string())));
+        assert_eq!(document.content, "c");
+        assert_eq!(document.undo("nobody"), Err(UndoError::NothingToUndo));
+    }
+
+    #[test]
+    fn quick_consecutive_edits_are_undone_together() {
+        let mut document = Document::new(String::new());
+        for text in ["h", "he", "hel"] {
+            document.update(text.to_string(), "ayse");
+        }
+        document.undo("ayse").unwrap();
+        assert_eq!(document.content, "");
+    }
+
+    #[test]
+    fn trailing_newline_only_change_keeps_authors() {
+        let mut document = Document::new("a".to_string());
+        document.update("a\n".to_string(), "ayse");
+        assert_eq!(document.content, "a\n");
+        assert_eq!(authors(&document), [""]);
+    }
+
+    #[test]
+    fn undo_and_redo_keep_the_trailing_newline() {
+        let mut document = Document::new("a\nb\n".to_string());
+        document.update("a\nB\n".to_string(), "ayse");
+        document.undo("ayse").unwrap();
+        assert_eq!(document.content, "a\nb\n");
+        document.redo("ayse").unwrap();
+        assert_eq!(document.content, "a\nB\n");
+    }
+}
@@ -1,56 +1,365 @@
-use std::collections::HashMap;
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::Path;
-use editor_protocol::DOCUMENTS_DIR;
-
-/// Loads all documents from the `DOCUMENTS_DIR` directory.
-pub fn load_all_documents(docs_map: &mut HashMap<String, String>) {
-    let doc_dir_path = Path::new(DOCUMENTS_DIR);
-    if !doc_dir_path.exists() {
-        if let Err(e) = fs::create_dir_all(doc_dir_path) {
-            eprintln!("Could not create document directory {}: {}", DOCUMENTS_DIR, e);
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use editor_protocol::{format_error, ErrorCode};
+use tracing::{debug, error, info, warn};
+use crate::client_handler::{self, SharedServerState};
+use crate::i18n::error_message;
+use crate::config::{ServerConfig, SharedConfig, StorageBackend};
+use crate::document::Document;
+use crate::metrics::METRICS;
+use crate::sqlite_store::SqliteStore;
+use crate::storage::{DocumentStore, FileStore, Revision};
+
+/// Opens the storage backend selected in the config.
+pub fn open_store(config: &ServerConfig) -> io::Result<Arc<dyn DocumentStore>> {
+    Ok(match config.storage_backend {
+        StorageBackend::File => {
+            Arc::new(FileStore::open(&config.documents_dir, config.allowed_extensions.clone(), config.history_limit)?)
+        }
+        StorageBackend::Sqlite => Arc::new(SqliteStore::open(&config.sqlite_path, config.history_limit)?),
+    })
+}
+
+/// Loads all documents from the store.
+pub fn load_all_documents(store: &dyn DocumentStore, docs_map: &mut HashMap<String, Document>) {
+    let doc_names = match store.list() {
+        Ok(doc_names) => doc_names,
+        Err(e) => {
+            error!(error = %e, "Could not list documents");
             return;
         }
-        println!("Document directory created: {}", DOCUMENTS_DIR);
-    }
-
-    match fs::read_dir(doc_dir_path) {
-        Ok(entries) => {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    if path.is_file() && path.extension().map_or(false, |ext| ext == "txt") {
-                        if let Some(doc_name) = path.file_name().and_then(|name| name.to_str()) {
-                            match fs::read_to_string(&path) {
-                                Ok(content) => {
-                                    docs_map.insert(doc_name.to_string(), content);
-                                    println!("Loaded document: {}", doc_name);
-                                }
-                                Err(e) => {
-                                    eprintln!("Error reading document {}: {}", doc_name, e);
-                                }
-                            }
-                        }
+    };
+
+    let count = doc_names.len();
+    for doc_name in doc_names {
+        match store.load(&doc_name) {
+            Ok(content) => {
+                docs_map.insert(doc_name.clone(), Document::new(content));
+                debug!(document = %doc_name, "Loaded document");
+            }
+            Err(e) => {
+                error!(document = %doc_name, error = %e, "Could not read document");
+            }
+        }
+    }
+    info!(loaded = docs_map.len(), found = count, "Documents loaded");
+}
+
+enum PersistenceRequest {
+    MarkDirty { doc_name: String, author: String, size: usize },
+    /// Flush right away and report how many documents were written.
+    Flush(Sender<usize>),
+    Shutdown,
+}
+
+/// Cheap, cloneable handle used by client handlers to talk to the persistence worker.
+#[derive(Clone)]
+pub struct PersistenceHandle {
+    tx: Sender<PersistenceRequest>,
+    store: Arc<dyn DocumentStore>,
+}
+
+impl PersistenceHandle {
+    /// Marks a document as changed by `author`; it will be written on the next flush.
+    pub fn mark_dirty(&self, doc_name: &str, author: &str, size: usize) {
+        let _ = self.tx.send(PersistenceRequest::MarkDirty {
+            doc_name: doc_name.to_string(),
+            author: author.to_string(),
+            size,
+        });
+    }
+
+    /// Writes every dirty document now instead of waiting for the next flush.
+    /// Returns the number of documents saved.
+    pub fn flush(&self) -> usize {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        if self.tx.send(PersistenceRequest::Flush(reply_tx)).is_err() {
+            return 0;
+        }
+        reply_rx.recv().unwrap_or(0)
+    }
+
+    /// Writes a document synchronously, bypassing the debounce (e.g. on creation).
+    pub fn save_now(&self, doc_name: &str, content: &str, author: &str) -> io::Result<u64> {
+        timed_save(self.store.as_ref(), doc_name, content, author)
+    }
+
+    /// Removes a document from storage.
+    pub fn delete(&self, doc_name: &str) -> io::Result<()> {
+        self.store.delete(doc_name)
+    }
+
+    /// Saved revisions of a document, oldest first.
+    pub fn history(&self, doc_name: &str) -> io::Result<Vec<Revision>> {
+        self.store.history(doc_name)
+    }
+
+    /// Content of a saved revision.
+    pub fn load_revision(&self, doc_name: &str, number: u64) -> io::Result<String> {
+        self.store.load_revision(doc_name, number)
+    }
+}
+
+/// Background task that coalesces document changes and writes them to disk
+/// on an interval or once enough dirty content has piled up.
+pub struct PersistenceWorker {
+    handle: PersistenceHandle,
+    thread: JoinHandle<()>,
+}
+
+impl PersistenceWorker {
+    /// Spawns the worker thread.
+    /// Flush settings are re-read from `config` on every iteration, so a config
+    /// reload takes effect without a restart.
+    pub fn start(shared_state: SharedServerState, store: Arc<dyn DocumentStore>, config: SharedConfig) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let worker = FlushWorker { shared_state, store: Arc::clone(&store), config };
+        let thread = thread::spawn(move || worker.run(rx));
+        PersistenceWorker { handle: PersistenceHandle { tx, store }, thread }
+    }
+
+    pub fn handle(&self) -> PersistenceHandle {
+        self.handle.clone()
+    }
+
+    /// Flushes everything that is still dirty and stops the worker.
+    pub fn shutdown(self) {
+        let _ = self.handle.tx.send(PersistenceRequest::Shutdown);
+        if self.thread.join().is_err() {
+            error!("Persistence worker panicked during shutdown");
+        }
+    }
+}
+
+struct FlushWorker {
+    shared_state: SharedServerState,
+    store: Arc<dyn DocumentStore>,
+    config: SharedConfig,
+}
+
+/// Longest wait between retries of a document that keeps failing to save.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// A document changed since its last successful save.
+struct DirtyDocument {
+    /// Users who changed it since then.
+    authors: BTreeSet<String>,
+    /// Size of the latest content; each change replaces it rather than adding to it.
+    size: usize,
+    /// When the document is written next.
+    due: Instant,
+    /// Failed saves in a row; the retry delay doubles with each one.
+    failures: u32,
+}
+
+impl FlushWorker {
+    fn run(&self, rx: Receiver<PersistenceRequest>) {
+        let mut dirty: HashMap<String, DirtyDocument> = HashMap::new();
+
+        loop {
+            let (flush_interval, flush_size_threshold) = {
+                let config = self.config.read().unwrap();
+                (config.flush_interval, config.flush_size_threshold)
+            };
+            let request = match dirty.values().map(|document| document.due).min() {
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                Some(due) => rx.recv_timeout(due.saturating_duration_since(Instant::now())),
+            };
+
+            match request {
+                Ok(PersistenceRequest::MarkDirty { doc_name, author, size }) => {
+                    // The interval starts with the first change, not the last flush.
+                    let document = dirty.entry(doc_name).or_insert_with(|| DirtyDocument {
+                        authors: BTreeSet::new(),
+                        size: 0,
+                        due: Instant::now() + flush_interval,
+                        failures: 0,
+                    });
+                    document.authors.insert(author);
+                    document.size = size;
+                    // Documents waiting out a failed save do not count; writing
+                    // them early would only fail again.
+                    let pending_bytes: usize =
+                        dirty.values().filter(|document| document.failures == 0).map(|document| document.size).sum();
+                    if pending_bytes >= flush_size_threshold {
+                        self.flush_dirty_documents(&mut dirty, flush_interval, |document| document.failures == 0);
                     }
                 }
+                Ok(PersistenceRequest::Flush(reply)) => {
+                    let _ = reply.send(self.flush_dirty_documents(&mut dirty, flush_interval, |_| true));
+                }
+                Ok(PersistenceRequest::Shutdown) | Err(RecvTimeoutError::Disconnected) => {
+                    self.flush_dirty_documents(&mut dirty, flush_interval, |_| true);
+                    if !dirty.is_empty() {
+                        error!(count = dirty.len(), "Documents could not be saved before shutdown");
+                    }
+                    break;
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let now = Instant::now();
+                    self.flush_dirty_documents(&mut dirty, flush_interval, |document| document.due <= now);
+                }
             }
         }
-        Err(e) => {
-            eprintln!("Error reading directory {}: {}", DOCUMENTS_DIR, e);
+    }
+
+    /// Writes the latest content of the dirty documents `select` picks.
+    /// Documents that fail to save stay dirty and are retried with a growing
+    /// delay; their editors are told about the first failure only.
+    /// Returns the number of documents written.
+    fn flush_dirty_documents(
+        &self,
+        dirty: &mut HashMap<String, DirtyDocument>,
+        flush_interval: Duration,
+        select: impl Fn(&DirtyDocument) -> bool,
+    ) -> usize {
+        let selected: Vec<String> = dirty.iter().filter(|(_, document)| select(document)).map(|(name, _)| name.clone()).collect();
+        if selected.is_empty() {
+            return 0;
+        }
+
+        // Snapshot under the lock, write to storage without holding it.
+        let snapshot: Vec<(String, DirtyDocument, String)> = {
+            let state = self.shared_state.lock().unwrap();
+            selected
+                .into_iter()
+                .filter_map(|name| {
+                    let document = dirty.remove(&name)?;
+                    let content = state.documents.get(&name)?.content.clone();
+                    Some((name, document, content))
+                })
+                .collect()
+        };
+
+        let mut saved = 0;
+        for (doc_name, mut document, content) in snapshot {
+            let author = document.authors.iter().filter(|a| !a.is_empty()).cloned().collect::<Vec<_>>().join(",");
+            match timed_save(self.store.as_ref(), &doc_name, &content, &author) {
+                Ok(_) => {
+                    if document.failures > 0 {
+                        info!(document = %doc_name, attempts = document.failures + 1, "Document saved after earlier failures");
+                    }
+                    saved += 1;
+                }
+                Err(e) => {
+                    document.failures += 1;
+                    let delay = flush_interval.saturating_mul(1 << document.failures.min(16)).min(MAX_RETRY_DELAY);
+                    document.due = Instant::now() + delay;
+                    if document.failures == 1 {
+                        warn!(document = %doc_name, error = %e, "Could not save document; will retry");
+                        let state = self.shared_state.lock().unwrap();
+                        client_handler::send_message_to_editors(
+                            &state.clients,
+                            None,
+                            &doc_name,
+                            &format_error(ErrorCode::StorageFailed, None, &error_message(ErrorCode::StorageFailed, format!("{}: {}", doc_name, e))),
+                        );
+                    } else {
+                        debug!(document = %doc_name, error = %e, failures = document.failures, retry_in = ?delay, "Save retry failed");
+                    }
+                    dirty.insert(doc_name, document);
+                }
+            }
         }
+        saved
     }
 }
 
-/// Saves a document to the disk.
-pub fn save_document(doc_name: &str, content: &str) -> Result<(), std::io::Error> {
-    let path_str = format!("{}{}", DOCUMENTS_DIR, doc_name);
-    let path = Path::new(&path_str);
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+/// Saves through the store and records the latency and outcome in the metrics.
+fn timed_save(store: &dyn DocumentStore, doc_name: &str, content: &str, author: &str) -> io::Result<u64> {
+    let started = Instant::now();
+    let result = store.save(doc_name, content, author);
+    METRICS.save_finished(started.elapsed(), result.is_ok());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_handler::ServerSharedState;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Mutex, RwLock};
+
+    /// Counts save attempts; fails every one of them when `fail` is set.
+    #[derive(Default)]
+    struct CountingStore {
+        saves: AtomicUsize,
+        fail: bool,
+    }
+
+    impl DocumentStore for CountingStore {
+        fn list(&self) -> io::Result<Vec<String>> {
+            Ok(Vec::new())
+        }
+        fn load(&self, _name: &str) -> io::Result<String> {
+            Ok(String::new())
+        }
+        fn save(&self, _name: &str, _content: &str, _author: &str) -> io::Result<u64> {
+            self.saves.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(io::Error::other("disk full"));
+            }
+            Ok(1)
+        }
+        fn delete(&self, _name: &str) -> io::Result<()> {
+            Ok(())
+        }
+        fn history(&self, _name: &str) -> io::Result<Vec<Revision>> {
+            Ok(Vec::new())
+        }
+        fn load_revision(&self, _name: &str, _number: u64) -> io::Result<String> {
+            Ok(String::new())
+        }
     }
-    let mut file = File::create(path)?;
-    file.write_all(content.as_bytes())?;
-    println!("Document saved: {}", doc_name);
-    Ok(())
-}
\ No newline at end of file
+
+    fn start_worker(store: Arc<CountingStore>, flush_size_threshold: usize) -> PersistenceWorker {
+        let mut documents = HashMap::new();
+        documents.insert("a.txt".to_string(), Document::new("x".repeat(40)));
+        let state = ServerSharedState { documents, clients: HashMap::new(), sessions: HashMap::new() };
+        let config = ServerConfig { flush_interval: Duration::from_secs(3600), flush_size_threshold, ..Default::default() };
+        PersistenceWorker::start(Arc::new(Mutex::new(state)), store, Arc::new(RwLock::new(config)))
+    }
+
+    #[test]
+    fn repeated_edits_of_one_document_do_not_add_up() {
+        let store = Arc::new(CountingStore::default());
+        let worker = start_worker(Arc::clone(&store), 100);
+        let handle = worker.handle();
+        for _ in 0..10 {
+            handle.mark_dirty("a.txt", "ayse", 40);
+        }
+        // The flush request is queued behind the edits, so they have all been seen.
+        assert_eq!(handle.flush(), 1);
+        assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+        worker.shutdown();
+    }
+
+    #[test]
+    fn reaching_the_size_threshold_flushes_early() {
+        let store = Arc::new(CountingStore::default());
+        let worker = start_worker(Arc::clone(&store), 40);
+        let handle = worker.handle();
+        handle.mark_dirty("a.txt", "ayse", 40);
+        assert_eq!(handle.flush(), 0);
+        assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+        worker.shutdown();
+    }
+
+    #[test]
+    fn failed_document_waits_before_the_next_attempt() {
+        let store = Arc::new(CountingStore { fail: true, ..Default::default() });
+        let worker = start_worker(Arc::clone(&store), 40);
+        let handle = worker.handle();
+        handle.mark_dirty("a.txt", "ayse", 40);
+        // Further edits do not retry a document that is backing off.
+        handle.mark_dirty("a.txt", "ayse", 40);
+        handle.mark_dirty("a.txt", "ayse", 40);
+        assert_eq!(handle.flush(), 0);
+        assert_eq!(store.saves.load(Ordering::SeqCst), 2);
+        worker.shutdown();
+    }
+}
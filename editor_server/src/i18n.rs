@@ -0,0 +1,89 @@
+use crate::document::UndoError;
+use editor_protocol::locale::{fill, Locale};
+use editor_protocol::ErrorCode;
+use std::fmt::Display;
+use std::sync::RwLock;
+
+/// Language of the texts sent to clients; see `language` in the config.
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::En);
+
+pub fn set_locale(locale: Locale) {
+    *LOCALE.write().unwrap() = locale;
+}
+
+/// The human-readable part of an `ERROR` message. `detail` fills the `{}` of
+/// the code's text (a document name, a limit, the expected usage...).
+pub fn error_message(code: ErrorCode, detail: impl Display) -> String {
+    let template = match *LOCALE.read().unwrap() {
+        Locale::En => english(code),
+        Locale::Tr => turkish(code),
+    };
+    fill(template, &[&detail])
+}
+
+/// Why an undo/redo failed; the detail of an `UndoFailed` error.
+pub fn undo_error_message(error: &UndoError) -> String {
+    let (template, detail) = match (*LOCALE.read().unwrap(), error) {
+        (Locale::En, UndoError::NothingToUndo) => ("nothing to undo", ""),
+        (Locale::En, UndoError::NothingToRedo) => ("nothing to redo", ""),
+        (Locale::En, UndoError::TooOld) => ("the edit is too old to undo", ""),
+        (Locale::En, UndoError::Conflict(author)) => ("{} has since changed the same lines", author.as_str()),
+        (Locale::En, UndoError::Invalid(reason)) => ("the edit no longer applies ({})", reason.as_str()),
+        (Locale::Tr, UndoError::NothingToUndo) => ("geri alınacak değişiklik yok", ""),
+        (Locale::Tr, UndoError::NothingToRedo) => ("yinelenecek değişiklik yok", ""),
+        (Locale::Tr, UndoError::TooOld) => ("değişiklik geri alınamayacak kadar eski", ""),
+        (Locale::Tr, UndoError::Conflict(author)) => ("{} aynı satırları sonradan değiştirdi", author.as_str()),
+        (Locale::Tr, UndoError::Invalid(reason)) => ("değişiklik artık uygulanamıyor ({})", reason.as_str()),
+    };
+    fill(template, &[&detail])
+}
+
+fn english(code: ErrorCode) -> &'static str {
+    match code {
+        ErrorCode::BadRequest => "Usage: {}",
+        ErrorCode::UnknownCommand => "Unknown command: {}",
+        ErrorCode::InvalidMessage => "Invalid message: {}",
+        ErrorCode::NotConnected => "Sign in with CONNECT first",
+        ErrorCode::AlreadyConnected => "Already signed in as {}",
+        ErrorCode::DocumentNotFound => "Document not found: {}",
+        ErrorCode::DocumentExists => "Document already exists: {}",
+        ErrorCode::NotEditingDocument => "You are not editing {}; switch to it first",
+        ErrorCode::DocumentLocked => "Document is locked: {}",
+        ErrorCode::RevisionNotFound => "Revision not found: {}",
+        ErrorCode::UndoFailed => "Undo/redo failed: {}",
+        ErrorCode::StorageFailed => "Storage error: {}",
+        ErrorCode::UserNotFound => "No connected user named {}",
+        ErrorCode::LineTooLong => "Line too long (at most {} bytes)",
+        ErrorCode::DocumentTooLarge => "Document too large (at most {} bytes)",
+        ErrorCode::RateLimited => "Too many messages, command ignored",
+        ErrorCode::TooManyConnections => "Too many connections from this address (at most {})",
+        ErrorCode::ExtensionNotAllowed => "File type not allowed: {}",
+        ErrorCode::InvalidArgument => "Invalid argument: {}",
+        ErrorCode::Internal => "Internal error: {}",
+    }
+}
+
+fn turkish(code: ErrorCode) -> &'static str {
+    match code {
+        ErrorCode::BadRequest => "Kullanım: {}",
+        ErrorCode::UnknownCommand => "Bilinmeyen komut: {}",
+        ErrorCode::InvalidMessage => "Geçersiz mesaj: {}",
+        ErrorCode::NotConnected => "Önce CONNECT ile bağlanmalısınız",
+        ErrorCode::AlreadyConnected => "Zaten {} olarak bağlısınız",
+        ErrorCode::DocumentNotFound => "Belge bulunamadı: {}",
+        ErrorCode::DocumentExists => "Belge zaten var: {}",
+        ErrorCode::NotEditingDocument => "{} belgesini düzenlemiyorsunuz; önce geçiş yapın",
+        ErrorCode::DocumentLocked => "Belge kilitli: {}",
+        ErrorCode::RevisionNotFound => "Revizyon bulunamadı: {}",
+        ErrorCode::UndoFailed => "Geri alma/yineleme yapılamadı: {}",
+        ErrorCode::StorageFailed => "Depolama hatası: {}",
+        ErrorCode::UserNotFound => "{} adında bağlı kullanıcı yok",
+        ErrorCode::LineTooLong => "Satır çok uzun (en fazla {} bayt)",
+        ErrorCode::DocumentTooLarge => "Belge çok büyük (en fazla {} bayt)",
+        ErrorCode::RateLimited => "Çok fazla mesaj gönderildi, komut yok sayıldı",
+        ErrorCode::TooManyConnections => "Bu adresten çok fazla bağlantı var (en fazla {})",
+        ErrorCode::ExtensionNotAllowed => "İzin verilmeyen dosya türü: {}",
+        ErrorCode::InvalidArgument => "Geçersiz argüman: {}",
+        ErrorCode::Internal => "İç hata: {}",
+    }
+}
@@ -0,0 +1,199 @@
+use crate::i18n::error_message;
+use editor_protocol::{ErrorCode, END_OF_MESSAGE_DELIMITER, UPDATE_DOCUMENT_CMD};
+use std::io::{self, BufRead, ErrorKind};
+use std::time::Instant;
+
+/// Result of reading one line with a length cap.
+pub enum LineRead {
+    Eof,
+    /// A complete line was read; the value is the number of bytes consumed.
+    Line(usize),
+    /// The line was longer than allowed. It was consumed and discarded so the
+    /// next read starts at the following line.
+    TooLong(usize),
+}
+
+/// Like `BufRead::read_line`, but never buffers more than `max` bytes of a
+/// line. `max == 0` means no limit.
+pub fn read_line_limited(reader: &mut impl BufRead, buf: &mut String, max: usize) -> io::Result<LineRead> {
+    let mut bytes = Vec::new();
+    let mut consumed = 0;
+    let mut too_long = false;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            break;
+        }
+        let (chunk, found_newline) = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => (&available[..=i], true),
+            None => (available, false),
+        };
+        if !too_long {
+            if max > 0 && bytes.len() + chunk.len() > max + 1 {
+                too_long = true;
+                bytes = Vec::new();
+            } else {
+                bytes.extend_from_slice(chunk);
+            }
+        }
+        let len = chunk.len();
+        reader.consume(len);
+        consumed += len;
+        if found_newline {
+            break;
+        }
+    }
+    if consumed == 0 {
+        return Ok(LineRead::Eof);
+    }
+    if too_long {
+        return Ok(LineRead::TooLong(consumed));
+    }
+    let line = String::from_utf8(bytes).map_err(|_| io::Error::new(ErrorKind::InvalidData, "stream did not contain valid UTF-8"))?;
+    buf.push_str(&line);
+    Ok(LineRead::Line(consumed))
+}
+
+/// Token bucket allowing `per_second` messages per second on average, with
+/// bursts of up to one second's worth. `per_second == 0` disables it.
+pub struct RateLimiter {
+    per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: u32) -> Self {
+        let per_second = f64::from(per_second);
+        RateLimiter { per_second, tokens: per_second, last_refill: Instant::now() }
+    }
+
+    /// Takes a token if one is available.
+    pub fn allow(&mut self) -> bool {
+        self.allow_at(Instant::now())
+    }
+
+    fn allow_at(&mut self, now: Instant) -> bool {
+        if self.per_second == 0.0 {
+            return true;
+        }
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * self.per_second;
+        self.tokens = (self.tokens + refill).min(self.per_second);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Why an `UPDATE_DOCUMENT` body was rejected.
+#[derive(Debug)]
+pub enum BodyError {
+    LineTooLong(usize),
+    DocumentTooLarge(usize),
+    /// The connection ended before `END_OF_MESSAGE`; the content is cut short.
+    Incomplete,
+}
+
+impl BodyError {
+    /// Label used for the violation in logs and metrics.
+    pub fn limit(&self) -> &'static str {
+        match self {
+            BodyError::LineTooLong(_) => "line_length",
+            BodyError::DocumentTooLarge(_) => "document_size",
+            BodyError::Incomplete => "incomplete_body",
+        }
+    }
+
+    /// The limit that was exceeded, in bytes.
+    pub fn max(&self) -> usize {
+        match self {
+            BodyError::LineTooLong(max) | BodyError::DocumentTooLarge(max) => *max,
+            BodyError::Incomplete => 0,
+        }
+    }
+
+    /// Error code reported to the client.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            BodyError::LineTooLong(_) => ErrorCode::LineTooLong,
+            BodyError::DocumentTooLarge(_) => ErrorCode::DocumentTooLarge,
+            BodyError::Incomplete => ErrorCode::BadRequest,
+        }
+    }
+}
+
+impl std::fmt::Display for BodyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyError::Incomplete => {
+                let usage = format!("{} <document>, <lines>, {}", UPDATE_DOCUMENT_CMD, END_OF_MESSAGE_DELIMITER);
+                f.write_str(&error_message(self.code(), usage))
+            }
+            _ => f.write_str(&error_message(self.code(), self.max())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn allowed(limiter: &mut RateLimiter, now: Instant, attempts: usize) -> usize {
+        (0..attempts).filter(|_| limiter.allow_at(now)).count()
+    }
+
+    #[test]
+    fn rate_limiter_allows_one_second_burst() {
+        let mut limiter = RateLimiter::new(10);
+        let start = limiter.last_refill;
+        assert_eq!(allowed(&mut limiter, start, 15), 10);
+        assert!(!limiter.allow_at(start));
+    }
+
+    #[test]
+    fn rate_limiter_refills_over_time() {
+        let mut limiter = RateLimiter::new(10);
+        let start = limiter.last_refill;
+        allowed(&mut limiter, start, 10);
+        assert_eq!(allowed(&mut limiter, start + Duration::from_millis(500), 10), 5);
+        // A long pause refills at most one second's worth
+        assert_eq!(allowed(&mut limiter, start + Duration::from_secs(60), 30), 10);
+    }
+
+    #[test]
+    fn rate_limiter_zero_disables_the_limit() {
+        let mut limiter = RateLimiter::new(0);
+        assert_eq!(allowed(&mut limiter, Instant::now(), 1000), 1000);
+    }
+
+    fn read_lines(input: &str, max: usize) -> Vec<Result<String, usize>> {
+        let mut reader = io::BufReader::with_capacity(4, input.as_bytes());
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            match read_line_limited(&mut reader, &mut line, max).unwrap() {
+                LineRead::Eof => return lines,
+                LineRead::Line(_) => lines.push(Ok(line)),
+                LineRead::TooLong(consumed) => lines.push(Err(consumed)),
+            }
+        }
+    }
+
+    #[test]
+    fn long_lines_are_discarded_up_to_the_newline() {
+        assert_eq!(
+            read_lines("short\nmuch too long\nok", 6),
+            [Ok("short\n".to_string()), Err(14), Ok("ok".to_string())]
+        );
+        assert_eq!(read_lines("much too long\n", 0), [Ok("much too long\n".to_string())]);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::config::{LogFormat, ServerConfig};
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+/// Whether `content` may show document text; see `log_document_content`.
+static LOG_CONTENT: AtomicBool = AtomicBool::new(false);
+
+/// Lets a config reload change the level without restarting.
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Installs the global subscriber. Records go to stdout as text or one JSON
+/// object per line; `RUST_LOG`, when set, takes precedence over `log_level`.
+pub fn init(config: &ServerConfig) -> Result<(), String> {
+    let (filter, handle) = reload::Layer::new(build_filter(&config.log_level)?);
+    let output = match config.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().with_ansi(std::io::stdout().is_terminal()).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .try_init()
+        .map_err(|e| e.to_string())?;
+    let _ = FILTER.set(handle);
+    LOG_CONTENT.store(config.log_document_content, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Applies the level and redaction settings of a reloaded config. The output
+/// format is fixed once logging has started.
+pub fn apply(config: &ServerConfig) -> Result<(), String> {
+    LOG_CONTENT.store(config.log_document_content, Ordering::Relaxed);
+    match FILTER.get() {
+        Some(handle) => handle.reload(build_filter(&config.log_level)?).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+fn build_filter(level: &str) -> Result<EnvFilter, String> {
+    match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) => EnvFilter::try_new(directives).map_err(|e| format!("invalid RUST_LOG: {}", e)),
+        Err(_) => EnvFilter::try_new(level).map_err(|e| format!("invalid log_level '{}': {}", level, e)),
+    }
+}
+
+/// Wraps document text (or anything that may contain it) for logging: it is
+/// shown only when `log_document_content` is on, otherwise just its size.
+pub fn content(text: &str) -> Content<'_> {
+    Content(text)
+}
+
+pub struct Content<'a>(&'a str);
+
+impl fmt::Display for Content<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if LOG_CONTENT.load(Ordering::Relaxed) {
+            write!(f, "{:?}", self.0)
+        } else {
+            write!(f, "<redacted, {} bytes>", self.0.len())
+        }
+    }
+}
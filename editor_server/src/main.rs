@@ -1,38 +1,150 @@
 use editor_protocol::{PORT, SERVER_ADDRESS};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
 use std::collections::HashMap;
+use std::io::ErrorKind;
 use std::net::TcpListener;
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::{error, info, warn};
 
+mod admin;
 mod client_handler;
+mod config;
+mod document;
 mod document_manager;
+mod i18n;
+mod limits;
+mod logging;
+mod metrics;
+mod search_index;
+mod sqlite_store;
+mod storage;
 
-pub type ClientWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+use admin::AdminContext;
+use client_handler::{ServerSharedState, SharedServerState};
+use config::{ServerConfig, SharedConfig};
+use document_manager::PersistenceWorker;
+
+/// How often the accept loop checks whether a shutdown was requested.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 fn main() -> std::io::Result<()> {
+    let (config, problems) = ServerConfig::load();
+    logging::init(&config).map_err(std::io::Error::other)?;
+    let config_path = config::config_path();
+    if Path::new(&config_path).exists() {
+        info!(path = %config_path, "Loaded config");
+    } else {
+        info!(path = %config_path, "No config file, using defaults");
+    }
+    for problem in &problems {
+        warn!("{}", problem);
+    }
+    i18n::set_locale(config.language);
+    let config: SharedConfig = Arc::new(RwLock::new(config));
+    let store = document_manager::open_store(&config.read().unwrap())?;
+    let shutdown_reason = watch_shutdown_signals()?;
+
     let listener = TcpListener::bind(format!("{}:{}", SERVER_ADDRESS, PORT))?;
-    println!("Server started at {}:{}", SERVER_ADDRESS, PORT);
-
-    let documents_arc = Arc::new(Mutex::new(HashMap::<String, String>::new()));
-    document_manager::load_all_documents(&mut documents_arc.lock().unwrap());
-
-    let all_clients_writers_arc = Arc::new(Mutex::new(HashMap::<std::net::SocketAddr, ClientWriter>::new()));
-
-    for stream in listener.incoming() {
-        match stream {
-            Ok(stream) => {
-                println!("New connection accepted: {}", stream.peer_addr()?);
-                let documents_clone = Arc::clone(&documents_arc);
-                let all_clients_writers_clone = Arc::clone(&all_clients_writers_arc);
-                thread::spawn(move || {
-                    client_handler::handle_client(stream, documents_clone, all_clients_writers_clone);
-                });
+    // Non-blocking so the loop below can notice a shutdown request between connections.
+    listener.set_nonblocking(true)?;
+    info!(address = SERVER_ADDRESS, port = PORT, "Server started");
+
+    let shared_state: SharedServerState = Arc::new(Mutex::new(ServerSharedState {
+        documents: HashMap::new(),
+        clients: HashMap::new(),
+        sessions: HashMap::new(),
+    }));
+    document_manager::load_all_documents(store.as_ref(), &mut shared_state.lock().unwrap().documents);
+
+    let persistence = PersistenceWorker::start(Arc::clone(&shared_state), store, Arc::clone(&config));
+    client_handler::start_heartbeat(Arc::clone(&shared_state), Arc::clone(&config));
+
+    // Only a socket this server created is removed on shutdown.
+    let mut admin_socket = config.read().unwrap().admin_socket.clone();
+    if let Some(ref path) = admin_socket {
+        let context = AdminContext {
+            shared_state: Arc::clone(&shared_state),
+            persistence: persistence.handle(),
+            config: Arc::clone(&config),
+        };
+        // The editor keeps working without the admin channel.
+        if let Err(e) = admin::start_admin_listener(path, context) {
+            error!(path = %path, error = %e, "Could not open admin socket");
+            admin_socket = None;
+        }
+    }
+
+    let metrics_address = config.read().unwrap().metrics_address.clone();
+    if let Some(ref address) = metrics_address {
+        if let Err(e) = metrics::start_metrics_server(address, Arc::clone(&shared_state)) {
+            error!(address = %address, error = %e, "Could not start metrics endpoint");
+        }
+    }
+
+    let mut handler_threads: Vec<JoinHandle<()>> = Vec::new();
+    while shutdown_reason.get().is_none() {
+        match listener.accept() {
+            Ok((stream, peer_addr)) => {
+                if let Err(e) = stream.set_nonblocking(false) {
+                    warn!(peer = %peer_addr, error = %e, "Connection rejected");
+                    continue;
+                }
+                metrics::METRICS.connection_accepted();
+                let shared_state_clone = Arc::clone(&shared_state);
+                let persistence_handle = persistence.handle();
+                let (keepalive_timeout, limits) = {
+                    let config = config.read().unwrap();
+                    (config.keepalive_timeout, config.limits)
+                };
+                let config_clone = Arc::clone(&config);
+                handler_threads.retain(|handle| !handle.is_finished());
+                handler_threads.push(thread::spawn(move || {
+                    client_handler::handle_client(
+                        stream,
+                        shared_state_clone,
+                        persistence_handle,
+                        keepalive_timeout,
+                        limits,
+                        config_clone,
+                    );
+                }));
             }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
             Err(e) => {
-                eprintln!("Connection failed: {}", e);
+                warn!(error = %e, "Accepting a connection failed");
             }
         }
     }
+
+    let reason = shutdown_reason.get().copied().unwrap_or("shutdown requested");
+    info!(reason, "Shutting down, no longer accepting connections");
+    drop(listener);
+    client_handler::disconnect_all_clients(&shared_state, reason);
+    for handle in handler_threads {
+        let _ = handle.join();
+    }
+    // Handlers are gone, so this flush sees every edit that was accepted.
+    persistence.shutdown();
+    if let Some(ref path) = admin_socket {
+        admin::remove_socket(path);
+    }
+    info!("Server stopped");
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Records the first SIGINT/SIGTERM as the shutdown reason.
+fn watch_shutdown_signals() -> std::io::Result<Arc<OnceLock<&'static str>>> {
+    let reason = Arc::new(OnceLock::new());
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+    let reason_clone = Arc::clone(&reason);
+    thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            let _ = reason_clone.set(if signal == SIGINT { "interrupted (SIGINT)" } else { "terminated (SIGTERM)" });
+        }
+    });
+    Ok(reason)
+}
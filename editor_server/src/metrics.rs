@@ -0,0 +1,304 @@
+use crate::client_handler::SharedServerState;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Upper bounds (seconds) of the save latency histogram buckets.
+const SAVE_DURATION_BUCKETS: [f64; 8] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+/// Longest request line plus headers read from a scraper.
+const MAX_REQUEST_HEAD: u64 = 8 * 1024;
+
+/// Server-wide counters, updated from wherever the event happens.
+pub static METRICS: Metrics = Metrics::new();
+
+pub struct Metrics {
+    connections: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    save_failures: AtomicU64,
+    /// Limit name -> violations
+    limit_violations: Mutex<BTreeMap<&'static str, u64>>,
+    /// Command -> messages received
+    commands: Mutex<BTreeMap<&'static str, u64>>,
+    /// Message type -> (broadcasts, recipients reached)
+    broadcasts: Mutex<BTreeMap<String, (u64, u64)>>,
+    save_durations: Mutex<Histogram>,
+}
+
+struct Histogram {
+    buckets: [u64; SAVE_DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Metrics {
+            connections: AtomicU64::new(0),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            save_failures: AtomicU64::new(0),
+            limit_violations: Mutex::new(BTreeMap::new()),
+            commands: Mutex::new(BTreeMap::new()),
+            broadcasts: Mutex::new(BTreeMap::new()),
+            save_durations: Mutex::new(Histogram { buckets: [0; SAVE_DURATION_BUCKETS.len()], count: 0, sum: 0.0 }),
+        }
+    }
+
+    pub fn connection_accepted(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn bytes_received(&self, bytes: usize) {
+        self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn bytes_sent(&self, bytes: usize) {
+        self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Counts a received message. Unrecognised commands share one label so a
+    /// misbehaving client cannot create unbounded series.
+    pub fn command_received(&self, command: &'static str) {
+        *self.commands.lock().unwrap().entry(command).or_default() += 1;
+    }
+
+    pub fn limit_violation(&self, limit: &'static str) {
+        *self.limit_violations.lock().unwrap().entry(limit).or_default() += 1;
+    }
+
+    /// Counts a message sent to several clients, labelled by its first word.
+    pub fn broadcast(&self, message: &str, recipients: usize) {
+        let kind = message.split(' ').next().unwrap_or_default();
+        let mut broadcasts = self.broadcasts.lock().unwrap();
+        let entry = broadcasts.entry(kind.to_string()).or_default();
+        entry.0 += 1;
+        entry.1 += recipients as u64;
+    }
+
+    /// Records how long a storage write took and whether it failed.
+    pub fn save_finished(&self, duration: Duration, succeeded: bool) {
+        if !succeeded {
+            self.save_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        let seconds = duration.as_secs_f64();
+        let mut histogram = self.save_durations.lock().unwrap();
+        for (bucket, bound) in histogram.buckets.iter_mut().zip(SAVE_DURATION_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.count += 1;
+        histogram.sum += seconds;
+    }
+
+    /// Renders everything in the Prometheus text exposition format. Session and
+    /// document gauges are read from the shared state at scrape time.
+    pub fn render(&self, shared_state: &SharedServerState) -> String {
+        let mut out = String::new();
+        {
+            let state = shared_state.lock().unwrap();
+            let signed_in = state.clients.values().filter(|client| client.username.is_some()).count();
+            let detached = state.sessions.values().filter(|session| session.detached_at.is_some()).count();
+            gauge_header(&mut out, "editor_sessions", "Sessions by state.");
+            let _ = writeln!(out, "editor_sessions{{state=\"connected\"}} {}", signed_in);
+            let _ = writeln!(out, "editor_sessions{{state=\"anonymous\"}} {}", state.clients.len() - signed_in);
+            let _ = writeln!(out, "editor_sessions{{state=\"detached\"}} {}", detached);
+
+            gauge_header(&mut out, "editor_documents", "Documents held in memory.");
+            let _ = writeln!(out, "editor_documents {}", state.documents.len());
+
+            gauge_header(&mut out, "editor_document_size_bytes", "Current size of each document.");
+            let mut documents: Vec<_> = state.documents.iter().collect();
+            documents.sort_by_key(|(name, _)| name.as_str());
+            for (name, document) in documents {
+                let _ = writeln!(out, "editor_document_size_bytes{{document=\"{}\"}} {}", escape_label(name), document.content.len());
+            }
+        }
+
+        counter(&mut out, "editor_connections_total", "TCP connections accepted.", self.connections.load(Ordering::Relaxed));
+        counter(&mut out, "editor_received_bytes_total", "Bytes read from clients.", self.bytes_in.load(Ordering::Relaxed));
+        counter(&mut out, "editor_sent_bytes_total", "Bytes written to clients.", self.bytes_out.load(Ordering::Relaxed));
+
+        counter_header(&mut out, "editor_messages_received_total", "Messages received from clients by command.");
+        for (command, count) in self.commands.lock().unwrap().iter() {
+            let _ = writeln!(out, "editor_messages_received_total{{command=\"{}\"}} {}", command, count);
+        }
+
+        counter_header(&mut out, "editor_limit_violations_total", "Requests rejected by a connection limit.");
+        for (limit, count) in self.limit_violations.lock().unwrap().iter() {
+            let _ = writeln!(out, "editor_limit_violations_total{{limit=\"{}\"}} {}", limit, count);
+        }
+
+        let broadcasts = self.broadcasts.lock().unwrap();
+        counter_header(&mut out, "editor_broadcasts_total", "Messages sent to several clients by message type.");
+        for (kind, (count, _)) in broadcasts.iter() {
+            let _ = writeln!(out, "editor_broadcasts_total{{message=\"{}\"}} {}", escape_label(kind), count);
+        }
+        counter_header(&mut out, "editor_broadcast_recipients_total", "Clients reached by broadcasts by message type.");
+        for (kind, (_, recipients)) in broadcasts.iter() {
+            let _ = writeln!(out, "editor_broadcast_recipients_total{{message=\"{}\"}} {}", escape_label(kind), recipients);
+        }
+        drop(broadcasts);
+
+        counter(&mut out, "editor_save_failures_total", "Document saves that failed.", self.save_failures.load(Ordering::Relaxed));
+        let histogram = self.save_durations.lock().unwrap();
+        let _ = writeln!(out, "# HELP editor_save_duration_seconds Time spent writing a document to storage.");
+        let _ = writeln!(out, "# TYPE editor_save_duration_seconds histogram");
+        for (bound, count) in SAVE_DURATION_BUCKETS.iter().zip(histogram.buckets) {
+            let _ = writeln!(out, "editor_save_duration_seconds_bucket{{le=\"{}\"}} {}", bound, count);
+        }
+        let _ = writeln!(out, "editor_save_duration_seconds_bucket{{le=\"+Inf\"}} {}", histogram.count);
+        let _ = writeln!(out, "editor_save_duration_seconds_sum {}", histogram.sum);
+        let _ = writeln!(out, "editor_save_duration_seconds_count {}", histogram.count);
+        out
+    }
+}
+
+fn gauge_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+}
+
+fn counter_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    counter_header(out, name, help);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Serves `GET /metrics` over plain HTTP on `address`. Meant for a local
+/// scraper, so requests are handled one at a time.
+pub fn start_metrics_server(address: &str, shared_state: SharedServerState) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(address)?;
+    info!(address, "Metrics endpoint listening");
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| serve_request(stream, &shared_state));
+            if let Err(e) = result {
+                debug!(error = %e, "Metrics request failed");
+            }
+        }
+        warn!("Metrics endpoint stopped");
+    }))
+}
+
+/// Reads the request line and skips the headers, reading at most
+/// `MAX_REQUEST_HEAD` bytes so a client cannot make the server buffer without end.
+fn read_request_line(stream: impl Read) -> io::Result<String> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_HEAD));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not used, but must be consumed up to the blank line.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim() != "" {
+        header.clear();
+    }
+    Ok(request_line)
+}
+
+fn serve_request(mut stream: TcpStream, shared_state: &SharedServerState) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let request_line = read_request_line(stream.try_clone()?)?;
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            ("200 OK", "text/plain; version=0.0.4", METRICS.render(shared_state))
+        }
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not found. Try /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_handler::ServerSharedState;
+    use crate::document::Document;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn state_with(documents: &[(&str, &str)]) -> SharedServerState {
+        Arc::new(Mutex::new(ServerSharedState {
+            documents: documents.iter().map(|(name, content)| (name.to_string(), Document::new(content.to_string()))).collect(),
+            clients: HashMap::new(),
+            sessions: HashMap::new(),
+        }))
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape_label("plain.txt"), "plain.txt");
+        assert_eq!(escape_label("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+    }
+
+    #[test]
+    fn render_uses_the_text_exposition_format() {
+        let metrics = Metrics::new();
+        metrics.connection_accepted();
+        metrics.command_received("UPDATE");
+        metrics.command_received("UPDATE");
+        metrics.broadcast("UPDATE_DOC a.txt", 3);
+        metrics.save_finished(Duration::from_millis(20), true);
+        let out = metrics.render(&state_with(&[("a\"b.txt", "hello")]));
+
+        for line in [
+            "# HELP editor_documents Documents held in memory.",
+            "# TYPE editor_documents gauge",
+            "editor_documents 1",
+            "editor_document_size_bytes{document=\"a\\\"b.txt\"} 5",
+            "editor_sessions{state=\"connected\"} 0",
+            "# TYPE editor_connections_total counter",
+            "editor_connections_total 1",
+            "editor_messages_received_total{command=\"UPDATE\"} 2",
+            "editor_broadcasts_total{message=\"UPDATE_DOC\"} 1",
+            "editor_broadcast_recipients_total{message=\"UPDATE_DOC\"} 3",
+            "# TYPE editor_save_duration_seconds histogram",
+            "editor_save_duration_seconds_bucket{le=\"0.01\"} 0",
+            "editor_save_duration_seconds_bucket{le=\"0.05\"} 1",
+            "editor_save_duration_seconds_bucket{le=\"+Inf\"} 1",
+            "editor_save_duration_seconds_count 1",
+        ] {
+            assert!(out.lines().any(|rendered| rendered == line), "missing {:?} in:\n{}", line, out);
+        }
+        // Every sample line is `<name>[{labels}] <value>`
+        for line in out.lines().filter(|line| !line.starts_with('#')) {
+            let (_, value) = line.rsplit_once(' ').unwrap();
+            assert!(value.parse::<f64>().is_ok(), "bad sample {:?}", line);
+        }
+    }
+
+    #[test]
+    fn request_line_is_read_up_to_the_headers() {
+        let request = "GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        assert_eq!(read_request_line(request.as_bytes()).unwrap(), "GET /metrics HTTP/1.1\r\n");
+    }
+
+    #[test]
+    fn overlong_request_is_cut_off() {
+        let request = format!("GET /{}", "a".repeat(1024 * 1024));
+        let line = read_request_line(request.as_bytes()).unwrap();
+        assert_eq!(line.len() as u64, MAX_REQUEST_HEAD);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::document::Document;
+use editor_protocol::ops::LineEdit;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Longest snippet sent back for a hit, in characters.
+const SNIPPET_LEN: usize = 80;
+/// Characters of context kept before the first matching word in a snippet.
+const SNIPPET_CONTEXT: usize = 20;
+
+/// Identifies a line independently of its position, so inserting or removing
+/// lines does not renumber the rest of the index.
+type LineId = u64;
+
+struct IndexedLine {
+    id: LineId,
+    words: BTreeSet<String>,
+}
+
+/// Inverted index of one document: every word (lowercased) and the lines it
+/// appears on. `Document` keeps it current by applying each edit to it.
+#[derive(Default)]
+pub struct LineIndex {
+    terms: BTreeMap<String, BTreeSet<LineId>>,
+    /// The document's lines in order.
+    lines: Vec<IndexedLine>,
+    next_id: LineId,
+}
+
+impl LineIndex {
+    pub fn build(content: &str) -> Self {
+        let mut index = LineIndex::default();
+        index.replace_lines(0, 0, &content.lines().collect::<Vec<_>>());
+        index
+    }
+
+    /// Updates the index for an edit to the indexed content. Only the lines the
+    /// edit removed or inserted are (re)indexed.
+    pub fn apply(&mut self, edit: &LineEdit) {
+        self.replace_lines(edit.start, edit.removed.len(), &edit.inserted);
+    }
+
+    fn replace_lines<S: AsRef<str>>(&mut self, start: usize, removed: usize, inserted: &[S]) {
+        let start = start.min(self.lines.len());
+        let end = (start + removed).min(self.lines.len());
+        let mut new_lines = Vec::with_capacity(inserted.len());
+        for line in inserted {
+            let id = self.next_id;
+            self.next_id += 1;
+            let words: BTreeSet<String> = words(line.as_ref()).collect();
+            for word in &words {
+                self.terms.entry(word.clone()).or_default().insert(id);
+            }
+            new_lines.push(IndexedLine { id, words });
+        }
+        for old_line in self.lines.splice(start..end, new_lines) {
+            for word in old_line.words {
+                if let Entry::Occupied(mut entry) = self.terms.entry(word) {
+                    entry.get_mut().remove(&old_line.id);
+                    if entry.get().is_empty() {
+                        entry.remove();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lines (0-based) containing every query term. A term also matches the
+    /// words it is a prefix of, so `collab` finds `collaborative`.
+    pub fn matching_lines(&self, query_terms: &[String]) -> BTreeSet<usize> {
+        let mut result: Option<BTreeSet<LineId>> = None;
+        for query_term in query_terms {
+            let lines: BTreeSet<LineId> = self
+                .terms
+                .range(query_term.clone()..)
+                .take_while(|(term, _)| term.starts_with(query_term.as_str()))
+                .flat_map(|(_, lines)| lines.iter().copied())
+                .collect();
+            let narrowed = match result {
+                Some(previous) => previous.intersection(&lines).copied().collect(),
+                None => lines,
+            };
+            if narrowed.is_empty() {
+                return BTreeSet::new();
+            }
+            result = Some(narrowed);
+        }
+        let Some(ids) = result else {
+            return BTreeSet::new();
+        };
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| ids.contains(&line.id))
+            .map(|(position, _)| position)
+            .collect()
+    }
+}
+
+/// Splits text into lowercased words of letters and digits.
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(fold_case)
+}
+
+/// Lowercases a word for comparison. `İ` lowercases to `i` followed by a
+/// combining dot above (U+0307); the dot is dropped so that a query typed
+/// with a plain `i` still matches.
+fn fold_case(word: &str) -> String {
+    word.to_lowercase().replace("i\u{307}", "i")
+}
+
+/// One line that matched a `SEARCH`.
+pub struct SearchHit<'a> {
+    pub document: &'a str,
+    /// 1-based, as shown to users.
+    pub line: usize,
+    pub snippet: String,
+}
+
+/// Finds the lines matching every word of `query` across all documents,
+/// ordered by document name and line, at most `limit` of them.
+pub fn search<'a>(documents: &'a HashMap<String, Document>, query: &str, limit: usize) -> Vec<SearchHit<'a>> {
+    let query_terms: Vec<String> = words(query).collect();
+    if query_terms.is_empty() {
+        return Vec::new();
+    }
+    let mut names: Vec<&String> = documents.keys().collect();
+    names.sort();
+    let mut hits = Vec::new();
+    for name in names {
+        let document = &documents[name];
+        let lines = document.index.matching_lines(&query_terms);
+        if lines.is_empty() {
+            continue;
+        }
+        let content: Vec<&str> = document.content.lines().collect();
+        for line in lines {
+            if hits.len() == limit {
+                return hits;
+            }
+            let text = content.get(line).copied().unwrap_or_default();
+            hits.push(SearchHit { document: name, line: line + 1, snippet: snippet(text, &query_terms) });
+        }
+    }
+    hits
+}
+
+/// The words of `line` (as in `words`, but not lowercased) with the character
+/// offset each one starts at.
+fn words_with_offsets(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    line.split(|c: char| !c.is_alphanumeric())
+        .map(move |word| {
+            let start = offset;
+            // Each piece is followed by exactly one separator character
+            offset += word.chars().count() + 1;
+            (start, word)
+        })
+        .filter(|(_, word)| !word.is_empty())
+}
+
+/// A single-line excerpt of `line` starting a little before the first
+/// matching word. Words are compared one by one, so offsets stay in the
+/// original text even where lowercasing changes a character's length.
+/// Tabs become spaces so the result fits the tab-separated reply.
+fn snippet(line: &str, query_terms: &[String]) -> String {
+    let chars: Vec<char> = line.chars().map(|c| if c == '\t' { ' ' } else { c }).collect();
+    let first_match = words_with_offsets(line)
+        .find(|(_, word)| {
+            let word = fold_case(word);
+            query_terms.iter().any(|term| word.starts_with(term.as_str()))
+        })
+        .map_or(0, |(offset, _)| offset);
+    let start = first_match.saturating_sub(SNIPPET_CONTEXT).min(chars.len());
+    let end = (start + SNIPPET_LEN).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect::<String>().trim().to_string();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(query: &str) -> Vec<String> {
+        words(query).collect()
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn lines_must_contain_every_term_or_a_word_it_prefixes() {
+        let index = LineIndex::build("Collaborative editing\nediting alone\n\nCOLLAB notes, editing");
+        assert_eq!(index.matching_lines(&terms("collab")), BTreeSet::from([0, 3]));
+        assert_eq!(index.matching_lines(&terms("collab edit")), BTreeSet::from([0, 3]));
+        assert_eq!(index.matching_lines(&terms("alone collab")), BTreeSet::new());
+        assert_eq!(index.matching_lines(&[]), BTreeSet::new());
+    }
+
+    #[test]
+    fn applied_edits_match_a_rebuilt_index() {
+        let mut content = lines("bir iki\nüç\ndört bir\nbeş");
+        let mut index = LineIndex::build(&content.join("\n"));
+        let steps = [
+            lines("bir iki\nyeni satır\nüç\ndört bir\nbeş"),
+            lines("yeni satır\nüç\ndört bir\nbeş"),
+            lines("yeni satır\nüç bir\nbeş\nbir"),
+            Vec::new(),
+            lines("bir"),
+        ];
+        for next in steps {
+            let edit = LineEdit::between(&content, &next).unwrap();
+            index.apply(&edit);
+            content = next;
+            let rebuilt = LineIndex::build(&content.join("\n"));
+            for query in ["bir", "iki", "üç", "yeni satır", "beş", "d"] {
+                assert_eq!(index.matching_lines(&terms(query)), rebuilt.matching_lines(&terms(query)), "{:?}", query);
+            }
+            assert_eq!(index.terms.keys().collect::<Vec<_>>(), rebuilt.terms.keys().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn snippet_starts_near_the_first_match() {
+        let line = format!("{} hedef kelime", "x".repeat(40));
+        assert_eq!(snippet(&line, &terms("kelime")), format!("…{} hedef kelime", "x".repeat(13)));
+        assert_eq!(snippet("kısa\tsatır", &terms("satır")), "kısa satır");
+        let long = format!("hedef {}", "y".repeat(100));
+        assert_eq!(snippet(&long, &terms("hedef")).chars().count(), SNIPPET_LEN + 1);
+    }
+
+    #[test]
+    fn snippet_offsets_survive_case_folding() {
+        // `İ` lowercases to two characters; the match must still be found in
+        // the original text.
+        let line = format!("{} hedef", "İ".repeat(30));
+        assert_eq!(snippet(&line, &terms("hedef")), format!("…{} hedef", "İ".repeat(19)));
+        assert_eq!(snippet("İSTANBUL hedef", &terms("istanbul hedef")), "İSTANBUL hedef");
+    }
+
+    #[test]
+    fn dotted_capital_i_matches_a_plain_i() {
+        let index = LineIndex::build("İSTANBUL\nizmir\nAnkara");
+        assert_eq!(index.matching_lines(&terms("ist")), BTreeSet::from([0]));
+        assert_eq!(index.matching_lines(&terms("İst")), BTreeSet::from([0]));
+        assert_eq!(index.matching_lines(&terms("i")), BTreeSet::from([0, 1]));
+        assert_eq!(snippet(&format!("{} İzmir", "x".repeat(30)), &terms("izm")), format!("…{} İzmir", "x".repeat(19)));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::storage::{unix_timestamp, DocumentStore, Revision};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::io;
+use std::sync::Mutex;
+use tracing::{debug, info};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS documents (
+        name       TEXT PRIMARY KEY,
+        content    TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS revisions (
+        document  TEXT NOT NULL REFERENCES documents(name) ON DELETE CASCADE,
+        number    INTEGER NOT NULL,
+        author    TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        content   TEXT NOT NULL,
+        PRIMARY KEY (document, number)
+    );
+";
+
+/// Keeps documents and their revisions in an embedded SQLite database, so a
+/// save and its revision row are written in a single transaction.
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+    /// Revisions kept per document; 0 keeps all of them.
+    history_limit: usize,
+}
+
+impl SqliteStore {
+    /// Opens (and creates if needed) the database file.
+    pub fn open(path: &str, history_limit: usize) -> io::Result<Self> {
+        let connection = Connection::open(path).map_err(to_io_error)?;
+        connection
+            .execute_batch("PRAGMA foreign_keys = ON;")
+            .and_then(|_| connection.execute_batch(SCHEMA))
+            .map_err(to_io_error)?;
+        info!(path, "Opened SQLite document store");
+        Ok(SqliteStore { connection: Mutex::new(connection), history_limit })
+    }
+}
+
+fn to_io_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+impl DocumentStore for SqliteStore {
+    fn list(&self) -> io::Result<Vec<String>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare("SELECT name FROM documents ORDER BY name").map_err(to_io_error)?;
+        let names = statement
+            .query_map([], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(to_io_error)?;
+        Ok(names)
+    }
+
+    fn load(&self, name: &str) -> io::Result<String> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .query_row("SELECT content FROM documents WHERE name = ?1", [name], |row| row.get(0))
+            .optional()
+            .map_err(to_io_error)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("document '{}' not found", name)))
+    }
+
+    fn save(&self, name: &str, content: &str, author: &str) -> io::Result<u64> {
+        let mut connection = self.connection.lock().unwrap();
+        let timestamp = unix_timestamp();
+        let transaction = connection.transaction().map_err(to_io_error)?;
+        transaction
+            .execute(
+                "INSERT INTO documents (name, content, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(name) DO UPDATE SET content = excluded.content, updated_at = excluded.updated_at",
+                params![name, content, timestamp],
+            )
+            .map_err(to_io_error)?;
+        let number: u64 = transaction
+            .query_row(
+                "SELECT COALESCE(MAX(number), 0) + 1 FROM revisions WHERE document = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .map_err(to_io_error)?;
+        transaction
+            .execute(
+                "INSERT INTO revisions (document, number, author, timestamp, content) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![name, number, author, timestamp, content],
+            )
+            .map_err(to_io_error)?;
+        if self.history_limit > 0 {
+            transaction
+                .execute(
+                    "DELETE FROM revisions WHERE document = ?1 AND number <= ?2",
+                    params![name, number.saturating_sub(self.history_limit as u64)],
+                )
+                .map_err(to_io_error)?;
+        }
+        transaction.commit().map_err(to_io_error)?;
+        debug!(document = name, revision = number, "Document saved");
+        Ok(number)
+    }
+
+    fn delete(&self, name: &str) -> io::Result<()> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute("DELETE FROM documents WHERE name = ?1", [name]).map_err(to_io_error)?;
+        Ok(())
+    }
+
+    fn history(&self, name: &str) -> io::Result<Vec<Revision>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare("SELECT number, author, timestamp FROM revisions WHERE document = ?1 ORDER BY number")
+            .map_err(to_io_error)?;
+        let revisions = statement
+            .query_map([name], |row| {
+                Ok(Revision {
+                    number: row.get(0)?,
+                    author: row.get(1)?,
+                    timestamp: row.get(2)?,
+                })
+            })
+            .and_then(|rows| rows.collect())
+            .map_err(to_io_error)?;
+        Ok(revisions)
+    }
+
+    fn load_revision(&self, name: &str, number: u64) -> io::Result<String> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .query_row(
+                "SELECT content FROM revisions WHERE document = ?1 AND number = ?2",
+                params![name, number],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(to_io_error)?
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("revision {} of '{}' not found", number, name))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_lists_metadata_and_prunes_beyond_the_limit() {
+        let store = SqliteStore::open(":memory:", 2).unwrap();
+        for content in ["one", "two", "three"] {
+            store.save("a.txt", content, "ayse").unwrap();
+        }
+        let numbers: Vec<u64> = store.history("a.txt").unwrap().iter().map(|revision| revision.number).collect();
+        assert_eq!(numbers, [2, 3]);
+        assert!(store.load_revision("a.txt", 1).is_err());
+        assert_eq!(store.load_revision("a.txt", 3).unwrap(), "three");
+        assert_eq!(store.load("a.txt").unwrap(), "three");
+    }
+
+    #[test]
+    fn delete_cascades_to_revisions() {
+        let store = SqliteStore::open(":memory:", 0).unwrap();
+        store.save("a.txt", "one", "ayse").unwrap();
+        store.delete("a.txt").unwrap();
+        assert!(store.list().unwrap().is_empty());
+        assert!(store.history("a.txt").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+/// Directory (inside the documents directory) holding revision snapshots.
+const HISTORY_DIR: &str = ".history";
+
+/// File in each document's history directory listing its revisions, one
+/// `<number>\t<timestamp>\t<author>` line each, so the history can be
+/// listed without opening every snapshot.
+const HISTORY_INDEX: &str = "index";
+
+/// Metadata of a stored document snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    pub number: u64,
+    pub author: String,
+    pub timestamp: u64,
+}
+
+/// Persistence backend for documents and their revision history.
+pub trait DocumentStore: Send + Sync {
+    /// Names of all stored documents.
+    fn list(&self) -> io::Result<Vec<String>>;
+    /// Current content of a document.
+    fn load(&self, name: &str) -> io::Result<String>;
+    /// Replaces the content of a document and records it as a new revision.
+    /// Returns the number of the new revision.
+    fn save(&self, name: &str, content: &str, author: &str) -> io::Result<u64>;
+    /// Removes a document and its history.
+    fn delete(&self, name: &str) -> io::Result<()>;
+    /// All recorded revisions of a document, oldest first.
+    fn history(&self, name: &str) -> io::Result<Vec<Revision>>;
+    /// Content of a document as it was at the given revision.
+    fn load_revision(&self, name: &str, number: u64) -> io::Result<String>;
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Stores each document as a plain file named after it, with revisions as
+/// numbered snapshot files under `.history/<document>/`.
+pub struct FileStore {
+    dir: PathBuf,
+    /// Only files with these extensions are listed as documents.
+    extensions: Vec<String>,
+    /// Revisions kept per document; 0 keeps all of them.
+    history_limit: usize,
+}
+
+impl FileStore {
+    /// Opens (and creates if needed) the documents directory.
+    pub fn open(dir: &str, extensions: Vec<String>, history_limit: usize) -> io::Result<Self> {
+        let dir = PathBuf::from(dir);
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+            info!(path = %dir.display(), "Document directory created");
+        }
+        Ok(FileStore { dir, extensions, history_limit })
+    }
+
+    fn document_path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    fn history_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(HISTORY_DIR).join(name)
+    }
+
+    fn index_path(&self, name: &str) -> PathBuf {
+        self.history_dir(name).join(HISTORY_INDEX)
+    }
+
+    /// Revisions of a document from its index, oldest first. Histories written
+    /// before the index existed are indexed on first use.
+    fn read_index(&self, name: &str) -> io::Result<Vec<Revision>> {
+        let raw = match fs::read_to_string(self.index_path(name)) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let revisions = self.scan_revisions(name)?;
+                if !revisions.is_empty() {
+                    self.write_index(name, &revisions)?;
+                }
+                return Ok(revisions);
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(raw
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                Some(Revision {
+                    number: fields.next()?.parse().ok()?,
+                    timestamp: fields.next()?.parse().unwrap_or(0),
+                    author: fields.next().unwrap_or("").to_string(),
+                })
+            })
+            .collect())
+    }
+
+    /// Rebuilds the revision list from the snapshot files, reading only their header lines.
+    fn scan_revisions(&self, name: &str) -> io::Result<Vec<Revision>> {
+        let history_dir = self.history_dir(name);
+        if !history_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut numbers: Vec<u64> = fs::read_dir(history_dir)?
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "rev") {
+                    path.file_stem()?.to_str()?.parse().ok()
+                } else {
+                    None
+                }
+            })
+            .collect();
+        numbers.sort_unstable();
+        numbers
+            .into_iter()
+            .map(|number| {
+                let mut header = String::new();
+                BufReader::new(File::open(self.revision_path(name, number))?).read_line(&mut header)?;
+                Ok(parse_revision_header(number, header.trim_end_matches('\n')))
+            })
+            .collect()
+    }
+
+    /// Replaces the index; written to a temporary file first so a crash leaves the old one intact.
+    fn write_index(&self, name: &str, revisions: &[Revision]) -> io::Result<()> {
+        let path = self.index_path(name);
+        let temp_path = path.with_extension("tmp");
+        let mut file = File::create(&temp_path)?;
+        for revision in revisions {
+            writeln!(file, "{}\t{}\t{}", revision.number, revision.timestamp, revision.author)?;
+        }
+        file.sync_all()?;
+        fs::rename(temp_path, path)
+    }
+
+    /// Drops the oldest snapshots beyond `history_limit`.
+    fn prune_history(&self, name: &str, mut revisions: Vec<Revision>) -> io::Result<()> {
+        if self.history_limit == 0 || revisions.len() <= self.history_limit {
+            return Ok(());
+        }
+        let removed: Vec<Revision> = revisions.drain(..revisions.len() - self.history_limit).collect();
+        // The index goes first: a snapshot missing from it is harmless, an
+        // indexed one without its file is not.
+        self.write_index(name, &revisions)?;
+        for revision in &removed {
+            match fs::remove_file(self.revision_path(name, revision.number)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        debug!(document = name, pruned = removed.len(), "Old revisions pruned");
+        Ok(())
+    }
+
+    fn revision_path(&self, name: &str, number: u64) -> PathBuf {
+        self.history_dir(name).join(format!("{}.rev", number))
+    }
+
+    /// Revision files start with a `<timestamp>\t<author>` header line, followed by the content.
+    /// The snapshot is written before its index line, so the index never
+    /// lists a revision that is not on disk.
+    fn write_revision(&self, name: &str, revision: &Revision, content: &str) -> io::Result<()> {
+        fs::create_dir_all(self.history_dir(name))?;
+        let mut file = File::create(self.revision_path(name, revision.number))?;
+        writeln!(file, "{}\t{}", revision.timestamp, revision.author)?;
+        file.write_all(content.as_bytes())?;
+        let mut index = OpenOptions::new().create(true).append(true).open(self.index_path(name))?;
+        writeln!(index, "{}\t{}\t{}", revision.number, revision.timestamp, revision.author)
+    }
+
+    fn read_revision(&self, name: &str, number: u64) -> io::Result<(Revision, String)> {
+        let raw = fs::read_to_string(self.revision_path(name, number))?;
+        let (header, content) = raw.split_once('\n').unwrap_or((raw.as_str(), ""));
+        Ok((parse_revision_header(number, header), content.to_string()))
+    }
+}
+
+/// Refuses names that would resolve outside the documents directory. Callers
+/// validate names too; this keeps the store safe on its own.
+fn check_name(name: &str) -> io::Result<()> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid document name {:?}", name)));
+    }
+    Ok(())
+}
+
+fn parse_revision_header(number: u64, header: &str) -> Revision {
+    let (timestamp, author) = header.split_once('\t').unwrap_or((header, ""));
+    Revision { number, author: author.to_string(), timestamp: timestamp.parse().unwrap_or(0) }
+}
+
+impl DocumentStore for FileStore {
+    fn list(&self) -> io::Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            let path = entry.path();
+            let allowed = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| self.extensions.iter().any(|allowed| allowed.eq_ignore_ascii_case(ext)));
+            if path.is_file() && allowed {
+                if let Some(doc_name) = path.file_name().and_then(|name| name.to_str()) {
+                    names.push(doc_name.to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    fn load(&self, name: &str) -> io::Result<String> {
+        check_name(name)?;
+        fs::read_to_string(self.document_path(name))
+    }
+
+    fn save(&self, name: &str, content: &str, author: &str) -> io::Result<u64> {
+        check_name(name)?;
+        let path = self.document_path(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = File::create(path)?;
+        file.write_all(content.as_bytes())?;
+
+        let mut revisions = self.read_index(name)?;
+        let number = revisions.last().map_or(1, |last| last.number + 1);
+        let revision = Revision { number, author: author.to_string(), timestamp: unix_timestamp() };
+        self.write_revision(name, &revision, content)?;
+        revisions.push(revision);
+        self.prune_history(name, revisions)?;
+        debug!(document = name, revision = number, "Document saved");
+        Ok(number)
+    }
+
+    fn delete(&self, name: &str) -> io::Result<()> {
+        check_name(name)?;
+        let path = self.document_path(name);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        let history_dir = self.history_dir(name);
+        if history_dir.exists() {
+            fs::remove_dir_all(history_dir)?;
+        }
+        Ok(())
+    }
+
+    fn history(&self, name: &str) -> io::Result<Vec<Revision>> {
+        check_name(name)?;
+        self.read_index(name)
+    }
+
+    fn load_revision(&self, name: &str, number: u64) -> io::Result<String> {
+        check_name(name)?;
+        self.read_revision(name, number).map(|(_, content)| content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh documents directory under the system temp dir, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(test: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("editor_store_{}_{}", std::process::id(), test));
+            let _ = fs::remove_dir_all(&path);
+            TempDir(path)
+        }
+
+        fn store(&self, history_limit: usize) -> FileStore {
+            FileStore::open(self.0.to_str().unwrap(), vec!["txt".to_string()], history_limit).unwrap()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn numbers(revisions: &[Revision]) -> Vec<u64> {
+        revisions.iter().map(|revision| revision.number).collect()
+    }
+
+    #[test]
+    fn save_records_numbered_revisions() {
+        let dir = TempDir::new("numbered");
+        let store = dir.store(0);
+        assert_eq!(store.save("a.txt", "one", "ayse").unwrap(), 1);
+        assert_eq!(store.save("a.txt", "two\nlines", "mehmet,ayse").unwrap(), 2);
+
+        let history = store.history("a.txt").unwrap();
+        assert_eq!(numbers(&history), [1, 2]);
+        assert_eq!(history[1].author, "mehmet,ayse");
+        assert_eq!(store.load("a.txt").unwrap(), "two\nlines");
+        assert_eq!(store.load_revision("a.txt", 1).unwrap(), "one");
+        assert_eq!(store.list().unwrap(), ["a.txt"]);
+    }
+
+    #[test]
+    fn history_is_read_from_the_index_alone() {
+        let dir = TempDir::new("index_only");
+        let store = dir.store(0);
+        store.save("a.txt", "one", "ayse").unwrap();
+        // A snapshot the index knows about is not opened to list the history
+        fs::write(store.revision_path("a.txt", 1), "garbage").unwrap();
+        assert_eq!(store.history("a.txt").unwrap()[0].author, "ayse");
+    }
+
+    #[test]
+    fn history_without_an_index_is_rebuilt_from_snapshot_headers() {
+        let dir = TempDir::new("legacy");
+        let store = dir.store(0);
+        store.save("a.txt", "one", "ayse").unwrap();
+        store.save("a.txt", "two", "mehmet").unwrap();
+        fs::remove_file(store.index_path("a.txt")).unwrap();
+
+        let history = store.history("a.txt").unwrap();
+        assert_eq!(numbers(&history), [1, 2]);
+        assert_eq!(history[1].author, "mehmet");
+        assert!(store.index_path("a.txt").exists());
+        assert_eq!(store.save("a.txt", "three", "ayse").unwrap(), 3);
+    }
+
+    #[test]
+    fn old_revisions_are_pruned_beyond_the_limit() {
+        let dir = TempDir::new("pruned");
+        let store = dir.store(2);
+        for content in ["one", "two", "three", "four"] {
+            store.save("a.txt", content, "ayse").unwrap();
+        }
+        assert_eq!(numbers(&store.history("a.txt").unwrap()), [3, 4]);
+        assert!(store.load_revision("a.txt", 1).is_err());
+        assert_eq!(store.load_revision("a.txt", 3).unwrap(), "three");
+        // Numbers keep counting up after pruning
+        assert_eq!(store.save("a.txt", "five", "ayse").unwrap(), 5);
+    }
+
+    #[test]
+    fn delete_removes_the_document_and_its_history() {
+        let dir = TempDir::new("delete");
+        let store = dir.store(0);
+        store.save("a.txt", "one", "ayse").unwrap();
+        store.delete("a.txt").unwrap();
+        assert!(store.list().unwrap().is_empty());
+        assert!(store.history("a.txt").unwrap().is_empty());
+    }
+
+    #[test]
+    fn names_outside_the_directory_are_refused() {
+        let dir = TempDir::new("escape");
+        let store = dir.store(0);
+        for name in ["../x", "../../x.txt", "a/b.txt", "a\\b.txt", "..", ""] {
+            assert_eq!(store.history(name).unwrap_err().kind(), io::ErrorKind::InvalidInput, "{:?}", name);
+            assert!(store.load_revision(name, 1).is_err());
+            assert!(store.save(name, "x", "ayse").is_err());
+        }
+        assert!(!dir.0.join(HISTORY_DIR).exists());
+    }
+}